@@ -11,10 +11,13 @@ glib::wrapper! {
 impl BookDetailsWindow {
     pub fn new(book: &Book, user_view: UserView) -> Self {
         glib::Object::builder()
-            .property("book-id", book.book_id)
+            .property("book-id", book.book_id.0)
             .property("title", &book.title)
             .property("author-name", &book.author.name)
-            .property("author-date-of-birth", book.author.date_of_birth)
+            .property(
+                "author-date-of-birth",
+                glib::BoxedAnyObject::new(book.author.date_of_birth),
+            )
             .property(
                 "author-date-of-death",
                 book.author.date_of_death.map(glib::BoxedAnyObject::new),
@@ -22,7 +25,7 @@ impl BookDetailsWindow {
             .property("author-description", &book.author.description)
             .property(
                 "publish-date",
-                time::format_date(&time::date_from(book.publish_date)),
+                time::format_partial_date(&book.publish_date),
             )
             .property("publisher", &book.publisher)
             .property("count", &book.count.to_string())
@@ -40,11 +43,20 @@ mod imp {
 
     use adw::{prelude::*, subclass::prelude::*};
     use gtk::{
-        glib::{self, g_warning, gformat, GString, WeakRef},
+        gio,
+        glib::{self, g_warning, gformat, BoxedAnyObject, GString, WeakRef},
         CompositeTemplate,
     };
+    use schema::{
+        dates::PartialDate,
+        endpoints::{AddToReadingList, Endpoint, ReadingLists, SimilarBooks},
+        ids::BookId,
+        lists::{
+            CreateReadingListRequest, ReadingList, ReadingListEntryRequest, ReadingListsRequest,
+        },
+    };
 
-    use crate::{time, user_view::UserView};
+    use crate::{time, user_view::UserView, widget_ext::WidgetUtilsExt, window::ShowToastExt};
 
     #[derive(Default, Debug, CompositeTemplate, glib::Properties)]
     #[properties(wrapper_type = super::BookDetailsWindow)]
@@ -57,7 +69,7 @@ mod imp {
         #[property(get, set)]
         author_name: RefCell<GString>,
         #[property(get, set)]
-        author_date_of_birth: RefCell<i64>,
+        author_date_of_birth: RefCell<Option<glib::Object>>,
         #[property(get = Self::format_date_of_birth)]
         author_date_of_birth_string: PhantomData<GString>,
         #[property(get, set = Self::set_author_date_of_death)]
@@ -78,6 +90,19 @@ mod imp {
         can_be_borrowed: Cell<bool>,
         #[property(get, set)]
         user_view: WeakRef<UserView>,
+
+        #[template_child]
+        similar_books_section: TemplateChild<gtk::Box>,
+        #[template_child]
+        similar_books_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        reading_lists: TemplateChild<gio::ListStore>,
+        #[template_child]
+        reading_lists_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        new_list_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        new_list_is_public: TemplateChild<adw::SwitchRow>,
     }
 
     #[glib::object_subclass]
@@ -104,8 +129,20 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl BookDetailsWindow {
+        fn book_id(&self) -> BookId {
+            BookId(self.obj().book_id())
+        }
+
+        fn date_of_birth(&self) -> PartialDate {
+            self.obj()
+                .author_date_of_birth()
+                .and_downcast::<glib::BoxedAnyObject>()
+                .map(|obj| *obj.borrow::<PartialDate>())
+                .unwrap_or_default()
+        }
+
         fn format_date_of_birth(&self) -> GString {
-            let birth = time::format_date(&time::date_from(self.obj().author_date_of_birth()));
+            let birth = time::format_partial_date(&self.date_of_birth());
 
             gformat!("Data nașterii: {birth}")
         }
@@ -118,19 +155,20 @@ mod imp {
         }
 
         fn format_date_of_death(&self) -> GString {
-            let birth = time::date_from(self.obj().author_date_of_birth());
             let Some(death) = self
                 .obj()
                 .author_date_of_death()
                 .and_downcast::<glib::BoxedAnyObject>()
-                .map(|obj| *obj.borrow::<i64>())
+                .map(|obj| *obj.borrow::<PartialDate>())
             else {
                 return GString::default();
             };
-            let death = time::date_from(death);
-            let years = death.difference(&birth).as_days() / 365;
+            let years = time::years_between(&self.date_of_birth(), &death);
 
-            gformat!("Data morții: {} ({years} ani)", time::format_date(&death))
+            gformat!(
+                "Data morții: {} ({years} ani)",
+                time::format_partial_date(&death)
+            )
         }
 
         fn is_author_dead(&self) -> bool {
@@ -156,7 +194,150 @@ mod imp {
             };
 
             self.obj().close();
-            user_view.borrow_book(self.obj().book_id()).await;
+            user_view.borrow_book(self.book_id()).await;
+        }
+
+        #[template_callback]
+        async fn on_show(&self) {
+            self.refresh_similar_books().await;
+            self.refresh_reading_lists().await;
+        }
+
+        async fn refresh_similar_books(&self) {
+            let Some(user_view) = self.user_view.upgrade() else {
+                g_warning!("biblioteca", "Failed to upgrade user_view, parent closed?");
+                return;
+            };
+
+            let request = SimilarBooks {
+                book_id: self.book_id(),
+            };
+            let similar_books = match user_view.soup_session().call(&request).await {
+                Ok(similar_books) => similar_books,
+                Err(err) => {
+                    g_warning!("biblioteca", "Error on GET to {}: {}", request.path(), err);
+                    return;
+                }
+            };
+
+            self.similar_books_box.dispose_children();
+            for similar_book in similar_books {
+                let button = gtk::Button::builder()
+                    .label(&similar_book.title)
+                    .tooltip_text(&similar_book.author_name)
+                    .build();
+                button.connect_clicked({
+                    let user_view = user_view.clone();
+                    let book_id = similar_book.book_id;
+                    move |_| user_view.show_book_details(book_id)
+                });
+
+                self.similar_books_box.append(&button);
+            }
+
+            self.similar_books_section
+                .set_visible(self.similar_books_box.first_child().is_some());
+        }
+
+        async fn refresh_reading_lists(&self) {
+            let Some(user_view) = self.user_view.upgrade() else {
+                g_warning!("biblioteca", "Failed to upgrade user_view, parent closed?");
+                return;
+            };
+            let cookie = user_view.session_cookie().unwrap();
+
+            let request = ReadingLists {
+                user_id: cookie.user_id(),
+                request: ReadingListsRequest {
+                    cookie: cookie.cookie().clone(),
+                },
+            };
+            let lists = user_view.soup_session().call(&request).await;
+
+            match lists {
+                Ok(lists) => {
+                    self.reading_lists.remove_all();
+                    let lists = lists
+                        .into_iter()
+                        .map(BoxedAnyObject::new)
+                        .collect::<Vec<_>>();
+                    self.reading_lists.extend_from_slice(&lists);
+                }
+                Err(err) => {
+                    self.obj()
+                        .show_toast_msg("Nu s-au putut obține listele de lectură");
+                    g_warning!("biblioteca", "Failed to fetch reading lists: {err}");
+                }
+            }
+        }
+
+        #[template_callback(function)]
+        fn reading_list_name(object: Option<BoxedAnyObject>) -> String {
+            object
+                .map(|obj| obj.borrow::<ReadingList>().name.clone())
+                .unwrap_or_default()
+        }
+
+        #[template_callback]
+        async fn on_add_to_list_clicked(&self, button: gtk::Button) {
+            let Some(user_view) = self.user_view.upgrade() else {
+                g_warning!("biblioteca", "Failed to upgrade user_view, parent closed?");
+                return;
+            };
+            let soup = user_view.soup_session();
+            let cookie = user_view.session_cookie().unwrap().cookie().clone();
+
+            let new_list_name = self.new_list_entry.text();
+            let list_id = if !new_list_name.is_empty() {
+                let request = CreateReadingListRequest {
+                    name: new_list_name.to_string(),
+                    is_public: self.new_list_is_public.is_active(),
+                    cookie: cookie.clone(),
+                };
+
+                match soup.call(&request).await {
+                    Ok(list) => {
+                        let list_id = list.list_id;
+                        self.reading_lists.append(&BoxedAnyObject::new(list));
+                        self.reading_lists_row
+                            .set_selected(self.reading_lists.n_items() - 1);
+                        self.new_list_entry.set_text("");
+                        list_id
+                    }
+                    Err(err) => {
+                        button.show_toast_msg(
+                            err.user_message().unwrap_or("Nu s-a putut crea lista"),
+                        );
+                        g_warning!("biblioteca", "Failed to create reading list: {err}");
+                        return;
+                    }
+                }
+            } else {
+                let Some(list_id) = self
+                    .reading_lists
+                    .item(self.reading_lists_row.selected())
+                    .and_downcast::<BoxedAnyObject>()
+                    .map(|obj| obj.borrow::<ReadingList>().list_id)
+                else {
+                    button.show_toast_msg("Trebuie să alegi o listă");
+                    return;
+                };
+                list_id
+            };
+
+            let request = AddToReadingList {
+                list_id,
+                request: ReadingListEntryRequest {
+                    book_id: self.book_id(),
+                    cookie,
+                },
+            };
+            if let Err(err) = soup.call(&request).await {
+                button.show_toast_msg("Nu s-a putut adăuga cartea în listă");
+                g_warning!("biblioteca", "Error on POST to {}: {}", request.path(), err);
+            } else {
+                button.show_toast_msg("Cartea a fost adăugată în listă");
+            }
         }
     }
 }
@@ -0,0 +1,186 @@
+use adw::glib;
+
+use crate::librarian_view::LibrarianView;
+
+glib::wrapper! {
+    pub struct CheckoutDialog(ObjectSubclass<imp::CheckoutDialog>)
+    @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl CheckoutDialog {
+    pub fn new(librarian_view: LibrarianView) -> Self {
+        glib::Object::builder()
+            .property("librarian-view", librarian_view)
+            .build()
+    }
+}
+
+mod imp {
+    use adw::{prelude::*, subclass::prelude::*};
+    use gtk::{
+        gio,
+        glib::{self, g_warning, BoxedAnyObject, WeakRef},
+        CompositeTemplate,
+    };
+    use schema::{
+        auth::{GetAllUsersRequest, User},
+        books::{Book, CheckoutReply, CheckoutRequest},
+        endpoints::Books,
+    };
+
+    use crate::{librarian_view::LibrarianView, window::ShowToastExt};
+
+    #[derive(Default, Debug, CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::CheckoutDialog)]
+    #[template(file = "src/checkout_dialog.blp")]
+    pub struct CheckoutDialog {
+        #[property(get, set, construct_only)]
+        librarian_view: WeakRef<LibrarianView>,
+
+        #[template_child]
+        users_dropdown: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        books_dropdown: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        users: TemplateChild<gio::ListStore>,
+        #[template_child]
+        books: TemplateChild<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CheckoutDialog {
+        const NAME: &'static str = "LibCheckoutDialog";
+        type Type = super::CheckoutDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for CheckoutDialog {}
+    impl WidgetImpl for CheckoutDialog {}
+    impl WindowImpl for CheckoutDialog {}
+    impl AdwWindowImpl for CheckoutDialog {}
+
+    #[gtk::template_callbacks]
+    impl CheckoutDialog {
+        #[template_callback]
+        async fn on_show(&self) {
+            let Some(librarian_view) = self.librarian_view.upgrade() else {
+                g_warning!("biblioteca", "Failed to upgrade librarian_view");
+                return;
+            };
+            let soup = librarian_view.soup_session();
+
+            let request = GetAllUsersRequest {
+                cookie: librarian_view.session_cookie().unwrap().cookie().clone(),
+            };
+            match soup.call(&request).await {
+                Ok(users) => {
+                    self.users.remove_all();
+                    let users = users
+                        .into_iter()
+                        .map(BoxedAnyObject::new)
+                        .collect::<Vec<_>>();
+                    self.users.extend_from_slice(&users);
+                }
+                Err(err) => {
+                    self.obj()
+                        .show_toast_msg("Nu s-a putut prelua lista de cititori");
+                    g_warning!("biblioteca", "Failed to fetch users: {err}");
+                }
+            }
+
+            match soup.call(&Books).await {
+                Ok(books) => {
+                    self.books.remove_all();
+                    let books = books
+                        .into_iter()
+                        .map(BoxedAnyObject::new)
+                        .collect::<Vec<_>>();
+                    self.books.extend_from_slice(&books);
+                }
+                Err(err) => {
+                    self.obj()
+                        .show_toast_msg("Nu s-a putut prelua lista de cărți");
+                    g_warning!("biblioteca", "Failed to fetch books: {err}");
+                }
+            }
+        }
+
+        #[template_callback(function)]
+        fn user_name(object: Option<BoxedAnyObject>) -> String {
+            object
+                .map(|obj| obj.borrow::<User>().name.clone())
+                .unwrap_or_default()
+        }
+
+        #[template_callback(function)]
+        fn book_title(object: Option<BoxedAnyObject>) -> String {
+            object
+                .map(|obj| {
+                    let book = obj.borrow::<Book>();
+                    format!("{} ({})", book.title, book.author.name)
+                })
+                .unwrap_or_default()
+        }
+
+        #[template_callback]
+        async fn on_checkout_clicked(&self, button: gtk::Button) {
+            let Some(librarian_view) = self.librarian_view.upgrade() else {
+                g_warning!("biblioteca", "Failed to upgrade librarian_view");
+                return;
+            };
+
+            let Some(user_id) = self
+                .users
+                .item(self.users_dropdown.selected())
+                .and_downcast::<BoxedAnyObject>()
+                .map(|obj| obj.borrow::<User>().id)
+            else {
+                button.show_toast_msg("Trebuie să alegi un cititor");
+                return;
+            };
+            let Some(book_id) = self
+                .books
+                .item(self.books_dropdown.selected())
+                .and_downcast::<BoxedAnyObject>()
+                .map(|obj| obj.borrow::<Book>().book_id)
+            else {
+                button.show_toast_msg("Trebuie să alegi o carte");
+                return;
+            };
+
+            let request = CheckoutRequest {
+                user_id,
+                book_id,
+                cookie: librarian_view.session_cookie().unwrap().cookie().clone(),
+            };
+            let reply = librarian_view.soup_session().call(&request).await;
+
+            match reply {
+                Ok(CheckoutReply::Ok) => {
+                    self.obj().close();
+                    librarian_view.refresh_borrows().await;
+                }
+                Ok(CheckoutReply::AlreadyBorrowed) => {
+                    button.show_toast_msg("Cititorul are deja această carte împrumutată");
+                }
+                Ok(CheckoutReply::NoCopiesLeft) => {
+                    button.show_toast_msg("Nu mai sunt copii disponibile din această carte");
+                }
+                Err(err) => {
+                    g_warning!("biblioteca", "Failed request to /checkout: {err}");
+                    button.show_toast_msg("Împrumutul nu a putut fi înregistrat");
+                }
+            }
+        }
+    }
+}
@@ -21,7 +21,7 @@ mod imp {
         glib::{self, g_warning, WeakRef},
         CompositeTemplate,
     };
-    use schema::books::ChangeAuthorDetailsRequest;
+    use schema::{books::ChangeAuthorDetailsRequest, dates::PartialDate};
 
     use crate::{librarian_view::LibrarianView, time, window::ShowToastExt};
 
@@ -43,6 +43,8 @@ mod imp {
         birth_month_entry: TemplateChild<adw::ComboRow>,
         #[template_child]
         birth_year_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        birth_circa_entry: TemplateChild<adw::SwitchRow>,
 
         #[template_child]
         death_day_entry: TemplateChild<adw::EntryRow>,
@@ -50,6 +52,8 @@ mod imp {
         death_month_entry: TemplateChild<adw::ComboRow>,
         #[template_child]
         death_year_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        death_circa_entry: TemplateChild<adw::SwitchRow>,
     }
 
     #[glib::object_subclass]
@@ -76,30 +80,30 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl EditAuthorDetailsWindow {
-        fn birth_date(&self, widget: &gtk::Widget) -> Result<i64, ()> {
+        fn birth_date(&self, widget: &gtk::Widget) -> Result<PartialDate, ()> {
             let date = time::date_from_entries(
                 widget,
                 &self.birth_year_entry,
                 &self.birth_month_entry,
                 &self.birth_day_entry,
+                &self.birth_circa_entry,
                 "nașterii",
                 false,
             )?;
 
-            Ok(date.unwrap().to_unix())
+            Ok(date.unwrap())
         }
 
-        fn death_date(&self, widget: &gtk::Widget) -> Result<Option<i64>, ()> {
-            let date = time::date_from_entries(
+        fn death_date(&self, widget: &gtk::Widget) -> Result<Option<PartialDate>, ()> {
+            time::date_from_entries(
                 widget,
                 &self.death_year_entry,
                 &self.death_month_entry,
                 &self.death_day_entry,
+                &self.death_circa_entry,
                 "decesului",
-                false,
-            )?;
-
-            Ok(date.as_ref().map(glib::DateTime::to_unix))
+                true,
+            )
         }
 
         #[template_callback]
@@ -129,10 +133,7 @@ mod imp {
                 cookie: librarian_view.session_cookie().unwrap().cookie().clone(),
             };
 
-            let result = librarian_view
-                .soup_session()
-                .post::<()>(request, "/change-author-details")
-                .await;
+            let result = librarian_view.soup_session().call(&request).await;
 
             if let Err(err) = result {
                 widget.show_toast_msg("Nu s-a putut adăuga autorul");
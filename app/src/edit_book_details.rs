@@ -41,6 +41,18 @@ impl EditBookDetailsWindow {
                     .map(|book| book.synopsis.clone())
                     .unwrap_or_default(),
             )
+            .property(
+                "series",
+                book.as_ref()
+                    .and_then(|book| book.series.clone())
+                    .unwrap_or_default(),
+            )
+            .property(
+                "genres",
+                book.as_ref()
+                    .map(|book| book.genres.join(", "))
+                    .unwrap_or_default(),
+            )
             .property(
                 "publisher",
                 book.as_ref()
@@ -50,8 +62,8 @@ impl EditBookDetailsWindow {
             .property(
                 "publish-date",
                 book.as_ref()
-                    .map(|book| book.publish_date)
-                    .unwrap_or(i64::MAX),
+                    .map(|book| book.publish_date.to_string())
+                    .unwrap_or_default(),
             )
             .property("librarian-view", librarian_view)
             .build()
@@ -67,7 +79,11 @@ mod imp {
         glib::{self, g_warning, BoxedAnyObject, GString, WeakRef},
         CompositeTemplate,
     };
-    use schema::books::{Author, ChangeBookDetailsRequest};
+    use schema::{
+        books::{Author, ChangeBookDetailsRequest},
+        endpoints::Authors,
+        ids::{AuthorId, BookId},
+    };
 
     use crate::{librarian_view::LibrarianView, time, window::ShowToastExt};
 
@@ -94,7 +110,11 @@ mod imp {
         #[property(get, set, construct_only)]
         publisher: RefCell<GString>,
         #[property(get, set, construct_only)]
-        publish_date: RefCell<i64>,
+        publish_date: RefCell<GString>,
+        #[property(get, set, construct_only)]
+        series: RefCell<GString>,
+        #[property(get, set, construct_only)]
+        genres: RefCell<GString>,
 
         #[template_child]
         title_entry: TemplateChild<adw::EntryRow>,
@@ -103,6 +123,10 @@ mod imp {
         #[template_child]
         count_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
+        series_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        genres_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
         authors_dropdown: TemplateChild<adw::ComboRow>,
         #[template_child]
         publisher_entry: TemplateChild<adw::EntryRow>,
@@ -113,6 +137,8 @@ mod imp {
         #[template_child]
         year_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
+        circa_entry: TemplateChild<adw::SwitchRow>,
+        #[template_child]
         authors: TemplateChild<gio::ListStore>,
     }
 
@@ -142,7 +168,7 @@ mod imp {
                 self.authors.append(&BoxedAnyObject::new(Author {
                     author_id: self.author_id(),
                     name: author_name.to_string(),
-                    date_of_birth: 0,
+                    date_of_birth: Default::default(),
                     date_of_death: None,
                     description: "".into(),
                 }));
@@ -154,14 +180,19 @@ mod imp {
 
             self.count_entry.set_text(&obj.count().to_string());
             self.synopsis_entry.set_text(&obj.synopsis());
+            self.series_entry.set_text(&obj.series());
+            self.genres_entry.set_text(&obj.genres());
 
             self.publisher_entry.set_text(&obj.publisher());
 
-            if obj.publish_date() != i64::MAX {
-                let date = time::date_from(obj.publish_date());
-                self.day_entry.set_text(&date.day_of_month().to_string());
-                self.month_entry.set_selected(date.month() as u32 - 1);
-                self.year_entry.set_text(&date.year().to_string());
+            if let Ok(date) = obj.publish_date().parse() {
+                time::set_entries(
+                    &date,
+                    &self.year_entry,
+                    &self.month_entry,
+                    &self.day_entry,
+                    &self.circa_entry,
+                );
             }
         }
     }
@@ -171,7 +202,7 @@ mod imp {
 
     #[gtk::template_callbacks]
     impl EditBookDetailsWindow {
-        fn author_id(&self) -> i64 {
+        fn author_id(&self) -> AuthorId {
             *self
                 .obj()
                 .author_id()
@@ -181,7 +212,7 @@ mod imp {
                 .borrow()
         }
 
-        fn book_id(&self) -> Option<i64> {
+        fn book_id(&self) -> Option<BookId> {
             Some(
                 *self
                     .obj()
@@ -204,7 +235,7 @@ mod imp {
             };
             let soup = librarian_view.soup_session();
 
-            let authors = soup.get::<Vec<Author>>("/authors").await;
+            let authors = soup.call(&Authors).await;
 
             match authors {
                 Ok(authors) => {
@@ -214,7 +245,7 @@ mod imp {
                         .obj()
                         .author_id()
                         .and_downcast::<BoxedAnyObject>()
-                        .map(|obj| *obj.borrow::<i64>());
+                        .map(|obj| *obj.borrow::<AuthorId>());
 
                     let our_author_index = author_id.and_then(|author_id| {
                         authors
@@ -272,44 +303,17 @@ mod imp {
                 button.show_toast_msg("Trebuie să alegi un autor");
                 return;
             };
-            let year = match self.year_entry.text().parse() {
-                Ok(year) => year,
-                Err(err) => {
-                    g_warning!(
-                        "biblioteca",
-                        "Failed to parse {}, err={err}",
-                        self.year_entry.text()
-                    );
-                    button.show_toast_msg("Anul trebuie să fie un număr");
-                    return;
-                }
-            };
-            let month = self.month_entry.selected() as i32 + 1;
-            let day = match self.day_entry.text().parse() {
-                Ok(day) => day,
-                Err(err) => {
-                    g_warning!(
-                        "biblioteca",
-                        "Failed to parse {}, err={err}",
-                        self.day_entry.text()
-                    );
-                    button.show_toast_msg("Ziua trebuie să fie un număr");
-                    return;
-                }
+            let Ok(Some(publish_date)) = time::date_from_entries(
+                button.upcast_ref(),
+                &self.year_entry,
+                &self.month_entry,
+                &self.day_entry,
+                &self.circa_entry,
+                "publicării",
+                false,
+            ) else {
+                return;
             };
-            let publish_date =
-                match glib::DateTime::new(&glib::TimeZone::local(), year, month, day, 0, 0, 0.0) {
-                    Ok(publish_date) => publish_date,
-                    Err(err) => {
-                        g_warning!("biblioteca", "Failed to parse date: {err}");
-                        button.show_toast_msg(&format!(
-                            "Detaliile introduse nu au format o dată validă: {}",
-                            err
-                        ));
-                        return;
-                    }
-                }
-                .to_unix();
             let count = match self.count_entry.text().parse() {
                 Ok(count) => count,
                 Err(_) => {
@@ -325,9 +329,17 @@ mod imp {
                 publisher: self.publisher_entry.text().into(),
                 count,
                 synopsis: self.synopsis_entry.text().into(),
+                series: Some(self.series_entry.text().to_string()),
+                genres: self
+                    .genres_entry
+                    .text()
+                    .split(',')
+                    .map(|genre| genre.trim().to_string())
+                    .filter(|genre| !genre.is_empty())
+                    .collect(),
                 cookie: librarian_view.session_cookie().unwrap().cookie().clone(),
             };
-            if let Err(err) = soup.post::<()>(request, "/change-book-details").await {
+            if let Err(err) = soup.call(&request).await {
                 g_warning!(
                     "biblioteca",
                     "Failed request to /change-book-details: {}",
@@ -1,16 +1,61 @@
-use std::mem;
+use std::{cell::RefCell, collections::HashMap, future::Future, mem};
 
-use gtk::glib::{self, Bytes, ValueDelegate};
+use gtk::{
+    gio::{self, prelude::*},
+    glib::{self, g_warning, Bytes, UriFlags, ValueDelegate},
+};
+use schema::{
+    endpoints::{Endpoint, Method},
+    errors::{ErrorCode, ErrorReply, FieldError},
+    events::Event,
+    ids::{UserId, UserKind},
+};
 use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value as JsonObject;
 use soup::{prelude::*, Message, Status};
 
-fn endpoint_to_uri(endpoint: &str) -> String {
-    const SERVER_URI: &str = "http://localhost:3000";
+use crate::settings;
+
+const DEFAULT_SERVER_URL: &str = "http://localhost:3000";
+/// How many times a `POST` is sent before a network error is given up on
+const POST_ATTEMPTS: u32 = 3;
+/// How many seconds to wait before following the server's events again once the stream broke
+const EVENTS_RETRY_SECONDS: u32 = 5;
+
+thread_local! {
+    static SERVER_URL: RefCell<String> = RefCell::new(
+        settings::server_url().unwrap_or_else(|| DEFAULT_SERVER_URL.to_string()),
+    );
+    /// The last reply to each `GET` the server gave an `ETag`, by URI. The tag is sent back as
+    /// `If-None-Match`, so a reply that didn't change comes back as `304 Not Modified`, with no
+    /// body to download.
+    static CACHE: RefCell<HashMap<String, CachedReply>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Clone)]
+struct CachedReply {
+    etag: String,
+    body: Bytes,
+}
+
+pub fn server_url() -> String {
+    SERVER_URL.with_borrow(|url| url.clone())
+}
+
+/// Points all sessions at a different server and remembers it for the next runs
+pub fn set_server_url(url: &str) -> Result<(), glib::Error> {
+    let url = url.trim().trim_end_matches('/');
+    glib::Uri::is_valid(url, UriFlags::NONE)?;
+
+    settings::set_server_url(url)?;
+    SERVER_URL.set(url.to_string());
 
+    Ok(())
+}
+
+fn endpoint_to_uri(endpoint: &str) -> String {
     let separator = if endpoint.starts_with('/') { "" } else { "/" };
 
-    format!("{SERVER_URI}{separator}{endpoint}")
+    SERVER_URL.with_borrow(|server_url| format!("{server_url}{separator}{endpoint}"))
 }
 
 #[derive(ValueDelegate, Default, Debug)]
@@ -22,7 +67,18 @@ impl Session {
         Self(soup::Session::new())
     }
 
-    pub async fn post<Res: DeserializeOwned + Default>(
+    /// Sends `request` where its [`Endpoint`] says and reads the reply it's answered with
+    pub async fn call<E: Endpoint>(&self, request: &E) -> Result<E::Response, Error>
+    where
+        E::Response: Default,
+    {
+        match E::METHOD {
+            Method::Get => self.get(&request.path()).await,
+            Method::Post => self.post(request.body(), &request.path()).await,
+        }
+    }
+
+    async fn post<Res: DeserializeOwned + Default>(
         &self,
         request: impl Serialize,
         endpoint: &str,
@@ -30,34 +86,41 @@ impl Session {
         // FIXME: Handle deserialization and response errors properly
 
         let uri = endpoint_to_uri(endpoint);
-        let msg = Message::new("POST", &uri).unwrap_or_else(|err| {
-            panic!("post: '{endpoint}' does not make a valid URI (derived URI: '{uri}'): {err}")
-        });
-
         let serialized_request = serde_json::to_string(&request).unwrap();
         let bytes = Bytes::from_owned(serialized_request);
-        msg.set_request_body_from_bytes(Some("application/json"), Some(&bytes));
 
-        let raw_response = self
-            .0
-            .send_and_read_future(&msg, glib::Priority::DEFAULT)
-            .await
-            .map_err(Error::Network)?;
+        // Network errors are retried with the same key, the server doesn't redo a request it
+        // already handled but whose response got lost on the way back
+        let idempotency_key = glib::uuid_string_random();
+        let mut attempt = 1;
+        let (msg, raw_response) = loop {
+            let msg = Message::new("POST", &uri).unwrap_or_else(|err| {
+                panic!("post: '{endpoint}' does not make a valid URI (derived URI: '{uri}'): {err}")
+            });
+            msg.set_request_body_from_bytes(Some("application/json"), Some(&bytes));
+            if let Some(headers) = msg.request_headers() {
+                headers.append("Idempotency-Key", &idempotency_key);
+            }
+
+            match self
+                .0
+                .send_and_read_future(&msg, glib::Priority::DEFAULT)
+                .await
+            {
+                Ok(raw_response) => break (msg, raw_response),
+                Err(err) if attempt < POST_ATTEMPTS => {
+                    g_warning!(
+                        "biblioteca",
+                        "POST to {endpoint} failed, trying again: {err}"
+                    );
+                    attempt += 1;
+                }
+                Err(err) => return Err(Error::Network(err)),
+            }
+        };
 
         if msg.status_code() >= 400 {
-            let json_object: JsonObject =
-                serde_json::from_slice(&raw_response).map_err(Error::Deserialization)?;
-            Err(Error::Api {
-                status: msg.status(),
-                status_code: msg.status_code(),
-                msg: match json_object.as_object() {
-                    Some(object) => object
-                        .get("error")
-                        .map(|msg| msg.to_string())
-                        .unwrap_or_else(|| "Unknown error".to_string()),
-                    _ => json_object.to_string(),
-                },
-            })
+            Err(api_error(&msg, &raw_response))
         } else {
             if mem::size_of::<Res>() == 0 {
                 return Ok(Res::default());
@@ -67,12 +130,17 @@ impl Session {
         }
     }
 
-    pub async fn get<Res: DeserializeOwned>(&self, endpoint: &str) -> Result<Res, Error> {
+    async fn get<Res: DeserializeOwned>(&self, endpoint: &str) -> Result<Res, Error> {
         let uri = endpoint_to_uri(endpoint);
         let msg = Message::new("GET", &uri).unwrap_or_else(|err| {
             panic!("post: '{endpoint}' does not make a valid URI (derived URI: '{uri}'): {err}")
         });
 
+        let cached = CACHE.with_borrow(|cache| cache.get(&uri).cloned());
+        if let (Some(cached), Some(headers)) = (&cached, msg.request_headers()) {
+            headers.append("If-None-Match", &cached.etag);
+        }
+
         let raw_response = self
             .0
             .send_and_read_future(&msg, glib::Priority::DEFAULT)
@@ -80,48 +148,210 @@ impl Session {
             .map_err(Error::Network)?;
 
         if msg.status_code() >= 400 {
-            let json_object: JsonObject =
-                serde_json::from_slice(&raw_response).map_err(Error::Deserialization)?;
-            Err(Error::Api {
-                status: msg.status(),
-                status_code: msg.status_code(),
-                msg: match json_object.as_object() {
-                    Some(object) => object
-                        .get("error")
-                        .map(|msg| msg.to_string())
-                        .unwrap_or_else(|| "Unknown error".to_string()),
-                    _ => json_object.to_string(),
+            return Err(api_error(&msg, &raw_response));
+        }
+
+        let body = match cached {
+            Some(cached) if msg.status() == Status::NotModified => cached.body,
+            _ => {
+                let etag = msg
+                    .response_headers()
+                    .and_then(|headers| headers.one("ETag"));
+                CACHE.with_borrow_mut(|cache| match etag {
+                    Some(etag) => {
+                        let reply = CachedReply {
+                            etag: etag.into(),
+                            body: raw_response.clone(),
+                        };
+                        cache.insert(uri, reply);
+                    }
+                    None => {
+                        cache.remove(&uri);
+                    }
+                });
+
+                raw_response
+            }
+        };
+
+        serde_json::from_slice(&body).map_err(Error::Deserialization)
+    }
+
+    /// Starts following `GET /api/v1/events` on behalf of `cookie`'s account. The stream stays
+    /// open until the server goes away, so it's read from a task of its own.
+    pub async fn follow_events(&self, cookie: &SessionCookie) -> Result<EventStream, Error> {
+        let uri = endpoint_to_uri("/api/v1/events");
+        let msg = Message::new("GET", &uri).unwrap_or_else(|err| {
+            panic!("follow_events: the server URL does not make a valid URI ('{uri}'): {err}")
+        });
+        if let Some(headers) = msg.request_headers() {
+            let cookie = cookie.cookie();
+            let credentials =
+                glib::base64_encode(format!("{}:{}", cookie.id, cookie.password).as_bytes());
+            headers.append("Authorization", &format!("Basic {credentials}"));
+            headers.append("Accept", "text/event-stream");
+        }
+
+        let stream = self
+            .0
+            .send_future(&msg, glib::Priority::DEFAULT)
+            .await
+            .map_err(Error::Network)?;
+
+        if msg.status_code() >= 400 {
+            let body = gio::MemoryOutputStream::new_resizable();
+            body.splice_future(
+                &stream,
+                gio::OutputStreamSpliceFlags::CLOSE_SOURCE
+                    | gio::OutputStreamSpliceFlags::CLOSE_TARGET,
+                glib::Priority::DEFAULT,
+            )
+            .await
+            .map_err(Error::Network)?;
+
+            return Err(api_error(&msg, &body.steal_as_bytes()));
+        }
+
+        Ok(EventStream(gio::DataInputStream::new(&stream)))
+    }
+
+    /// Follows the events of `cookie`'s account for as long as the task running it lives, giving
+    /// each one to `apply` and connecting again whenever the stream breaks. Whatever changed while
+    /// the events weren't followed is only seen by fetching everything again, so `refresh` runs
+    /// on every connection and when the server says events were missed.
+    pub async fn receive_events<R>(
+        &self,
+        cookie: &SessionCookie,
+        refresh: impl Fn() -> R,
+        apply: impl Fn(Event),
+    ) where
+        R: Future<Output = ()>,
+    {
+        let mut first_attempt = true;
+
+        loop {
+            let events = self.follow_events(cookie).await;
+            if events.is_ok() || first_attempt {
+                refresh().await;
+            }
+            first_attempt = false;
+
+            match events {
+                Ok(events) => loop {
+                    match events.next().await {
+                        Ok(Some(Event::Missed)) => refresh().await,
+                        Ok(Some(event)) => apply(event),
+                        Ok(None) => break,
+                        Err(err) => {
+                            g_warning!("biblioteca", "Stopped following events: {err}");
+                            break;
+                        }
+                    }
                 },
-            })
-        } else {
-            Ok(serde_json::from_slice(&raw_response).map_err(Error::Deserialization)?)
+                Err(err) => g_warning!("biblioteca", "Failed to follow events: {err}"),
+            }
+
+            glib::timeout_future_seconds(EVENTS_RETRY_SECONDS).await;
+        }
+    }
+}
+
+/// Changes to the library as the server commits them, from [`Session::follow_events`]
+#[derive(Debug)]
+pub struct EventStream(gio::DataInputStream);
+
+impl EventStream {
+    /// Waits for the next event, `None` once the server closes the stream
+    pub async fn next(&self) -> Result<Option<Event>, Error> {
+        let mut data = String::new();
+
+        loop {
+            let line = self
+                .0
+                .read_line_utf8_future(glib::Priority::DEFAULT)
+                .await
+                .map_err(Error::Network)?;
+            let Some(line) = line else {
+                return Ok(None);
+            };
+            let line = line.trim_end_matches('\r');
+
+            // A blank line ends an event, the keep-alive comments have no data
+            if line.is_empty() && !data.is_empty() {
+                return serde_json::from_str(&data)
+                    .map(Some)
+                    .map_err(Error::Deserialization);
+            }
+            if let Some(value) = line.strip_prefix("data:") {
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
         }
     }
 }
 
+fn api_error(msg: &Message, raw_response: &Bytes) -> Error {
+    let (msg_text, code, details) = match serde_json::from_slice::<ErrorReply>(raw_response) {
+        Ok(reply) => (reply.error, reply.code, reply.details),
+        Err(_) => (
+            String::from_utf8_lossy(raw_response).into_owned(),
+            ErrorCode::Unknown,
+            Vec::new(),
+        ),
+    };
+
+    Error::Api {
+        status: msg.status(),
+        status_code: msg.status_code(),
+        msg: msg_text,
+        code,
+        details,
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("A network error occured: {0}")]
     Network(glib::Error),
-    #[error("The server returned an error: '{msg}' status: {status_code} ({status})")]
+    #[error(
+        "The server returned an error: '{msg}' code: {code:?} status: {status_code} ({status})"
+    )]
     Api {
         status: Status,
         status_code: u32,
         msg: String,
+        code: ErrorCode,
+        /// What is wrong with each field, when `code` is [`ErrorCode::InvalidFields`]
+        details: Vec<FieldError>,
     },
     #[error("Encountered a deserialization error: {0}")]
     Deserialization(serde_json::Error),
 }
 
+impl Error {
+    /// What the server said went wrong, `None` if it couldn't be reached or replied nonsense
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Api { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// A message about the error that can be shown to the user, if there is one more specific
+    /// than what the view already says
+    pub fn user_message(&self) -> Option<&'static str> {
+        self.code()?.user_message()
+    }
+}
+
 #[derive(glib::Boxed, Clone, Debug, Default)]
 #[boxed_type(name = "LibSessionCookie", nullable)]
 pub struct SessionCookie {
     cookie: schema::session::Cookie,
-    user_type: i64,
+    user_type: UserKind,
 }
 
 impl SessionCookie {
-    pub fn new(id: i64, password: String, user_type: i64) -> Self {
+    pub fn new(id: UserId, password: String, user_type: UserKind) -> Self {
         Self {
             cookie: schema::session::Cookie { id, password },
             user_type,
@@ -132,11 +362,11 @@ impl SessionCookie {
         &self.cookie
     }
 
-    pub fn user_id(&self) -> i64 {
+    pub fn user_id(&self) -> UserId {
         self.cookie.id
     }
 
-    pub fn user_type(&self) -> i64 {
+    pub fn user_type(&self) -> UserKind {
         self.user_type
     }
 }
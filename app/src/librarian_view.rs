@@ -10,6 +10,10 @@ impl LibrarianView {
     pub async fn refresh_books(&self) {
         self.imp().refresh_books().await;
     }
+
+    pub async fn refresh_borrows(&self) {
+        self.imp().refresh_borrows().await;
+    }
 }
 
 mod imp {
@@ -21,24 +25,30 @@ mod imp {
     use adw::{glib, prelude::*, subclass::prelude::*};
     use gtk::{
         gio,
-        glib::{g_warning, BoxedAnyObject, GString},
+        glib::{g_warning, BoxedAnyObject, GString, JoinHandle, MainContext},
         CompositeTemplate,
     };
     use schema::{
-        auth::{
-            DeleteUserReply, DeleteUserRequest, GetAllUsersReply, GetAllUsersRequest,
-            PromoteUserRequest, User,
+        auth::{DeleteUserReply, DeleteUserRequest, GetAllUsersRequest, PromoteUserRequest, User},
+        books::{Book, Borrow, BorrowsRequest},
+        dates::Timestamp,
+        endpoints::{
+            AuthorPopularityReport, Books, DeleteBook, EndBorrow, Endpoint, LengthenBorrow,
+            LoanStatusReport, MostBorrowedReport, NeverBorrowedReport, RegistrationsReport, Report,
+            UtilisationReport,
         },
-        books::{Book, Borrow, BorrowsReply, BorrowsRequest},
-        LIBRARIAN, NORMAL_USER,
+        events::Event,
+        ids::{BookId, BorrowId, UserId, UserKind},
+        reports::ReportRequest,
     };
-    use soup::Status;
 
     use crate::{
+        checkout_dialog::CheckoutDialog,
         confirmation_dialog::ConfirmationDialogBuilder,
         edit_author_details::EditAuthorDetailsWindow,
         edit_book_details::EditBookDetailsWindow,
-        http::{Error, Session, SessionCookie},
+        http::{Session, SessionCookie},
+        list_store_ext::ListStoreExt,
         time,
         widget_ext::WidgetUtilsExt,
         window::ShowToastExt,
@@ -57,10 +67,30 @@ mod imp {
         #[template_child]
         users: TemplateChild<gio::ListStore>,
 
+        #[template_child]
+        report_from_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        report_to_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        loans_report: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        most_borrowed_report: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        never_borrowed_report: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        author_popularity_report: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        utilisation_report: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        registrations_report: TemplateChild<gtk::ListBox>,
+
         #[property(get, set)]
         soup_session: OnceCell<Session>,
         #[property(get, set)]
         session_cookie: RefCell<Option<SessionCookie>>,
+
+        /// Keeps the lists up to date while a librarian is logged in
+        events_task: RefCell<Option<JoinHandle<()>>>,
     }
 
     #[glib::object_subclass]
@@ -80,7 +110,14 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for LibrarianView {}
+    impl ObjectImpl for LibrarianView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj()
+                .connect_session_cookie_notify(|this| this.imp().follow_events());
+        }
+    }
     impl WidgetImpl for LibrarianView {}
 
     #[gtk::template_callbacks]
@@ -93,13 +130,71 @@ mod imp {
             self.session_cookie.borrow().as_ref().cloned().unwrap()
         }
 
-        #[template_callback]
-        async fn on_show(&self) {
+        async fn refresh(&self) {
             self.refresh_books().await;
             self.refresh_users().await;
             self.refresh_borrows().await;
         }
 
+        /// Stops following the events of the previous account, and starts following them for
+        /// the librarian that logged in
+        fn follow_events(&self) {
+            if let Some(task) = self.events_task.take() {
+                task.abort();
+            }
+
+            let Some(cookie) = self.obj().session_cookie() else {
+                return;
+            };
+            if cookie.user_type() != UserKind::Librarian {
+                return;
+            }
+
+            let this = self.obj().clone();
+            let task = MainContext::default().spawn_local(async move {
+                let imp = this.imp();
+                imp.soup_session()
+                    .receive_events(&cookie, || imp.refresh(), |event| imp.apply_event(event))
+                    .await;
+            });
+            self.events_task.replace(Some(task));
+        }
+
+        /// Applies a change the server committed to the lists
+        fn apply_event(&self, event: Event) {
+            match event {
+                Event::BookSaved { book } => {
+                    let book_id = book.book_id;
+                    self.all_books
+                        .save(book, |saved: &Book| saved.book_id == book_id);
+                }
+                Event::BookDeleted { book_id } => {
+                    self.all_books
+                        .remove_where(|book: &Book| book.book_id == book_id);
+                }
+                Event::LoanSaved { loan } => {
+                    self.borrows.save(loan.borrow(), |borrow: &Borrow| {
+                        borrow.borrow_id == loan.borrow_id
+                    });
+                }
+                Event::LoanReturned { borrow_id, .. } => {
+                    self.borrows
+                        .remove_where(|borrow: &Borrow| borrow.borrow_id == borrow_id);
+                }
+                Event::UserSaved { user } => {
+                    let user_id = user.id;
+                    self.users.save(user, |saved: &User| saved.id == user_id);
+                }
+                Event::UserDeleted { user_id } => {
+                    self.users.remove_where(|user: &User| user.id == user_id);
+                }
+                // The authors are fetched when a book is edited
+                Event::AuthorAdded { .. } => {}
+                // The lists were already fetched again
+                Event::Missed => {}
+            }
+        }
+
         #[template_callback]
         async fn on_refresh_clicked(&self, _: &gtk::Button) {
             let Some(current_view) = self.view_stack.visible_child_name() else {
@@ -114,10 +209,12 @@ mod imp {
                 self.refresh_borrows().await;
             } else if current_view == "users" {
                 self.refresh_users().await;
+            } else if current_view == "statistics" {
+                self.refresh_reports().await;
             }
         }
 
-        fn book_with(&self, book_id: i64) -> Book {
+        fn book_with(&self, book_id: BookId) -> Book {
             self.all_books
                 .into_iter()
                 .map(|obj| obj.unwrap().downcast::<BoxedAnyObject>().unwrap())
@@ -126,7 +223,7 @@ mod imp {
                 .unwrap()
         }
 
-        fn user_with(&self, user_id: i64) -> User {
+        fn user_with(&self, user_id: UserId) -> User {
             self.users
                 .into_iter()
                 .map(|obj| obj.unwrap().downcast::<BoxedAnyObject>().unwrap())
@@ -136,7 +233,7 @@ mod imp {
         }
 
         pub(super) async fn refresh_books(&self) {
-            let books = self.soup_session().get::<Vec<Book>>("/books").await;
+            let books = self.soup_session().call(&Books).await;
 
             match books {
                 Ok(books) => {
@@ -158,10 +255,7 @@ mod imp {
             let request = GetAllUsersRequest {
                 cookie: self.cookie().cookie().clone(),
             };
-            let users = self
-                .soup_session()
-                .post::<GetAllUsersReply>(request, "/auth/all-users")
-                .await;
+            let users = self.soup_session().call(&request).await;
 
             match users {
                 Ok(users) => {
@@ -181,14 +275,11 @@ mod imp {
             }
         }
 
-        async fn refresh_borrows(&self) {
+        pub(super) async fn refresh_borrows(&self) {
             let request = BorrowsRequest {
                 cookie: self.cookie().cookie().clone(),
             };
-            let borrows = self
-                .soup_session()
-                .post::<BorrowsReply>(request, "/borrows")
-                .await;
+            let borrows = self.soup_session().call(&request).await;
 
             match borrows {
                 Ok(borrows) => {
@@ -300,30 +391,34 @@ mod imp {
             dialog.present();
         }
 
-        async fn delete_book(&self, book_id: i64) {
-            if let Err(err) = self
-                .soup_session()
-                .post::<()>(self.cookie().cookie(), &format!("/delete-book/{book_id}"))
-                .await
-            {
-                let extras = if let Error::Api { status, .. } = err {
-                    if status == Status::BadRequest {
-                        "(cartea este în împrumut)"
-                    } else {
-                        ""
-                    }
-                } else {
-                    ""
+        async fn delete_book(&self, book_id: BookId) {
+            let request = DeleteBook {
+                book_id,
+                cookie: self.cookie().cookie().clone(),
+            };
+            if let Err(err) = self.soup_session().call(&request).await {
+                let msg = match err.user_message() {
+                    Some(reason) => format!("Ștergerea cărții a eșuat: {reason}"),
+                    None => "Ștergerea cărții a eșuat".to_string(),
                 };
 
-                self.obj()
-                    .show_toast_msg(&format!("Ștergerea cărții a eșuat {extras}"));
+                self.obj().show_toast_msg(&msg);
             } else {
                 self.refresh_books().await;
             }
         }
 
         // --- BORROWS VIEW ---
+        #[template_callback(function)]
+        fn show_checkout_button(visible_child: GString) -> bool {
+            visible_child == "borrows"
+        }
+
+        #[template_callback]
+        fn on_checkout_clicked(&self, _: gtk::Button) {
+            CheckoutDialog::new(self.obj().clone()).present();
+        }
+
         #[template_callback]
         fn on_bind_borrow_book_title(
             &self,
@@ -368,7 +463,7 @@ mod imp {
         ) {
             if let Some(borrow) = list_item.item().and_downcast::<BoxedAnyObject>() {
                 let borrow = borrow.borrow::<Borrow>();
-                let valid_until = glib::DateTime::from_unix_local(borrow.valid_until).unwrap();
+                let valid_until = time::local(borrow.valid_until);
                 let now = time::now();
                 let remaining_days = valid_until.difference(&now).as_days();
 
@@ -406,10 +501,7 @@ mod imp {
                 return;
             };
             let borrow = borrow.borrow::<Borrow>().clone();
-            let valid_until = glib::DateTime::from_unix_local(borrow.valid_until)
-                .unwrap()
-                .add_days(30)
-                .unwrap();
+            let valid_until = time::local(borrow.valid_until).add_days(30).unwrap();
             let now = time::now();
             let remaining_days = valid_until.difference(&now).as_days();
 
@@ -431,16 +523,16 @@ mod imp {
             dialog.present();
         }
 
-        async fn lengthen_borrow(&self, borrow_id: i64) {
-            let endpoint = format!("/lengthen-borrow/{borrow_id}?days=30");
-            if let Err(err) = self
-                .soup_session()
-                .post::<()>(self.cookie().cookie(), &endpoint)
-                .await
-            {
+        async fn lengthen_borrow(&self, borrow_id: BorrowId) {
+            let request = LengthenBorrow {
+                borrow_id,
+                days: 30,
+                cookie: self.cookie().cookie().clone(),
+            };
+            if let Err(err) = self.soup_session().call(&request).await {
                 self.obj()
                     .show_toast_msg("Prelungirea duratei împrumutului a eșuat");
-                g_warning!("biblioteca", "Error on POST to {}: {}", endpoint, err);
+                g_warning!("biblioteca", "Error on POST to {}: {}", request.path(), err);
             } else {
                 self.refresh_borrows().await;
             }
@@ -471,15 +563,14 @@ mod imp {
             dialog.present();
         }
 
-        async fn finish_borrow(&self, borrow_id: i64) {
-            let endpoint = format!("/end-borrow/{borrow_id}");
-            if let Err(err) = self
-                .soup_session()
-                .post::<()>(self.cookie().cookie(), &endpoint)
-                .await
-            {
+        async fn finish_borrow(&self, borrow_id: BorrowId) {
+            let request = EndBorrow {
+                borrow_id,
+                cookie: self.cookie().cookie().clone(),
+            };
+            if let Err(err) = self.soup_session().call(&request).await {
                 self.obj().show_toast_msg("Terminarea împrumutului a eșuat");
-                g_warning!("biblioteca", "Error on POST to {}: {}", endpoint, err);
+                g_warning!("biblioteca", "Error on POST to {}: {}", request.path(), err);
             } else {
                 self.refresh_borrows().await;
             }
@@ -535,20 +626,16 @@ mod imp {
         #[template_callback]
         fn on_bind_user_type(&self, list_item: &gtk::ListItem, _: &gtk::SignalListItemFactory) {
             if let Some(object) = list_item.item().and_downcast::<glib::BoxedAnyObject>() {
-                let user_type = object.borrow::<User>().kind;
-                let user_type = if user_type == NORMAL_USER {
-                    "Utilizator normal".to_string()
-                } else if user_type == LIBRARIAN {
-                    "Bibliotecar".to_string()
-                } else {
-                    format!("Invalid ({user_type})")
+                let user_type = match object.borrow::<User>().kind {
+                    UserKind::Reader => "Utilizator normal",
+                    UserKind::Librarian => "Bibliotecar",
                 };
 
                 list_item
                     .child()
                     .and_downcast::<gtk::Label>()
                     .unwrap()
-                    .set_label(&user_type);
+                    .set_label(user_type);
             }
         }
 
@@ -556,7 +643,7 @@ mod imp {
         fn show_promote_button(object: Option<BoxedAnyObject>) -> bool {
             object
                 .map(|obj| obj.borrow::<User>().kind)
-                .map(|kind| kind == NORMAL_USER)
+                .map(|kind| kind == UserKind::Reader)
                 .unwrap_or(false)
         }
 
@@ -589,15 +676,12 @@ mod imp {
             dialog.present();
         }
 
-        async fn promote_user_account(&self, user_id: i64) {
+        async fn promote_user_account(&self, user_id: UserId) {
             let request = PromoteUserRequest {
                 user_to_be_promoted: user_id,
                 cookie: self.cookie().cookie().clone(),
             };
-            let reply = self
-                .soup_session()
-                .post::<()>(request, "/auth/promote-user")
-                .await;
+            let reply = self.soup_session().call(&request).await;
             if let Err(reply) = reply {
                 self.obj()
                     .show_toast_msg("Nu s-a putut realiza promovarea utilizatorului");
@@ -632,15 +716,12 @@ mod imp {
             dialog.present();
         }
 
-        async fn delete_user_account(&self, user_id: i64) {
+        async fn delete_user_account(&self, user_id: UserId) {
             let request = DeleteUserRequest {
                 user_to_be_deleted: user_id,
                 cookie: self.cookie().cookie().clone(),
             };
-            let reply = self
-                .soup_session()
-                .post::<DeleteUserReply>(request, "/auth/delete-user")
-                .await;
+            let reply = self.soup_session().call(&request).await;
             match reply {
                 Ok(reply) => match reply {
                     DeleteUserReply::Ok => self.refresh_users().await,
@@ -653,5 +734,169 @@ mod imp {
                 },
             }
         }
+
+        // --- STATISTICS VIEW ---
+
+        #[template_callback]
+        async fn on_generate_reports_clicked(&self, _: gtk::Button) {
+            self.refresh_reports().await;
+        }
+
+        /// Parses a date typed in as YYYY-MM-DD, returning the start of that day
+        fn report_date(&self, entry: &adw::EntryRow) -> Result<Option<glib::DateTime>, ()> {
+            let text = entry.text();
+            if text.is_empty() {
+                return Ok(None);
+            }
+
+            glib::DateTime::from_iso8601(
+                &format!("{text}T00:00:00"),
+                Some(&glib::TimeZone::local()),
+            )
+            .map(Some)
+            .map_err(|err| {
+                g_warning!("biblioteca", "Failed to parse {}, err={err}", text);
+                self.obj()
+                    .show_toast_msg("Datele trebuie introduse în formatul AAAA-LL-ZZ");
+            })
+        }
+
+        async fn refresh_reports(&self) {
+            let Ok(from) = self.report_date(&self.report_from_entry) else {
+                return;
+            };
+            let Ok(to) = self.report_date(&self.report_to_entry) else {
+                return;
+            };
+
+            let request = ReportRequest {
+                from: from.and_then(|from| Timestamp::from_unix(from.to_unix())),
+                // The end date is inclusive for the user, but exclusive for the server
+                to: to
+                    .and_then(|to| to.add_days(1).ok())
+                    .and_then(|to| Timestamp::from_unix(to.to_unix())),
+                cookie: self.cookie().cookie().clone(),
+            };
+
+            if let Some(loans) = self.fetch_report(LoanStatusReport(request.clone())).await {
+                fill_report(
+                    &self.loans_report,
+                    [
+                        ("În termen".to_string(), loans.active.to_string()),
+                        ("Întârziate".to_string(), loans.overdue.to_string()),
+                    ],
+                );
+            }
+
+            if let Some(books) = self.fetch_report(MostBorrowedReport(request.clone())).await {
+                fill_report(
+                    &self.most_borrowed_report,
+                    books.into_iter().map(|book| {
+                        (
+                            book.title,
+                            format!("{}, {} împrumuturi", book.author_name, book.times_borrowed),
+                        )
+                    }),
+                );
+            }
+
+            if let Some(books) = self
+                .fetch_report(NeverBorrowedReport(request.clone()))
+                .await
+            {
+                fill_report(
+                    &self.never_borrowed_report,
+                    books.into_iter().map(|book| {
+                        (
+                            book.title,
+                            format!("{}, {} copii", book.author_name, book.count),
+                        )
+                    }),
+                );
+            }
+
+            if let Some(authors) = self
+                .fetch_report(AuthorPopularityReport(request.clone()))
+                .await
+            {
+                fill_report(
+                    &self.author_popularity_report,
+                    authors.into_iter().map(|author| {
+                        (
+                            author.name,
+                            format!(
+                                "{} împrumuturi, {} cititori",
+                                author.times_borrowed, author.distinct_readers
+                            ),
+                        )
+                    }),
+                );
+            }
+
+            if let Some(books) = self.fetch_report(UtilisationReport(request.clone())).await {
+                fill_report(
+                    &self.utilisation_report,
+                    books.into_iter().map(|book| {
+                        (
+                            book.title,
+                            format!(
+                                "{} din {} copii împrumutate ({:.0}%)",
+                                book.borrowed,
+                                book.count,
+                                book.utilisation * 100.0
+                            ),
+                        )
+                    }),
+                );
+            }
+
+            if let Some(registrations) = self.fetch_report(RegistrationsReport(request)).await {
+                fill_report(
+                    &self.registrations_report,
+                    registrations
+                        .into_iter()
+                        .map(|day| (day.day, format!("{} conturi noi", day.registrations))),
+                );
+            }
+        }
+
+        async fn fetch_report<R: Report>(&self, request: R) -> Option<R::Response>
+        where
+            R::Response: Default,
+        {
+            match self.soup_session().call(&request).await {
+                Ok(report) => Some(report),
+                Err(err) => {
+                    self.obj()
+                        .show_toast_msg("A apărut o eroare în timpul generării rapoartelor");
+                    g_warning!("biblioteca", "Error on POST to {}: {}", R::ROUTE, err);
+                    None
+                }
+            }
+        }
+    }
+
+    fn fill_report(list: &gtk::ListBox, rows: impl IntoIterator<Item = (String, String)>) {
+        while let Some(row) = list.row_at_index(0) {
+            list.remove(&row);
+        }
+
+        for (title, subtitle) in rows {
+            list.append(
+                &adw::ActionRow::builder()
+                    .title(title)
+                    .subtitle(subtitle)
+                    .use_markup(false)
+                    .build(),
+            );
+        }
+
+        if list.row_at_index(0).is_none() {
+            list.append(
+                &adw::ActionRow::builder()
+                    .title("Nu există date pentru perioada aleasă")
+                    .build(),
+            );
+        }
     }
 }
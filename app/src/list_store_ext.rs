@@ -0,0 +1,43 @@
+use gtk::{
+    gio::{self, prelude::*},
+    glib::BoxedAnyObject,
+};
+
+/// Changing one record of a `gio::ListStore` of [`BoxedAnyObject`]s, as the server's events say
+pub trait ListStoreExt {
+    /// Replaces the record `matches` picks with `record`, or adds it at the end if there's none
+    fn save<T: 'static>(&self, record: T, matches: impl Fn(&T) -> bool);
+
+    /// Removes the record `matches` picks, if there is one
+    fn remove_where<T: 'static>(&self, matches: impl Fn(&T) -> bool);
+}
+
+impl ListStoreExt for gio::ListStore {
+    fn save<T: 'static>(&self, record: T, matches: impl Fn(&T) -> bool) {
+        let object = BoxedAnyObject::new(record);
+
+        match position_of(self, matches) {
+            Some(position) => self.splice(position, 1, &[object]),
+            None => self.append(&object),
+        }
+    }
+
+    fn remove_where<T: 'static>(&self, matches: impl Fn(&T) -> bool) {
+        if let Some(position) = position_of(self, matches) {
+            self.remove(position);
+        }
+    }
+}
+
+fn position_of<T: 'static>(store: &gio::ListStore, matches: impl Fn(&T) -> bool) -> Option<u32> {
+    (0..store.n_items()).find(|&position| {
+        store
+            .item(position)
+            .and_downcast::<BoxedAnyObject>()
+            .is_some_and(|object| {
+                object
+                    .try_borrow::<T>()
+                    .is_ok_and(|record| matches(&record))
+            })
+    })
+}
@@ -14,10 +14,10 @@ mod imp {
         subclass::prelude::*,
     };
     use gtk::CompositeTemplate;
-    use schema::auth::{CreateAccount, Login, LoginReply};
+    use schema::auth::{CreateAccount, Login};
 
     use crate::{
-        http::{Session, SessionCookie},
+        http::{self, Session, SessionCookie},
         window::ShowToastExt as _,
     };
 
@@ -37,6 +37,9 @@ mod imp {
         #[template_child]
         signup_password: TemplateChild<adw::PasswordEntryRow>,
 
+        #[template_child]
+        server_url_entry: TemplateChild<adw::EntryRow>,
+
         #[property(get, set)]
         soup_session: OnceCell<Session>,
         #[property(get, set)]
@@ -60,7 +63,13 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for LoginPage {}
+    impl ObjectImpl for LoginPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.server_url_entry.set_text(&http::server_url());
+        }
+    }
 
     impl WidgetImpl for LoginPage {}
 
@@ -87,10 +96,7 @@ mod imp {
                 password: password.clone(),
             };
 
-            let reply = self
-                .soup_session()
-                .post::<LoginReply>(request, "/auth/login")
-                .await;
+            let reply = self.soup_session().call(&request).await;
 
             match reply {
                 Ok(reply) => {
@@ -113,10 +119,7 @@ mod imp {
                 password: password.clone(),
             };
 
-            let reply = self
-                .soup_session()
-                .post::<LoginReply>(request, "/auth/create-account")
-                .await;
+            let reply = self.soup_session().call(&request).await;
 
             match reply {
                 Ok(reply) => {
@@ -126,5 +129,21 @@ mod imp {
                 Err(_) => self.obj().show_toast_msg("Nu s-a putut creea contul"),
             }
         }
+
+        #[template_callback]
+        fn on_server_url_applied(&self, entry: adw::EntryRow) {
+            match http::set_server_url(&entry.text()) {
+                Ok(()) => {
+                    entry.set_text(&http::server_url());
+                    self.obj()
+                        .show_toast_msg("Adresa serverului a fost salvată");
+                }
+                Err(err) => {
+                    g_warning!("biblioteca", "Failed to set server url: {err}");
+                    self.obj()
+                        .show_toast_msg("Adresa serverului nu este validă");
+                }
+            }
+        }
     }
 }
@@ -2,12 +2,15 @@ use adw::{glib, prelude::*, Application};
 use window::LibWindow;
 
 mod book_details;
+mod checkout_dialog;
 mod confirmation_dialog;
 mod edit_author_details;
 mod edit_book_details;
 mod http;
 mod librarian_view;
+mod list_store_ext;
 mod login_page;
+mod settings;
 mod time;
 mod user_view;
 mod widget_ext;
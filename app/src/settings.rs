@@ -0,0 +1,45 @@
+//! Settings that persist between runs, stored in a key file in the user's config directory
+
+use std::path::PathBuf;
+
+use gtk::glib::{self, KeyFile, KeyFileFlags};
+
+const SERVER_GROUP: &str = "server";
+const URL_KEY: &str = "url";
+
+fn settings_path() -> PathBuf {
+    glib::user_config_dir()
+        .join("biblioteca")
+        .join("settings.ini")
+}
+
+fn load() -> KeyFile {
+    let key_file = KeyFile::new();
+    // A missing or broken settings file just means that the defaults are used
+    let _ = key_file.load_from_file(settings_path(), KeyFileFlags::KEEP_COMMENTS);
+
+    key_file
+}
+
+pub fn server_url() -> Option<String> {
+    load()
+        .string(SERVER_GROUP, URL_KEY)
+        .ok()
+        .map(|url| url.to_string())
+}
+
+pub fn set_server_url(url: &str) -> Result<(), glib::Error> {
+    let path = settings_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| {
+            glib::Error::new(
+                glib::FileError::Failed,
+                &format!("{}: {err}", dir.display()),
+            )
+        })?;
+    }
+
+    let key_file = load();
+    key_file.set_string(SERVER_GROUP, URL_KEY, url);
+    key_file.save_to_file(path)
+}
@@ -1,29 +1,97 @@
 use adw::prelude::*;
 use gtk::glib::{self, g_warning, GString};
+use schema::dates::{PartialDate, Timestamp};
 
 use crate::window::ShowToastExt;
 
+const MONTHS: [&str; 12] = [
+    "ianuarie",
+    "februarie",
+    "martie",
+    "aprilie",
+    "mai",
+    "iunie",
+    "iulie",
+    "august",
+    "septembrie",
+    "octombrie",
+    "noiembrie",
+    "decembrie",
+];
+
 pub fn now() -> glib::DateTime {
     glib::DateTime::now(&glib::TimeZone::local()).unwrap()
 }
 
+/// The moment in the local timezone, which is how it's shown
 #[track_caller]
-pub fn date_from(unix: i64) -> glib::DateTime {
-    glib::DateTime::from_unix_local(unix).unwrap()
+pub fn local(timestamp: Timestamp) -> glib::DateTime {
+    glib::DateTime::from_unix_local(timestamp.unix()).unwrap()
 }
 
 pub fn format_date(date: &glib::DateTime) -> GString {
     date.format("%d %B %Y").unwrap()
 }
 
+/// Writes out as much of the date as is known, e.g. "15 ianuarie 1850", "ianuarie 1850" or
+/// "cca. 1850"
+pub fn format_partial_date(date: &PartialDate) -> String {
+    let mut formatted = String::new();
+    if date.is_circa() {
+        formatted.push_str("cca. ");
+    }
+    if let Some(day) = date.day() {
+        formatted.push_str(&format!("{day} "));
+    }
+    if let Some(month) = date.month() {
+        formatted.push_str(&format!("{} ", MONTHS[month as usize - 1]));
+    }
+    if date.year() < 0 {
+        formatted.push_str(&format!("{} î.Hr.", -date.year()));
+    } else {
+        formatted.push_str(&date.year().to_string());
+    }
+
+    formatted
+}
+
+/// Whole years from `from` to `to`, counting from the first days they could be
+pub fn years_between(from: &PartialDate, to: &PartialDate) -> i32 {
+    let day_of_year = |date: &PartialDate| (date.month().unwrap_or(1), date.day().unwrap_or(1));
+
+    let years = to.year() - from.year();
+    if day_of_year(to) < day_of_year(from) {
+        years - 1
+    } else {
+        years
+    }
+}
+
+/// Fills in the entries with `date`. The first month in `month_entry` stands for an unknown one.
+pub fn set_entries(
+    date: &PartialDate,
+    year_entry: &adw::EntryRow,
+    month_entry: &adw::ComboRow,
+    day_entry: &adw::EntryRow,
+    circa_entry: &adw::SwitchRow,
+) {
+    year_entry.set_text(&date.year().to_string());
+    month_entry.set_selected(date.month().unwrap_or(0));
+    day_entry.set_text(&date.day().map(|day| day.to_string()).unwrap_or_default());
+    circa_entry.set_active(date.is_circa());
+}
+
+/// Reads the date typed in the entries, of which only the year is required. The first month in
+/// `month_entry` stands for an unknown one.
 pub fn date_from_entries(
     widget: &gtk::Widget,
     year_entry: &adw::EntryRow,
     month_entry: &adw::ComboRow,
     day_entry: &adw::EntryRow,
+    circa_entry: &adw::SwitchRow,
     context: &str,
     allow_empty: bool,
-) -> Result<Option<glib::DateTime>, ()> {
+) -> Result<Option<PartialDate>, ()> {
     if allow_empty && year_entry.text().is_empty() && day_entry.text().is_empty() {
         return Ok(None);
     }
@@ -40,29 +108,33 @@ pub fn date_from_entries(
             return Err(());
         }
     };
-    let month = month_entry.selected() as i32 + 1;
-    let day = match day_entry.text().parse() {
-        Ok(day) => day,
-        Err(err) => {
-            g_warning!(
-                "biblioteca",
-                "Failed to parse {}, err={err}",
-                day_entry.text()
-            );
-            widget.show_toast_msg(&format!("Ziua {context} trebuie să fie un număr"));
-            return Err(());
-        }
+    let month = Some(month_entry.selected()).filter(|&month| month != 0);
+    let day = match day_entry.text().as_str() {
+        "" => None,
+        day => match day.parse() {
+            Ok(day) => Some(day),
+            Err(err) => {
+                g_warning!("biblioteca", "Failed to parse {day}, err={err}");
+                widget.show_toast_msg(&format!("Ziua {context} trebuie să fie un număr"));
+                return Err(());
+            }
+        },
     };
 
-    match glib::DateTime::new(&glib::TimeZone::local(), year, month, day, 0, 0, 0.0) {
-        Ok(publish_date) => Ok(Some(publish_date)),
-        Err(err) => {
-            g_warning!("biblioteca", "Failed to parse date: {err}");
-            widget.show_toast_msg(&format!(
-                "Detaliile introduse nu au format o dată validă: {}",
-                err
-            ));
-            Err(())
-        }
-    }
+    let Some(date) = PartialDate::new(year, month, day) else {
+        g_warning!(
+            "biblioteca",
+            "Not a date: year={year}, month={month:?}, day={day:?}"
+        );
+        widget.show_toast_msg(&format!(
+            "Detaliile introduse pentru data {context} nu formează o dată validă"
+        ));
+        return Err(());
+    };
+
+    Ok(Some(if circa_entry.is_active() {
+        date.circa()
+    } else {
+        date
+    }))
 }
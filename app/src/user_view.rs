@@ -1,5 +1,6 @@
 use adw::glib;
 use gtk::glib::subclass::types::ObjectSubclassIsExt;
+use schema::ids::BookId;
 
 glib::wrapper! {
     pub struct UserView(ObjectSubclass<imp::UserView>)
@@ -7,9 +8,13 @@ glib::wrapper! {
 }
 
 impl UserView {
-    pub async fn borrow_book(&self, book_id: i64) {
+    pub async fn borrow_book(&self, book_id: BookId) {
         self.imp().borrow_book(book_id).await;
     }
+
+    pub fn show_book_details(&self, book_id: BookId) {
+        self.imp().show_book_details(book_id);
+    }
 }
 
 mod imp {
@@ -18,14 +23,21 @@ mod imp {
     use adw::{glib, prelude::*, subclass::prelude::*};
     use gtk::{
         gio,
-        glib::{g_warning, BoxedAnyObject, MainContext},
+        glib::{g_warning, BoxedAnyObject, JoinHandle, MainContext},
         CompositeTemplate,
     };
-    use schema::books::{Book, BorrowReply, BorrowRequest, BorrowedBook, BorrowedByReply};
+    use schema::{
+        books::{Book, BorrowRequest, BorrowedBook},
+        endpoints::{Books, BorrowedBy, Endpoint, ReturnBook, UpdateChaptersRead},
+        events::Event,
+        ids::{BookId, BorrowId, UserKind},
+    };
 
     use crate::{
         book_details::BookDetailsWindow,
         http::{Session, SessionCookie},
+        list_store_ext::ListStoreExt,
+        time,
         widget_ext::WidgetUtilsExt,
         window::ShowToastExt,
     };
@@ -43,6 +55,9 @@ mod imp {
         soup_session: OnceCell<Session>,
         #[property(get, set)]
         session_cookie: RefCell<Option<SessionCookie>>,
+
+        /// Keeps the lists up to date while a reader is logged in
+        events_task: RefCell<Option<JoinHandle<()>>>,
     }
 
     #[glib::object_subclass]
@@ -62,7 +77,14 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for UserView {}
+    impl ObjectImpl for UserView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj()
+                .connect_session_cookie_notify(|this| this.imp().follow_events());
+        }
+    }
     impl WidgetImpl for UserView {}
 
     #[gtk::template_callbacks]
@@ -75,7 +97,11 @@ mod imp {
             self.session_cookie.borrow().as_ref().cloned().unwrap()
         }
 
-        fn book_for_id(&self, id: i64) -> Book {
+        fn book_for_id(&self, id: BookId) -> Book {
+            self.find_book(id).unwrap()
+        }
+
+        fn find_book(&self, id: BookId) -> Option<Book> {
             self.all_books
                 .clone()
                 .into_iter()
@@ -86,23 +112,74 @@ mod imp {
                         .unwrap()
                 })
                 .find(|book| book.book_id == id)
-                .unwrap()
         }
 
         #[template_callback]
-        async fn on_show(&self) {
-            self.refresh_books().await;
-            self.refresh_borrowed_books().await;
+        async fn on_refresh_clicked(&self, _: &gtk::Button) {
+            self.refresh().await;
         }
 
-        #[template_callback]
-        async fn on_refresh_clicked(&self, _: &gtk::Button) {
+        async fn refresh(&self) {
             self.refresh_books().await;
             self.refresh_borrowed_books().await;
         }
 
+        /// Stops following the events of the previous account, and starts following them for
+        /// the reader that logged in
+        fn follow_events(&self) {
+            if let Some(task) = self.events_task.take() {
+                task.abort();
+            }
+
+            let Some(cookie) = self.obj().session_cookie() else {
+                return;
+            };
+            if cookie.user_type() != UserKind::Reader {
+                return;
+            }
+
+            let this = self.obj().clone();
+            let task = MainContext::default().spawn_local(async move {
+                let imp = this.imp();
+                imp.soup_session()
+                    .receive_events(&cookie, || imp.refresh(), |event| imp.apply_event(event))
+                    .await;
+            });
+            self.events_task.replace(Some(task));
+        }
+
+        /// Applies a change the server committed to the lists
+        fn apply_event(&self, event: Event) {
+            match event {
+                Event::BookSaved { book } => {
+                    let book_id = book.book_id;
+                    self.all_books
+                        .save(book, |saved: &Book| saved.book_id == book_id);
+                }
+                Event::BookDeleted { book_id } => {
+                    self.all_books
+                        .remove_where(|book: &Book| book.book_id == book_id);
+                }
+                Event::LoanSaved { loan } => {
+                    self.borrowed_books
+                        .save(loan.borrowed_book(), |borrowed: &BorrowedBook| {
+                            borrowed.borrow_id == loan.borrow_id
+                        });
+                }
+                Event::LoanReturned { borrow_id, .. } => {
+                    self.borrowed_books
+                        .remove_where(|borrowed: &BorrowedBook| borrowed.borrow_id == borrow_id);
+                }
+                // Not shown to readers, and the lists were already fetched again after `Missed`
+                Event::AuthorAdded { .. }
+                | Event::UserSaved { .. }
+                | Event::UserDeleted { .. }
+                | Event::Missed => {}
+            }
+        }
+
         async fn refresh_books(&self) {
-            let books = self.soup_session().get::<Vec<Book>>("/books").await;
+            let books = self.soup_session().call(&Books).await;
 
             match books {
                 Ok(books) => {
@@ -122,10 +199,10 @@ mod imp {
         }
 
         async fn refresh_borrowed_books(&self) {
-            let books = self
-                .soup_session()
-                .post::<BorrowedByReply>("", &format!("/borrowed-by/{}", self.cookie().user_id()))
-                .await;
+            let request = BorrowedBy {
+                user_id: self.cookie().user_id(),
+            };
+            let books = self.soup_session().call(&request).await;
 
             match books {
                 Ok(books) => {
@@ -247,22 +324,35 @@ mod imp {
             BookDetailsWindow::new(&book, self.obj().clone()).present();
         }
 
-        pub(super) async fn borrow_book(&self, book_id: i64) {
+        pub(super) fn show_book_details(&self, book_id: BookId) {
+            let Some(book) = self.find_book(book_id) else {
+                g_warning!(
+                    "biblioteca",
+                    "show_book_details: no book with id {}",
+                    book_id
+                );
+                return;
+            };
+
+            BookDetailsWindow::new(&book, self.obj().clone()).present();
+        }
+
+        pub(super) async fn borrow_book(&self, book_id: BookId) {
             let request = BorrowRequest {
                 cookie: self.cookie().cookie().clone(),
                 book_id,
             };
 
-            let reply = self
-                .soup_session()
-                .post::<BorrowReply>(request, "/borrow")
-                .await;
+            let reply = self.soup_session().call(&request).await;
 
             match reply {
                 Ok(reply) => {
                     if reply.already_borrowed {
                         self.obj()
                             .show_toast_msg("Nu poți împrumuta aceeași carte de mai multe ori");
+                    } else if reply.no_copies_left {
+                        self.obj()
+                            .show_toast_msg("Toate exemplarele acestei cărți sunt împrumutate");
                     }
                 }
                 Err(e) => g_warning!("biblioteca", "we got the error: {}", e),
@@ -305,16 +395,16 @@ mod imp {
             }
         }
 
-        async fn update_chapters_read(&self, borrow_id: i64, value: i64) {
-            let endpoint = format!("/update-borrow-chapters-read/{borrow_id}?value={value}");
-            if let Err(err) = self
-                .soup_session()
-                .post::<()>(self.cookie().cookie(), &endpoint)
-                .await
-            {
+        async fn update_chapters_read(&self, borrow_id: BorrowId, value: i64) {
+            let request = UpdateChaptersRead {
+                borrow_id,
+                chapters_read: value,
+                cookie: self.cookie().cookie().clone(),
+            };
+            if let Err(err) = self.soup_session().call(&request).await {
                 self.obj()
                     .show_toast_msg("Modificarea numărului de capitole citite a eșuat");
-                g_warning!("biblioteca", "Error on POST to {}: {}", endpoint, err);
+                g_warning!("biblioteca", "Error on POST to {}: {}", request.path(), err);
             } else {
                 self.refresh_borrowed_books().await;
             }
@@ -324,7 +414,7 @@ mod imp {
         fn on_bind_date(&self, list_item: &gtk::ListItem, _: &gtk::SignalListItemFactory) {
             if let Some(borrowed_book) = list_item.item().and_downcast::<glib::BoxedAnyObject>() {
                 let borrowed_book = borrowed_book.borrow::<BorrowedBook>();
-                let return_on = glib::DateTime::from_unix_local(borrowed_book.valid_until).unwrap();
+                let return_on = time::local(borrowed_book.valid_until);
                 list_item
                     .child()
                     .and_downcast::<gtk::Label>()
@@ -349,12 +439,11 @@ mod imp {
                 return;
             };
 
-            let endpoint = format!("/return-book/{}", object.borrow::<BorrowedBook>().borrow_id);
-            let result = user_view
-                .imp()
-                .soup_session()
-                .post::<()>(user_view.imp().cookie().cookie().clone(), &endpoint)
-                .await;
+            let request = ReturnBook {
+                borrow_id: object.borrow::<BorrowedBook>().borrow_id,
+                cookie: user_view.imp().cookie().cookie().clone(),
+            };
+            let result = user_view.imp().soup_session().call(&request).await;
 
             if let Err(error) = result {
                 button.show_toast_msg("Înapoierea cărții a eșuat");
@@ -49,7 +49,7 @@ mod imp {
 
     use adw::{glib, prelude::*, subclass::prelude::*};
     use gtk::CompositeTemplate;
-    use schema::{LIBRARIAN, NORMAL_USER};
+    use schema::ids::UserKind;
 
     use crate::{
         http::{Session, SessionCookie},
@@ -109,12 +109,9 @@ mod imp {
             let Some(session_cookie) = self.obj().session_cookie() else {
                 return;
             };
-            let user_type = session_cookie.user_type();
-
-            if user_type == NORMAL_USER {
-                self.stack.set_visible_child_name("user-view");
-            } else if user_type == LIBRARIAN {
-                self.stack.set_visible_child_name("librarian-view");
+            match session_cookie.user_type() {
+                UserKind::Reader => self.stack.set_visible_child_name("user-view"),
+                UserKind::Librarian => self.stack.set_visible_child_name("librarian-view"),
             }
         }
 
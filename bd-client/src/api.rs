@@ -0,0 +1,257 @@
+//! One method for each route of `/api/v1`, in the order `server/src/api/mod.rs` registers them.
+
+use reqwest::Method;
+use schema::{
+    auth::{CreateAccount, GetAllUsersReply, Login, LoginReply, UserChanges},
+    backups::{Backup, BackupsReply},
+    books::{
+        Author, AuthorFields, Book, BookFields, BorrowedBook, BorrowedByReply, BorrowsReply,
+        LoanChanges, NewLoan, RecommendationsReply, SimilarBooksReply,
+    },
+    dates::Timestamp,
+    endpoints::Report,
+    ids::{BookId, BorrowId, UserId},
+    lists::{ReadingList, ReadingListFields, ReadingListsReply},
+    reports::ReportFormat,
+};
+
+use crate::{Client, Error, EventStream};
+
+impl Client {
+    /// Checks an account's email and password, which doesn't need credentials. The id in the
+    /// reply is what [`Client::with_credentials`] takes.
+    pub async fn login(&self, email: &str, password: &str) -> Result<LoginReply, Error> {
+        let login = Login {
+            email: email.into(),
+            password: password.into(),
+        };
+
+        self.json(self.request(Method::POST, "/login").json(&login))
+            .await
+    }
+
+    /// Makes a reader account, which doesn't need credentials
+    pub async fn create_account(&self, account: &CreateAccount) -> Result<LoginReply, Error> {
+        self.json(self.request(Method::POST, "/users").json(account))
+            .await
+    }
+
+    pub async fn users(&self) -> Result<GetAllUsersReply, Error> {
+        self.json(self.request(Method::GET, "/users")).await
+    }
+
+    pub async fn update_user(&self, user_id: UserId, changes: &UserChanges) -> Result<(), Error> {
+        let path = format!("/users/{user_id}");
+
+        self.empty(self.request(Method::PATCH, &path).json(changes))
+            .await
+    }
+
+    pub async fn delete_user(&self, user_id: UserId) -> Result<(), Error> {
+        let path = format!("/users/{user_id}");
+
+        self.empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// The books `user_id` has borrowed and not returned yet
+    pub async fn borrowed_by(&self, user_id: UserId) -> Result<BorrowedByReply, Error> {
+        let path = format!("/users/{user_id}/loans");
+
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// The lists of `user_id`, only the public ones unless they're the requester's own
+    pub async fn reading_lists(&self, user_id: UserId) -> Result<ReadingListsReply, Error> {
+        let path = format!("/users/{user_id}/reading-lists");
+
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    pub async fn recommendations(&self) -> Result<RecommendationsReply, Error> {
+        self.json(self.request(Method::GET, "/me/recommendations"))
+            .await
+    }
+
+    pub async fn authors(&self) -> Result<Vec<Author>, Error> {
+        self.json(self.request(Method::GET, "/authors")).await
+    }
+
+    pub async fn add_author(&self, fields: &AuthorFields) -> Result<Author, Error> {
+        self.json(self.request(Method::POST, "/authors").json(fields))
+            .await
+    }
+
+    pub async fn books(&self) -> Result<Vec<Book>, Error> {
+        self.json(self.request(Method::GET, "/books")).await
+    }
+
+    pub async fn create_book(&self, fields: &BookFields) -> Result<Book, Error> {
+        self.json(self.request(Method::POST, "/books").json(fields))
+            .await
+    }
+
+    pub async fn book(&self, book_id: BookId) -> Result<Book, Error> {
+        let path = format!("/books/{book_id}");
+
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    pub async fn update_book(&self, book_id: BookId, fields: &BookFields) -> Result<Book, Error> {
+        let path = format!("/books/{book_id}");
+
+        self.json(self.request(Method::PUT, &path).json(fields))
+            .await
+    }
+
+    pub async fn delete_book(&self, book_id: BookId) -> Result<(), Error> {
+        let path = format!("/books/{book_id}");
+
+        self.empty(self.request(Method::DELETE, &path)).await
+    }
+
+    pub async fn similar_books(&self, book_id: BookId) -> Result<SimilarBooksReply, Error> {
+        let path = format!("/books/{book_id}/similar");
+
+        self.json(self.request(Method::GET, &path)).await
+    }
+
+    /// Every loan that hasn't been returned, librarians only
+    pub async fn loans(&self) -> Result<BorrowsReply, Error> {
+        self.json(self.request(Method::GET, "/loans")).await
+    }
+
+    /// Borrows a book for the requester, or checks it out to a reader if a `user_id` is given
+    pub async fn borrow(&self, loan: &NewLoan) -> Result<BorrowedBook, Error> {
+        self.json(self.request(Method::POST, "/loans").json(loan))
+            .await
+    }
+
+    pub async fn update_loan(
+        &self,
+        borrow_id: BorrowId,
+        changes: &LoanChanges,
+    ) -> Result<(), Error> {
+        let path = format!("/loans/{borrow_id}");
+
+        self.empty(self.request(Method::PATCH, &path).json(changes))
+            .await
+    }
+
+    pub async fn return_book(&self, borrow_id: BorrowId) -> Result<(), Error> {
+        let path = format!("/loans/{borrow_id}");
+
+        self.empty(self.request(Method::DELETE, &path)).await
+    }
+
+    pub async fn create_reading_list(
+        &self,
+        fields: &ReadingListFields,
+    ) -> Result<ReadingList, Error> {
+        self.json(self.request(Method::POST, "/reading-lists").json(fields))
+            .await
+    }
+
+    pub async fn update_reading_list(
+        &self,
+        list_id: i64,
+        fields: &ReadingListFields,
+    ) -> Result<(), Error> {
+        let path = format!("/reading-lists/{list_id}");
+
+        self.empty(self.request(Method::PUT, &path).json(fields))
+            .await
+    }
+
+    pub async fn delete_reading_list(&self, list_id: i64) -> Result<(), Error> {
+        let path = format!("/reading-lists/{list_id}");
+
+        self.empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// Puts the books on the list in the order of `book_ids`, which must be exactly the books
+    /// already on it
+    pub async fn reorder_reading_list(
+        &self,
+        list_id: i64,
+        book_ids: &[BookId],
+    ) -> Result<(), Error> {
+        let path = format!("/reading-lists/{list_id}/books");
+
+        self.empty(self.request(Method::PUT, &path).json(&book_ids))
+            .await
+    }
+
+    pub async fn add_to_reading_list(&self, list_id: i64, book_id: BookId) -> Result<(), Error> {
+        let path = format!("/reading-lists/{list_id}/books/{book_id}");
+
+        self.empty(self.request(Method::PUT, &path)).await
+    }
+
+    pub async fn remove_from_reading_list(
+        &self,
+        list_id: i64,
+        book_id: BookId,
+    ) -> Result<(), Error> {
+        let path = format!("/reading-lists/{list_id}/books/{book_id}");
+
+        self.empty(self.request(Method::DELETE, &path)).await
+    }
+
+    /// The report `R` over `[from, to)`, unbounded on the missing sides, e.g.
+    /// `client.report::<LoanStatusReport>(None, None)`
+    pub async fn report<R: Report>(
+        &self,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<R::Response, Error> {
+        self.json(self.report_request(R::NAME, from, to, ReportFormat::Json))
+            .await
+    }
+
+    /// The report called `name` as CSV, with a header row
+    pub async fn report_csv(
+        &self,
+        name: &str,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<String, Error> {
+        self.text(self.report_request(name, from, to, ReportFormat::Csv))
+            .await
+    }
+
+    fn report_request(
+        &self,
+        name: &str,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+        format: ReportFormat,
+    ) -> crate::Request {
+        let format = match format {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        };
+
+        self.request(Method::GET, &format!("/reports/{name}"))
+            .query("from", from)
+            .query("to", to)
+            .query("format", Some(format))
+    }
+
+    pub async fn backups(&self) -> Result<BackupsReply, Error> {
+        self.json(self.request(Method::GET, "/backups")).await
+    }
+
+    /// Backs the database up right away, rather than waiting for the daily backup
+    pub async fn create_backup(&self) -> Result<Backup, Error> {
+        self.json(self.request(Method::POST, "/backups")).await
+    }
+
+    /// Follows the changes to the library as they're committed, until the stream is dropped
+    pub async fn events(&self) -> Result<EventStream, Error> {
+        let response = self
+            .send_with(&self.streaming, self.request(Method::GET, "/events"))
+            .await?;
+
+        Ok(EventStream::new(response))
+    }
+}
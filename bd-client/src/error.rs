@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use schema::errors::{ErrorCode, ErrorReply, FieldError};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("A network error occured: {0}")]
+    Network(reqwest::Error),
+    #[error("The server didn't answer within {0:?}")]
+    Timeout(Duration),
+    #[error(
+        "The server returned an error: '{}' code: {:?} status: {status}",
+        reply.error,
+        reply.code
+    )]
+    Api {
+        status: StatusCode,
+        reply: ErrorReply,
+    },
+    #[error("Encountered a deserialization error: {0}")]
+    Deserialization(serde_json::Error),
+}
+
+impl Error {
+    /// What the server said went wrong, `None` if it couldn't be reached or replied nonsense
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Api { reply, .. } => Some(reply.code),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// What is wrong with each field, when the code is [`ErrorCode::InvalidFields`]
+    pub fn details(&self) -> &[FieldError] {
+        match self {
+            Self::Api { reply, .. } => &reply.details,
+            _ => &[],
+        }
+    }
+
+    /// Reads an error response, keeping the body as the message if it isn't the usual JSON
+    pub(crate) fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        let reply = serde_json::from_slice(body).unwrap_or_else(|_| ErrorReply {
+            error: String::from_utf8_lossy(body).into_owned(),
+            code: ErrorCode::Unknown,
+            details: Vec::new(),
+        });
+
+        Self::Api { status, reply }
+    }
+}
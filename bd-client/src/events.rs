@@ -0,0 +1,60 @@
+//! Reading the Server-Sent Events of `GET /api/v1/events`
+
+use reqwest::Response;
+use schema::events::Event;
+
+use crate::Error;
+
+/// The changes to the library, from [`Client::events`](crate::Client::events).
+///
+/// Events that happen while the stream is broken aren't sent again, so after an error a client
+/// should follow the events anew and fetch whatever it shows.
+#[derive(Debug)]
+pub struct EventStream {
+    response: Response,
+    /// What has been read of the events that haven't been returned yet, as bytes since a chunk
+    /// may end in the middle of a character
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Waits for the next event, `None` once the server closes the stream
+    pub async fn next(&mut self) -> Result<Option<Event>, Error> {
+        loop {
+            while let Some(end) = self.buffer.windows(2).position(|bytes| bytes == b"\n\n") {
+                let message: Vec<u8> = self.buffer.drain(..end + 2).collect();
+                if let Some(event) = parse(&String::from_utf8_lossy(&message))? {
+                    return Ok(Some(event));
+                }
+            }
+
+            match self.response.chunk().await.map_err(Error::Network)? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// The event in one message of the stream, `None` for the keep-alive comments which have no data
+fn parse(message: &str) -> Result<Option<Event>, Error> {
+    let data: Vec<&str> = message
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|value| value.strip_prefix(' ').unwrap_or(value))
+        .collect();
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(&data.join("\n"))
+        .map(Some)
+        .map_err(Error::Deserialization)
+}
@@ -0,0 +1,327 @@
+//! An async client for version 1 of the server's HTTP API, the routes under `/api/v1`.
+//!
+//! Every route has a method on [`Client`] taking and returning the `bd-schema` types. Requests are
+//! made on behalf of the account given to [`Client::with_credentials`], failures come back as an
+//! [`Error`] holding the server's [`ErrorCode`](schema::errors::ErrorCode), and requests that are
+//! safe to repeat are sent again when the network or the server fails them.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), client::Error> {
+//! let client = client::Client::new("http://localhost:3000")?;
+//! let account = client.login("ana@example.com", "parola").await?;
+//! let client = client.with_credentials(account.id, "parola");
+//!
+//! for loan in client.borrowed_by(account.id).await? {
+//!     println!("{} is due on {}", loan.book_id, loan.valid_until);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, time::Duration};
+
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{header::HeaderValue, Method, Response, StatusCode};
+use schema::ids::UserId;
+use serde::{de::DeserializeOwned, Serialize};
+
+mod api;
+mod error;
+mod events;
+
+pub use error::Error;
+pub use events::EventStream;
+
+const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+const IDEMPOTENCY_KEY_LENGTH: usize = 32;
+
+/// How requests are sent again after failing in a way that may not happen the next time: a
+/// network error, a timeout, or a `502`, `503` or `504` from the server.
+///
+/// `GET`, `PUT` and `DELETE` requests are always safe to repeat. `POST`s and `PATCH`es are sent
+/// with an `Idempotency-Key`, so that the server only handles them once: lengthening a loan twice
+/// isn't the same as lengthening it once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a request is sent at most, including the first time
+    pub attempts: u32,
+    /// How long to wait before the second attempt, doubled for each one after it
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Sends every request only once
+    pub fn never() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    fn delay_before(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(2)))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The account requests are made on behalf of, sent in an `Authorization: Basic` header
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub user_id: UserId,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user_id", &self.user_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sets up a [`Client`] that doesn't use the defaults
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    server_url: String,
+    credentials: Option<Credentials>,
+    timeout: Duration,
+    connect_timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+impl ClientBuilder {
+    /// `server_url` is where the server is reachable, e.g. `http://localhost:3000`, without the
+    /// `/api/v1`
+    pub fn new(server_url: impl Into<String>) -> Self {
+        Self {
+            server_url: server_url.into(),
+            credentials: None,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn credentials(mut self, user_id: UserId, password: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials {
+            user_id,
+            password: password.into(),
+        });
+        self
+    }
+
+    /// How long one attempt at a request may take, from connecting to reading the whole reply
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn build(self) -> Result<Client, Error> {
+        let http = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .map_err(Error::Network)?;
+        let streaming = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .map_err(Error::Network)?;
+
+        Ok(Client {
+            http,
+            streaming,
+            api_url: format!("{}/api/v1", self.server_url.trim_end_matches('/')),
+            credentials: self.credentials,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+        })
+    }
+}
+
+/// Talks to one server. Clones share their connections, so a client can be cloned for each task
+/// rather than wrapped in an `Arc`.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    /// Without the timeout, for [`Client::events`] which stays open
+    streaming: reqwest::Client,
+    api_url: String,
+    credentials: Option<Credentials>,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+impl Client {
+    /// A client with the default timeouts and retries, see [`ClientBuilder`] to change them
+    pub fn new(server_url: impl Into<String>) -> Result<Self, Error> {
+        ClientBuilder::new(server_url).build()
+    }
+
+    /// The same client, making requests on behalf of another account
+    pub fn with_credentials(&self, user_id: UserId, password: impl Into<String>) -> Self {
+        Self {
+            credentials: Some(Credentials {
+                user_id,
+                password: password.into(),
+            }),
+            ..self.clone()
+        }
+    }
+
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
+    fn request(&self, method: Method, path: &str) -> Request {
+        Request {
+            method,
+            url: format!("{}{path}", self.api_url),
+            query: Vec::new(),
+            body: None,
+        }
+    }
+
+    async fn send(&self, request: Request) -> Result<Response, Error> {
+        self.send_with(&self.http, request).await
+    }
+
+    /// Sends `request` through `http` until it gets an answer or runs out of attempts, turning
+    /// error responses into [`Error::Api`]
+    async fn send_with(&self, http: &reqwest::Client, request: Request) -> Result<Response, Error> {
+        // The same key for every attempt, that's how the server knows they're the same request
+        let idempotency_key = matches!(request.method, Method::POST | Method::PATCH)
+            .then(new_idempotency_key);
+
+        let mut attempt = 1;
+        loop {
+            let mut builder = http
+                .request(request.method.clone(), &request.url)
+                .query(&request.query);
+            if let Some(credentials) = &self.credentials {
+                builder = builder.basic_auth(credentials.user_id, Some(&credentials.password));
+            }
+            if let Some(key) = &idempotency_key {
+                builder = builder.header(IDEMPOTENCY_KEY, key);
+            }
+            if let Some(body) = &request.body {
+                builder = builder
+                    .header(
+                        reqwest::header::CONTENT_TYPE,
+                        HeaderValue::from_static("application/json"),
+                    )
+                    .body(body.clone());
+            }
+
+            let may_retry = attempt < self.retry_policy.attempts;
+            match builder.send().await {
+                Ok(response) if may_retry && is_transient(response.status()) => {}
+                Ok(response) => return read_error(response).await,
+                Err(err) if may_retry && (err.is_connect() || err.is_timeout()) => {}
+                Err(err) if err.is_timeout() => return Err(Error::Timeout(self.timeout)),
+                Err(err) => return Err(Error::Network(err)),
+            }
+
+            attempt += 1;
+            tokio::time::sleep(self.retry_policy.delay_before(attempt)).await;
+        }
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: Request) -> Result<T, Error> {
+        let response = self.send(request).await?;
+        let body = response.bytes().await.map_err(|err| self.network(err))?;
+
+        serde_json::from_slice(&body).map_err(Error::Deserialization)
+    }
+
+    /// For the routes that reply with nothing but `204 No Content`
+    async fn empty(&self, request: Request) -> Result<(), Error> {
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    async fn text(&self, request: Request) -> Result<String, Error> {
+        let response = self.send(request).await?;
+
+        response.text().await.map_err(|err| self.network(err))
+    }
+
+    fn network(&self, err: reqwest::Error) -> Error {
+        if err.is_timeout() {
+            Error::Timeout(self.timeout)
+        } else {
+            Error::Network(err)
+        }
+    }
+}
+
+/// What is needed to send a request again
+struct Request {
+    method: Method,
+    url: String,
+    query: Vec<(&'static str, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl Request {
+    fn query(mut self, name: &'static str, value: Option<impl ToString>) -> Self {
+        if let Some(value) = value {
+            self.query.push((name, value.to_string()));
+        }
+        self
+    }
+
+    fn json(mut self, body: &impl Serialize) -> Self {
+        self.body = Some(serde_json::to_vec(body).expect("request bodies are always serializable"));
+        self
+    }
+}
+
+/// Statuses given when something between the client and the server, or the server itself, is
+/// briefly unavailable
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+async fn read_error(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+
+    let body = response.bytes().await.unwrap_or_default();
+    Err(Error::from_response(status, &body))
+}
+
+fn new_idempotency_key() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(IDEMPOTENCY_KEY_LENGTH)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,267 @@
+//! Moving the catalog between servers as one JSON file of authors and books
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use client::Client;
+use schema::{
+    books::{Author, AuthorFields, Book, BookFields},
+    ids::AuthorId,
+};
+use serde::{Deserialize, Serialize};
+
+/// What `bdctl export` writes and `bdctl import` reads. Authors without books are kept too.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Catalog {
+    pub authors: Vec<Author>,
+    pub books: Vec<Book>,
+}
+
+/// How many records an import added and how many were already there
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub authors_added: usize,
+    pub books_added: usize,
+    pub books_skipped: usize,
+}
+
+pub async fn export(client: &Client) -> anyhow::Result<Catalog> {
+    Ok(Catalog {
+        authors: client.authors().await?,
+        books: client.books().await?,
+    })
+}
+
+/// Adds the catalog's authors and books to the server's. Authors the server already has, with the
+/// same name and date of birth, are reused, and books it already has, with the same title and
+/// author, are skipped, so importing the same file twice adds nothing the second time.
+pub async fn import(client: &Client, catalog: &Catalog) -> anyhow::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    let mut known_authors: HashMap<(String, String), AuthorId> = client
+        .authors()
+        .await?
+        .into_iter()
+        .map(|author| (author_key(&author), author.author_id))
+        .collect();
+    let mut known_books: Vec<(String, AuthorId)> = client
+        .books()
+        .await?
+        .into_iter()
+        .map(|book| (book.title, book.author.author_id))
+        .collect();
+
+    // Ids in the file are the exporting server's, they're mapped to this one's. The authors of the
+    // books come first since they have their descriptions, which older exports left out of the
+    // list of authors.
+    let mut author_ids = HashMap::new();
+    let book_authors = catalog.books.iter().map(|book| &book.author);
+    for author in book_authors.chain(&catalog.authors) {
+        if author_ids.contains_key(&author.author_id) {
+            continue;
+        }
+
+        let author_id = match known_authors.get(&author_key(author)) {
+            Some(&author_id) => author_id,
+            None => {
+                let added = client
+                    .add_author(&author_fields(author))
+                    .await
+                    .with_context(|| format!("Failed to add the author {}", author.name))?;
+                known_authors.insert(author_key(author), added.author_id);
+                summary.authors_added += 1;
+                added.author_id
+            }
+        };
+        author_ids.insert(author.author_id, author_id);
+    }
+
+    for book in &catalog.books {
+        let author_id = author_ids[&book.author.author_id];
+        if known_books.contains(&(book.title.clone(), author_id)) {
+            summary.books_skipped += 1;
+            continue;
+        }
+
+        client
+            .create_book(&book_fields(book, author_id))
+            .await
+            .with_context(|| format!("Failed to add the book {}", book.title))?;
+        known_books.push((book.title.clone(), author_id));
+        summary.books_added += 1;
+    }
+
+    Ok(summary)
+}
+
+fn author_key(author: &Author) -> (String, String) {
+    (author.name.clone(), author.date_of_birth.to_string())
+}
+
+fn author_fields(author: &Author) -> AuthorFields {
+    AuthorFields {
+        name: author.name.clone(),
+        date_of_birth: author.date_of_birth,
+        date_of_death: author.date_of_death,
+        description: author.description.clone(),
+    }
+}
+
+fn book_fields(book: &Book, author_id: AuthorId) -> BookFields {
+    BookFields {
+        title: book.title.clone(),
+        author_id,
+        publish_date: book.publish_date,
+        publisher: book.publisher.clone(),
+        count: book.count,
+        synopsis: book.synopsis.clone(),
+        series: book.series.clone(),
+        genres: book.genres.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, routing::get, Json, Router};
+    use schema::{dates::PartialDate, ids::BookId};
+
+    use super::*;
+
+    /// The catalog of a server, which answers the routes `export` and `import` use
+    type Library = Arc<Mutex<Catalog>>;
+
+    async fn authors(State(library): State<Library>) -> Json<Vec<Author>> {
+        Json(library.lock().unwrap().authors.clone())
+    }
+
+    async fn add_author(
+        State(library): State<Library>,
+        Json(fields): Json<AuthorFields>,
+    ) -> Json<Author> {
+        let mut library = library.lock().unwrap();
+        let author = Author {
+            author_id: AuthorId(library.authors.len() as i64 + 1),
+            name: fields.name,
+            date_of_birth: fields.date_of_birth,
+            date_of_death: fields.date_of_death,
+            description: fields.description,
+        };
+        library.authors.push(author.clone());
+
+        Json(author)
+    }
+
+    async fn books(State(library): State<Library>) -> Json<Vec<Book>> {
+        Json(library.lock().unwrap().books.clone())
+    }
+
+    async fn create_book(
+        State(library): State<Library>,
+        Json(fields): Json<BookFields>,
+    ) -> Json<Book> {
+        let mut library = library.lock().unwrap();
+        let author = library
+            .authors
+            .iter()
+            .find(|author| author.author_id == fields.author_id)
+            .unwrap()
+            .clone();
+        let book = Book {
+            book_id: BookId(library.books.len() as i64 + 1),
+            title: fields.title,
+            author,
+            publish_date: fields.publish_date,
+            publisher: fields.publisher,
+            count: fields.count,
+            synopsis: fields.synopsis,
+            series: fields.series,
+            genres: fields.genres,
+            ..Book::default()
+        };
+        library.books.push(book.clone());
+
+        Json(book)
+    }
+
+    /// Serves `catalog` on a free local port, returning a client for it
+    async fn serve(catalog: Catalog) -> Client {
+        let app = Router::new()
+            .route("/api/v1/authors", get(authors).post(add_author))
+            .route("/api/v1/books", get(books).post(create_book))
+            .with_state(Arc::new(Mutex::new(catalog)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Client::new(format!("http://{address}")).unwrap()
+    }
+
+    fn author(author_id: i64, name: &str, description: &str) -> Author {
+        Author {
+            author_id: AuthorId(author_id),
+            name: name.into(),
+            date_of_birth: PartialDate::from_year(1880).unwrap(),
+            date_of_death: None,
+            description: description.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn catalogs_survive_an_export_and_an_import() {
+        let rebreanu = author(1, "Liviu Rebreanu", "Romancier interbelic");
+        let source = serve(Catalog {
+            authors: vec![rebreanu.clone(), author(2, "Ion Barbu", "Poet")],
+            books: vec![Book {
+                book_id: BookId(1),
+                title: "Ion".into(),
+                author: rebreanu,
+                count: 2,
+                genres: vec!["Roman".into()],
+                ..Book::default()
+            }],
+        })
+        .await;
+        let target = serve(Catalog::default()).await;
+
+        let catalog = export(&source).await.unwrap();
+        assert_eq!(catalog.authors[0].description, "Romancier interbelic");
+        let summary = import(&target, &catalog).await.unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                authors_added: 2,
+                books_added: 1,
+                books_skipped: 0,
+            }
+        );
+
+        let books = target.books().await.unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].title, "Ion");
+        assert_eq!(books[0].count, 2);
+        assert_eq!(books[0].genres, ["Roman"]);
+        assert_eq!(books[0].author.description, "Romancier interbelic");
+
+        // Older exports left the descriptions out of the list of authors, the books have them
+        let mut old_export = catalog;
+        old_export.authors[0].description = String::new();
+        let fresh = serve(Catalog::default()).await;
+        import(&fresh, &old_export).await.unwrap();
+        let books = fresh.books().await.unwrap();
+        assert_eq!(books[0].author.description, "Romancier interbelic");
+
+        let again = import(&target, &export(&source).await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            again,
+            ImportSummary {
+                authors_added: 0,
+                books_added: 0,
+                books_skipped: 1,
+            }
+        );
+    }
+}
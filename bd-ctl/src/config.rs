@@ -0,0 +1,106 @@
+//! Where the server is and who to log in as, kept in a TOML file between runs:
+//!
+//! ```toml
+//! server = "http://localhost:3000"
+//! user_id = 3
+//! password = "parola"
+//! ```
+//!
+//! The file is `$XDG_CONFIG_HOME/bdctl/config.toml`, or `~/.config/bdctl/config.toml`, unless
+//! `--config` says otherwise. `bdctl login` writes it.
+
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use client::Client;
+use schema::ids::UserId;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_SERVER: &str = "http://localhost:3000";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub server: String,
+    #[serde(default)]
+    pub user_id: Option<UserId>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server: DEFAULT_SERVER.into(),
+            user_id: None,
+            password: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the configuration file, the defaults are used if it doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Writes the configuration file, readable only by its owner since it holds a password
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Created without access for anyone else, rather than narrowed once the password is in
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        // The mode only applies to new files, one saved by an older version may be readable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(toml::to_string(self)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// A client for the server, on behalf of the saved account if there is one
+    pub fn client(&self) -> anyhow::Result<Client> {
+        let client = Client::new(&self.server)?;
+
+        Ok(match (self.user_id, &self.password) {
+            (Some(user_id), Some(password)) => client.with_credentials(user_id, password.clone()),
+            _ => client,
+        })
+    }
+}
+
+pub fn default_path() -> anyhow::Result<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config"))
+            .context("Neither XDG_CONFIG_HOME nor HOME is set, pass --config")?,
+    };
+
+    Ok(dir.join("bdctl").join("config.toml"))
+}
@@ -0,0 +1,496 @@
+//! `bdctl`, the library from the command line, for scripting what would otherwise be done by
+//! clicking through the app. Every command is one or a few requests to the server's `/api/v1`.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
+use client::Client;
+use schema::{
+    auth::{GetAllUsersReply, UserChanges},
+    books::{Book, Borrow, LoanChanges, NewLoan},
+    dates::Timestamp,
+    endpoints::{
+        AuthorPopularityReport, LoanStatusReport, MostBorrowedReport, NeverBorrowedReport,
+        RegistrationsReport, Report, UtilisationReport,
+    },
+    ids::{BookId, BorrowId, UserId, UserKind},
+    Integer,
+};
+
+use crate::{
+    catalog::Catalog,
+    config::Config,
+    output::{print, print_json, Table},
+};
+
+mod catalog;
+mod config;
+mod output;
+
+const DAY: Integer = 24 * 60 * 60;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Library management from the command line")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Configuration file holding the server and the account to use
+    #[arg(short, long, global = true, env = "BDCTL_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// URL of the server, overriding the configuration file
+    #[arg(long, global = true, env = "BDCTL_SERVER")]
+    server: Option<String>,
+
+    /// Print replies as JSON rather than as tables
+    #[arg(long, global = true)]
+    json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check an account's password and save it in the configuration file
+    Login {
+        email: String,
+        /// Read from the standard input if not given
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// List the books whose title, author, series or genres contain the query, or every book
+    Books { query: Option<String> },
+    /// Borrow a book, or check it out to a reader
+    Borrow {
+        book_id: BookId,
+        /// The reader the book is checked out to, librarians only
+        #[arg(long)]
+        reader: Option<UserId>,
+    },
+    /// Return borrowed books
+    Return {
+        #[arg(required = true)]
+        borrow_ids: Vec<BorrowId>,
+    },
+    /// Push back when loans are due, librarians only
+    Renew {
+        #[arg(required = true)]
+        borrow_ids: Vec<BorrowId>,
+        #[arg(long)]
+        days: Integer,
+    },
+    /// List the loans past their due date, librarians only
+    Overdue,
+    /// List every account, librarians only
+    Users,
+    /// Make an account a librarian, librarians only
+    Promote {
+        /// Id or email of the account
+        user: String,
+    },
+    /// Print a report, one of most-borrowed, never-borrowed, loans, author-popularity,
+    /// registrations or utilisation, librarians only
+    Report {
+        name: String,
+        /// Start of the period, in RFC 3339
+        #[arg(long)]
+        from: Option<Timestamp>,
+        /// End of the period, in RFC 3339
+        #[arg(long)]
+        to: Option<Timestamp>,
+    },
+    /// Write the catalog's authors and books as JSON
+    Export {
+        /// Where to write, the standard output if not given
+        file: Option<PathBuf>,
+    },
+    /// Add the authors and books of a file written by `export`, librarians only
+    Import { file: PathBuf },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            if let Some(err) = err.downcast_ref::<client::Error>() {
+                for detail in err.details() {
+                    eprintln!("  {}: {}", detail.field, detail.message);
+                }
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    let config_path = match args.config {
+        Some(path) => path,
+        None => config::default_path()?,
+    };
+    let mut config = Config::load(&config_path)?;
+    if let Some(server) = args.server {
+        config.server = server;
+    }
+    let client = config.client()?;
+    let json = args.json;
+
+    match args.command {
+        Command::Login { email, password } => {
+            let password = password_or_prompt(password)?;
+            let account = client.login(&email, &password).await?;
+
+            config.user_id = Some(account.id);
+            config.password = Some(password);
+            config.save(&config_path)?;
+            println!("Logged in as {email}, saved to {}", config_path.display());
+        }
+        Command::Books { query } => {
+            let mut books = client.books().await?;
+            if let Some(query) = query {
+                books.retain(|book| book.matches(&query));
+            }
+            print(json, &books, books_table);
+        }
+        Command::Borrow { book_id, reader } => {
+            let loan = NewLoan {
+                book_id,
+                user_id: reader,
+                reading_list_id: None,
+            };
+            let loan = client.borrow(&loan).await?;
+            print(json, &loan, |loan| {
+                let mut table = Table::new(["loan", "book", "due"]);
+                table.row([
+                    loan.borrow_id.to_string(),
+                    loan.book_id.to_string(),
+                    loan.valid_until.to_string(),
+                ]);
+                table
+            });
+        }
+        Command::Return { borrow_ids } => {
+            for_each_loan(&borrow_ids, "returned", |borrow_id| {
+                client.return_book(borrow_id)
+            })
+            .await?;
+        }
+        Command::Renew { borrow_ids, days } => {
+            let changes = LoanChanges {
+                lengthen_by_days: Some(days),
+                ..LoanChanges::default()
+            };
+            for_each_loan(&borrow_ids, "renewed", |borrow_id| {
+                client.update_loan(borrow_id, &changes)
+            })
+            .await?;
+        }
+        Command::Overdue => {
+            let now = Timestamp::now();
+            let mut loans = client.loans().await?;
+            loans.retain(|loan| loan.valid_until.unix() < now.unix());
+            loans.sort_by_key(|loan| loan.valid_until.unix());
+
+            if json {
+                print_json(&loans);
+            } else {
+                let titles: HashMap<BookId, String> = client
+                    .books()
+                    .await?
+                    .into_iter()
+                    .map(|book| (book.book_id, book.title))
+                    .collect();
+                let users = client.users().await?;
+                print!("{}", overdue_table(&loans, &titles, &users, now));
+            }
+        }
+        Command::Users => {
+            let users = client.users().await?;
+            print(json, &users, users_table);
+        }
+        Command::Promote { user } => {
+            let user_id = match user.parse() {
+                Ok(user_id) => user_id,
+                Err(_) => {
+                    client
+                        .users()
+                        .await?
+                        .into_iter()
+                        .find(|account| account.email == user)
+                        .with_context(|| format!("No user with the email {user}"))?
+                        .id
+                }
+            };
+            let changes = UserChanges {
+                kind: UserKind::Librarian,
+            };
+            client.update_user(user_id, &changes).await?;
+            println!("{user} is now a librarian");
+        }
+        Command::Report { name, from, to } => {
+            if json {
+                print_json(&report_json(&client, &name, from, to).await?);
+            } else {
+                let csv = client.report_csv(&name, from, to).await?;
+                print!("{}", csv_table(&csv)?);
+            }
+        }
+        Command::Export { file } => {
+            let catalog = catalog::export(&client).await?;
+            let text = serde_json::to_string_pretty(&catalog)?;
+            match file {
+                Some(file) => {
+                    fs::write(&file, text)
+                        .with_context(|| format!("Failed to write {}", file.display()))?;
+                    eprintln!(
+                        "Exported {} authors and {} books to {}",
+                        catalog.authors.len(),
+                        catalog.books.len(),
+                        file.display()
+                    );
+                }
+                None => println!("{text}"),
+            }
+        }
+        Command::Import { file } => {
+            let text = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let catalog: Catalog = serde_json::from_str(&text)
+                .with_context(|| format!("{} isn't an exported catalog", file.display()))?;
+
+            let summary = catalog::import(&client, &catalog).await?;
+            println!(
+                "Added {} authors and {} books, skipped {} books already in the catalog",
+                summary.authors_added, summary.books_added, summary.books_skipped
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn password_or_prompt(password: Option<String>) -> anyhow::Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            eprint!("Password: ");
+            io::stderr().flush()?;
+
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    if password.is_empty() {
+        bail!("The password can't be empty");
+    }
+
+    Ok(password)
+}
+
+/// Does the same thing to every loan, going on past the ones that fail so that one bad id in a
+/// batch doesn't stop the rest
+async fn for_each_loan<F, Fut>(borrow_ids: &[BorrowId], done: &str, action: F) -> anyhow::Result<()>
+where
+    F: Fn(BorrowId) -> Fut,
+    Fut: std::future::Future<Output = Result<(), client::Error>>,
+{
+    let mut failed = 0;
+    for &borrow_id in borrow_ids {
+        match action(borrow_id).await {
+            Ok(()) => println!("Loan {borrow_id} {done}"),
+            Err(err) => {
+                eprintln!("Loan {borrow_id} failed: {err}");
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} of {} loans failed", borrow_ids.len());
+    }
+
+    Ok(())
+}
+
+fn books_table(books: &Vec<Book>) -> Table {
+    let mut table = Table::new(["id", "copies", "published", "author", "title"]);
+    for book in books {
+        table.row([
+            book.book_id.to_string(),
+            book.count.to_string(),
+            book.publish_date.to_string(),
+            book.author.name.clone(),
+            book.title.clone(),
+        ]);
+    }
+    table
+}
+
+fn users_table(users: &GetAllUsersReply) -> Table {
+    let mut table = Table::new(["id", "kind", "loans", "email", "name"]);
+    for user in users {
+        let kind = if user.kind == UserKind::Librarian {
+            "librarian"
+        } else {
+            "reader"
+        };
+        table.row([
+            user.id.to_string(),
+            kind.to_string(),
+            user.borrowed_book_count.to_string(),
+            user.email.clone(),
+            user.name.clone(),
+        ]);
+    }
+    table
+}
+
+fn overdue_table(
+    loans: &[Borrow],
+    titles: &HashMap<BookId, String>,
+    users: &GetAllUsersReply,
+    now: Timestamp,
+) -> Table {
+    let mut table = Table::new(["loan", "due", "days late", "reader", "book"]);
+    for loan in loans {
+        let reader = users
+            .iter()
+            .find(|user| user.id == loan.user_id)
+            .map_or_else(|| loan.user_id.to_string(), |user| user.email.clone());
+        let title = titles
+            .get(&loan.book_id)
+            .cloned()
+            .unwrap_or_else(|| loan.book_id.to_string());
+
+        table.row([
+            loan.borrow_id.to_string(),
+            loan.valid_until.to_string(),
+            ((now.unix() - loan.valid_until.unix()) / DAY).to_string(),
+            reader,
+            title,
+        ]);
+    }
+    table
+}
+
+/// The CSV export of a report, aligned as a table
+fn csv_table(csv: &str) -> anyhow::Result<Table> {
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    let mut table = Table::new(reader.headers()?);
+    for record in reader.records() {
+        table.row(&record?);
+    }
+    Ok(table)
+}
+
+async fn report_json(
+    client: &Client,
+    name: &str,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> anyhow::Result<serde_json::Value> {
+    async fn typed<R: Report>(
+        client: &Client,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> anyhow::Result<serde_json::Value> {
+        Ok(serde_json::to_value(client.report::<R>(from, to).await?)?)
+    }
+
+    match name {
+        MostBorrowedReport::NAME => typed::<MostBorrowedReport>(client, from, to).await,
+        NeverBorrowedReport::NAME => typed::<NeverBorrowedReport>(client, from, to).await,
+        LoanStatusReport::NAME => typed::<LoanStatusReport>(client, from, to).await,
+        AuthorPopularityReport::NAME => typed::<AuthorPopularityReport>(client, from, to).await,
+        RegistrationsReport::NAME => typed::<RegistrationsReport>(client, from, to).await,
+        UtilisationReport::NAME => typed::<UtilisationReport>(client, from, to).await,
+        _ => bail!("There's no report called {name}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schema::{books::Author, dates::PartialDate};
+
+    use super::*;
+
+    fn book(title: &str, author: &str, genres: &[&str]) -> Book {
+        Book {
+            title: title.into(),
+            author: Author {
+                name: author.into(),
+                ..Author::default()
+            },
+            publish_date: PartialDate::from_year(1920).unwrap(),
+            genres: genres.iter().map(|genre| genre.to_string()).collect(),
+            ..Book::default()
+        }
+    }
+
+    #[test]
+    fn books_match_on_title_author_and_genres() {
+        let book = book("Pădurea spânzuraților", "Liviu Rebreanu", &["Roman"]);
+
+        assert!(book.matches("pădurea"));
+        assert!(book.matches("REBREANU"));
+        assert!(book.matches("roman"));
+        assert!(!book.matches("Sadoveanu"));
+    }
+
+    #[test]
+    fn tables_are_aligned() {
+        let mut table = Table::new(["id", "title"]);
+        table.row(["7", "Ion"]);
+        table.row(["1234", "Răscoala"]);
+
+        assert_eq!(
+            table.to_string(),
+            "ID    TITLE\n7     Ion\n1234  Răscoala\n"
+        );
+    }
+
+    #[test]
+    fn reports_become_tables() {
+        let table = csv_table("book_id,title\n1,\"Moara cu noroc, nuvelă\"\n").unwrap();
+
+        assert_eq!(
+            table.to_string(),
+            "BOOK_ID  TITLE\n1        Moara cu noroc, nuvelă\n"
+        );
+    }
+
+    #[test]
+    fn configs_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("bdctl-test-{}", std::process::id()))
+            .join("config.toml");
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        let config = Config {
+            server: "http://biblioteca:3000".into(),
+            user_id: Some(UserId(3)),
+            password: Some("parola".into()),
+        };
+        config.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), config);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+//! Printing replies either as tables for people or as JSON for scripts
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+/// Columns aligned with spaces, under an upper case header
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: ToString>(header: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            header: header
+                .into_iter()
+                .map(|name| name.to_string().to_uppercase())
+                .collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row<S: ToString>(&mut self, cells: impl IntoIterator<Item = S>) {
+        self.rows
+            .push(cells.into_iter().map(|cell| cell.to_string()).collect());
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut widths: Vec<usize> = self
+            .header
+            .iter()
+            .map(|name| name.chars().count())
+            .collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            let last = row.len().saturating_sub(1);
+            for (column, (cell, width)) in row.iter().zip(&widths).enumerate() {
+                if column == last {
+                    // Not padded, so that lines don't end in spaces
+                    writeln!(f, "{cell}")?;
+                } else {
+                    write!(f, "{cell:<width$}  ")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints `value` as JSON if `json` is set, otherwise the table made from it
+pub fn print<T: Serialize>(json: bool, value: &T, table: impl FnOnce(&T) -> Table) {
+    if json {
+        print_json(value);
+    } else {
+        print!("{}", table(value));
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("replies are always serializable")
+    );
+}
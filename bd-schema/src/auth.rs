@@ -1,24 +1,50 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{session, Integer, Text};
+use crate::{
+    ids::{UserId, UserKind},
+    session,
+    validation::{FieldErrors, Validate},
+    Integer, Text,
+};
 
 #[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateAccount {
     pub name: String,
     pub email: String,
     pub password: String,
 }
 
+impl Validate for CreateAccount {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors
+            .not_blank("name", &self.name)
+            .not_blank("email", &self.email)
+            .email("email", &self.email)
+            .not_blank("password", &self.password);
+    }
+}
+
 #[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Login {
     pub email: String,
     pub password: String,
 }
 
+impl Validate for Login {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors
+            .not_blank("email", &self.email)
+            .not_blank("password", &self.password);
+    }
+}
+
 #[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct LoginReply {
-    pub id: Integer,
-    pub kind: Integer,
+    pub id: UserId,
+    pub kind: UserKind,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -26,12 +52,17 @@ pub struct GetAllUsersRequest {
     pub cookie: session::Cookie,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+impl Validate for GetAllUsersRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct User {
-    pub id: Integer,
+    pub id: UserId,
     pub name: Text,
     pub email: Text,
-    pub kind: Integer,
+    pub kind: UserKind,
     pub borrowed_book_count: Integer,
 }
 
@@ -39,16 +70,35 @@ pub type GetAllUsersReply = Vec<User>;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct PromoteUserRequest {
-    pub user_to_be_promoted: Integer,
+    pub user_to_be_promoted: UserId,
     pub cookie: session::Cookie,
 }
 
+impl Validate for PromoteUserRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
+/// Sent to `PATCH /api/v1/users/:id`, users can only be promoted to [`UserKind::Librarian`]
+#[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UserChanges {
+    pub kind: UserKind,
+}
+
+impl Validate for UserChanges {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct DeleteUserRequest {
-    pub user_to_be_deleted: Integer,
+    pub user_to_be_deleted: UserId,
     pub cookie: session::Cookie,
 }
 
+impl Validate for DeleteUserRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub enum DeleteUserReply {
     #[default]
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{dates::Timestamp, session, Integer, Text};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BackupRequest {
+    pub cookie: session::Cookie,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Backup {
+    /// File name of the backup, inside the server's backup directory
+    pub name: Text,
+    pub created_at: Timestamp,
+    /// Size in bytes
+    pub size: Integer,
+    /// Hex encoded SHA-256 of the backup file
+    pub sha256: Text,
+}
+
+pub type BackupsReply = Vec<Backup>;
@@ -1,82 +1,281 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{session, Integer, Text};
+use crate::{
+    dates::{PartialDate, Timestamp},
+    errors::FieldErrorCode,
+    ids::{AuthorId, BookId, BorrowId, UserId},
+    session,
+    validation::{FieldErrors, Validate},
+    Integer, Text,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Author {
-    pub author_id: Integer,
+    pub author_id: AuthorId,
     pub name: Text,
-    pub date_of_birth: Integer,
-    pub date_of_death: Option<Integer>,
+    pub date_of_birth: PartialDate,
+    pub date_of_death: Option<PartialDate>,
     pub description: Text,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Book {
-    pub book_id: Integer,
+    pub book_id: BookId,
     pub title: Text,
     pub author: Author,
-    pub publish_date: Integer,
+    pub publish_date: PartialDate,
     pub publisher: Text,
     pub count: Integer,
     pub synopsis: Text,
     pub can_be_borrowed: bool,
+    pub series: Option<Text>,
+    pub genres: Vec<Text>,
+}
+
+impl Book {
+    /// Whether the title, the author, the series or one of the genres contains `query`, ignoring
+    /// case
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        std::iter::once(&self.title)
+            .chain([&self.author.name])
+            .chain(&self.series)
+            .chain(&self.genres)
+            .any(|text| text.to_lowercase().contains(&query))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct BorrowRequest {
     pub cookie: session::Cookie,
-    pub book_id: Integer,
+    pub book_id: BookId,
+}
+
+impl Validate for BorrowRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct BorrowReply {
     pub already_borrowed: bool,
+    /// Every copy of the book is already borrowed
+    #[serde(default)]
+    pub no_copies_left: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BorrowedBook {
-    pub borrow_id: Integer,
-    pub book_id: Integer,
-    pub valid_until: Integer,
+    pub borrow_id: BorrowId,
+    pub book_id: BookId,
+    pub valid_until: Timestamp,
     pub chapters_read: Integer,
 }
 
 pub type BorrowedByReply = Vec<BorrowedBook>;
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CheckoutRequest {
+    /// The reader the book is lent to
+    pub user_id: UserId,
+    pub book_id: BookId,
+    pub cookie: session::Cookie,
+}
+
+impl Validate for CheckoutRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum CheckoutReply {
+    #[default]
+    Ok,
+    AlreadyBorrowed,
+    NoCopiesLeft,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct BorrowsRequest {
     pub cookie: session::Cookie,
 }
 
+impl Validate for BorrowsRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Borrow {
-    pub borrow_id: Integer,
-    pub book_id: Integer,
-    pub user_id: Integer,
-    pub valid_until: Integer,
+    pub borrow_id: BorrowId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub valid_until: Timestamp,
 }
 
 pub type BorrowsReply = Vec<Borrow>;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ChangeBookDetailsRequest {
-    pub book_id: Option<Integer>,
+    pub book_id: Option<BookId>,
     pub title: Text,
-    pub author_id: Integer,
-    pub publish_date: Integer,
+    pub author_id: AuthorId,
+    pub publish_date: PartialDate,
     pub publisher: Text,
     pub count: Integer,
     pub synopsis: Text,
+    #[serde(default)]
+    pub series: Option<Text>,
+    #[serde(default)]
+    pub genres: Vec<Text>,
     pub cookie: session::Cookie,
 }
 
+impl Validate for ChangeBookDetailsRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        validate_book(errors, &self.title, self.count);
+    }
+}
+
+/// The editable details of a book, as sent to `POST /api/v1/books` and `PUT /api/v1/books/:id`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BookFields {
+    pub title: Text,
+    pub author_id: AuthorId,
+    pub publish_date: PartialDate,
+    pub publisher: Text,
+    pub count: Integer,
+    pub synopsis: Text,
+    #[serde(default)]
+    pub series: Option<Text>,
+    #[serde(default)]
+    pub genres: Vec<Text>,
+}
+
+impl Validate for BookFields {
+    fn validate(&self, errors: &mut FieldErrors) {
+        validate_book(errors, &self.title, self.count);
+    }
+}
+
+fn validate_book(errors: &mut FieldErrors, title: &str, count: Integer) {
+    errors.not_blank("title", title).at_least("count", count, 0);
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ChangeAuthorDetailsRequest {
-    pub author_id: Option<Integer>,
+    pub author_id: Option<AuthorId>,
     pub name: Text,
-    pub date_of_birth: Integer,
-    pub date_of_death: Option<Integer>,
+    pub date_of_birth: PartialDate,
+    pub date_of_death: Option<PartialDate>,
     pub description: Text,
     pub cookie: session::Cookie,
 }
+
+impl Validate for ChangeAuthorDetailsRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        validate_author(errors, &self.name, self.date_of_birth, self.date_of_death);
+    }
+}
+
+/// A new author, as sent to `POST /api/v1/authors`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuthorFields {
+    pub name: Text,
+    pub date_of_birth: PartialDate,
+    #[serde(default)]
+    pub date_of_death: Option<PartialDate>,
+    pub description: Text,
+}
+
+impl Validate for AuthorFields {
+    fn validate(&self, errors: &mut FieldErrors) {
+        validate_author(errors, &self.name, self.date_of_birth, self.date_of_death);
+    }
+}
+
+fn validate_author(
+    errors: &mut FieldErrors,
+    name: &str,
+    date_of_birth: PartialDate,
+    date_of_death: Option<PartialDate>,
+) {
+    errors.not_blank("name", name);
+    if date_of_death.is_some_and(|date_of_death| date_of_death.is_before(&date_of_birth)) {
+        errors.add(
+            "date_of_death",
+            FieldErrorCode::TooSmall,
+            "The date of death can't come before the date of birth",
+        );
+    }
+}
+
+/// Sent to `POST /api/v1/loans`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewLoan {
+    pub book_id: BookId,
+    /// The reader the book is lent to when a librarian checks it out at the desk, the requester
+    /// if missing
+    #[serde(default)]
+    pub user_id: Option<UserId>,
+    /// One of the requester's reading lists, which the book is taken off once it's borrowed
+    #[serde(default)]
+    pub reading_list_id: Option<Integer>,
+}
+
+impl Validate for NewLoan {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
+/// Sent to `PATCH /api/v1/loans/:id`, what is left out stays the same
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LoanChanges {
+    /// Pushes the due date back by this many days, librarians only
+    #[serde(default)]
+    pub lengthen_by_days: Option<Integer>,
+    /// Makes the loan due right away, librarians only
+    #[serde(default)]
+    pub due_now: bool,
+    #[serde(default)]
+    pub chapters_read: Option<Integer>,
+}
+
+impl Validate for LoanChanges {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(days) = self.lengthen_by_days {
+            errors.at_least("lengthen_by_days", days, 1);
+        }
+        if let Some(chapters_read) = self.chapters_read {
+            errors.at_least("chapters_read", chapters_read, 0);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Recommendation {
+    pub book_id: BookId,
+    pub title: Text,
+    pub author_name: Text,
+    /// How strongly this book is recommended, higher is better
+    pub score: f64,
+}
+
+pub type SimilarBooksReply = Vec<Recommendation>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RecommendationsRequest {
+    pub cookie: session::Cookie,
+}
+
+impl Validate for RecommendationsRequest {
+    fn validate(&self, _: &mut FieldErrors) {}
+}
+
+pub type RecommendationsReply = Vec<Recommendation>;
@@ -0,0 +1,527 @@
+//! Moments and calendar dates, with the timezone spelled out.
+//!
+//! * [`Timestamp`] is a moment, always in UTC. It's sent as RFC 3339 and stored as seconds since
+//!   the Unix epoch, which clients turn into local time only to show it.
+//! * [`PartialDate`] is a day in the calendar, with no timezone at all, of which only the year may
+//!   be known and which may be approximate. It's what authors' lives and publications are dated
+//!   with, sent and stored as text like `1850-01-15`, `1850-01`, `1850` or `1850~` (circa 1850).
+
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Integer;
+
+/// A moment in time, precise to the second
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(DateTime<Utc>);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Self::from(Utc::now())
+    }
+
+    /// `None` for moments too far from the epoch to be represented
+    pub fn from_unix(seconds: Integer) -> Option<Self> {
+        DateTime::from_timestamp(seconds, 0).map(Self)
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn unix(&self) -> Integer {
+        self.0.timestamp()
+    }
+
+    pub fn to_utc(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+impl Default for Timestamp {
+    fn default() -> Self {
+        Self(DateTime::UNIX_EPOCH)
+    }
+}
+
+/// Drops the fractions of a second, which aren't stored
+impl<Tz: TimeZone> From<DateTime<Tz>> for Timestamp {
+    fn from(time: DateTime<Tz>) -> Self {
+        Self::from_unix(time.timestamp()).expect("a DateTime always fits")
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = chrono::ParseError;
+
+    /// Takes RFC 3339 with any offset
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DateTime::parse_from_rfc3339(s).map(Self::from)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| de::Error::custom(format!("'{s}' is not an RFC 3339 timestamp")))
+    }
+}
+
+/// A calendar date of which the month and the day may be unknown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PartialDate {
+    year: i32,
+    month: Option<u32>,
+    day: Option<u32>,
+    circa: bool,
+}
+
+impl PartialDate {
+    /// Years are written with four digits, so that the text form reads well. It doesn't sort
+    /// chronologically (`-0500` comes after `-0100` and `1850~` after `1850-01`), so dates are only
+    /// ever compared once parsed, never as text in SQL.
+    pub const YEARS: std::ops::RangeInclusive<i32> = -9999..=9999;
+
+    /// `None` if the year is out of [`Self::YEARS`]
+    pub fn from_year(year: i32) -> Option<Self> {
+        Self::new(year, None, None)
+    }
+
+    pub fn from_year_month(year: i32, month: u32) -> Option<Self> {
+        Self::new(year, Some(month), None)
+    }
+
+    /// `None` if the date doesn't exist
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        Self::new(year, Some(month), Some(day))
+    }
+
+    /// `None` for a day without a month or a date that doesn't exist
+    pub fn new(year: i32, month: Option<u32>, day: Option<u32>) -> Option<Self> {
+        let exists = match (month, day) {
+            (None, None) => true,
+            (Some(month), None) => (1..=12).contains(&month),
+            (Some(month), Some(day)) => NaiveDate::from_ymd_opt(year, month, day).is_some(),
+            (None, Some(_)) => false,
+        };
+
+        (Self::YEARS.contains(&year) && exists).then_some(Self {
+            year,
+            month,
+            day,
+            circa: false,
+        })
+    }
+
+    /// Marks the date as approximate
+    pub fn circa(self) -> Self {
+        Self {
+            circa: true,
+            ..self
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> Option<u32> {
+        self.month
+    }
+
+    pub fn day(&self) -> Option<u32> {
+        self.day
+    }
+
+    pub fn is_circa(&self) -> bool {
+        self.circa
+    }
+
+    /// The first day the date could be
+    pub fn earliest(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month.unwrap_or(1), self.day.unwrap_or(1))
+            .expect("checked on creation")
+    }
+
+    /// The last day the date could be
+    pub fn latest(&self) -> NaiveDate {
+        match (self.month, self.day) {
+            (Some(month), Some(day)) => NaiveDate::from_ymd_opt(self.year, month, day),
+            (Some(12), None) | (None, _) => NaiveDate::from_ymd_opt(self.year, 12, 31),
+            (Some(month), None) => NaiveDate::from_ymd_opt(self.year, month + 1, 1)
+                .and_then(|next_month| next_month.pred_opt()),
+        }
+        .expect("checked on creation")
+    }
+
+    /// Whether the whole of `self` comes strictly before all of `other`. Approximate dates are
+    /// taken at face value.
+    pub fn is_before(&self, other: &Self) -> bool {
+        self.latest() < other.earliest()
+    }
+}
+
+impl Default for PartialDate {
+    fn default() -> Self {
+        Self::from(NaiveDate::default())
+    }
+}
+
+impl From<NaiveDate> for PartialDate {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            year: date.year(),
+            month: Some(date.month()),
+            day: Some(date.day()),
+            circa: false,
+        }
+    }
+}
+
+/// Chronological by the earliest day, with the less precise of two dates that start on the same
+/// day first
+impl Ord for PartialDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.earliest()
+            .cmp(&other.earliest())
+            .then_with(|| other.latest().cmp(&self.latest()))
+            .then_with(|| self.circa.cmp(&other.circa))
+    }
+}
+
+impl PartialOrd for PartialDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for PartialDate {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.year < 0 {
+            write!(f, "-{:04}", -self.year)?;
+        } else {
+            write!(f, "{:04}", self.year)?;
+        }
+        if let Some(month) = self.month {
+            write!(f, "-{month:02}")?;
+        }
+        if let Some(day) = self.day {
+            write!(f, "-{day:02}")?;
+        }
+        if self.circa {
+            f.write_str("~")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePartialDateError(String);
+
+impl Display for ParsePartialDateError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "'{}' is not a date written as YYYY, YYYY-MM or YYYY-MM-DD, with an optional ~",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParsePartialDateError {}
+
+impl FromStr for PartialDate {
+    type Err = ParsePartialDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParsePartialDateError(s.to_string());
+
+        let (rest, circa) = match s.strip_suffix('~') {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+        let (rest, sign) = match rest.strip_prefix('-') {
+            Some(rest) => (rest, -1),
+            None => (rest, 1),
+        };
+
+        let mut parts = rest.split('-');
+        let mut number = |digits: usize| {
+            parts
+                .next()
+                .map(|part| {
+                    let is_valid = part.len() == digits && part.bytes().all(|b| b.is_ascii_digit());
+                    is_valid
+                        .then(|| part.parse::<u32>().unwrap())
+                        .ok_or_else(error)
+                })
+                .transpose()
+        };
+
+        let year = number(4)?.ok_or_else(error)? as i32 * sign;
+        let month = number(2)?;
+        let day = number(2)?;
+        if parts.next().is_some() {
+            return Err(error());
+        }
+
+        let date = Self::new(year, month, day).ok_or_else(error)?;
+        Ok(if circa { date.circa() } else { date })
+    }
+}
+
+impl Serialize for PartialDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PartialDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "openapi")]
+mod openapi {
+    use utoipa::{
+        openapi::{KnownFormat, ObjectBuilder, RefOr, Schema, SchemaFormat, SchemaType},
+        ToSchema,
+    };
+
+    use super::{PartialDate, Timestamp};
+
+    impl<'s> ToSchema<'s> for Timestamp {
+        fn schema() -> (&'s str, RefOr<Schema>) {
+            let schema = ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .format(Some(SchemaFormat::KnownFormat(KnownFormat::DateTime)))
+                .description(Some("A moment, in RFC 3339"))
+                .example(Some("2024-02-17T09:00:00Z".into()));
+
+            ("Timestamp", schema.into())
+        }
+    }
+
+    impl<'s> ToSchema<'s> for PartialDate {
+        fn schema() -> (&'s str, RefOr<Schema>) {
+            let schema = ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .pattern(Some(r"^-?\d{4}(-\d{2}(-\d{2})?)?~?$"))
+                .description(Some(
+                    "A date of which the month and day may be unknown, followed by ~ if it's \
+                     approximate",
+                ))
+                .example(Some("1850-01-15".into()));
+
+            ("PartialDate", schema.into())
+        }
+    }
+}
+
+/// Both are stored through the types the database already knows, the timestamps as seconds since
+/// the Unix epoch and the dates as their text form
+#[cfg(feature = "sqlx")]
+mod database {
+    use sqlx::{
+        database::{HasArguments, HasValueRef},
+        encode::IsNull,
+        error::BoxDynError,
+        Database, Decode, Encode, Type,
+    };
+
+    use super::{PartialDate, Timestamp};
+    use crate::Integer;
+
+    impl<DB: Database> Type<DB> for Timestamp
+    where
+        Integer: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <Integer as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <Integer as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for Timestamp
+    where
+        Integer: Encode<'q, DB>,
+    {
+        fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+            self.unix().encode_by_ref(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for Timestamp
+    where
+        Integer: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError> {
+            let seconds = Integer::decode(value)?;
+            Timestamp::from_unix(seconds)
+                .ok_or_else(|| format!("{seconds} is out of the range of timestamps").into())
+        }
+    }
+
+    impl<DB: Database> Type<DB> for PartialDate
+    where
+        String: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <String as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <String as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for PartialDate
+    where
+        String: Encode<'q, DB>,
+    {
+        fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+            self.to_string().encode(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for PartialDate
+    where
+        String: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError> {
+            Ok(String::decode(value)?.parse()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> PartialDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn dates_round_trip_through_text() {
+        for text in [
+            "1850",
+            "1850-01",
+            "1850-01-15",
+            "1850~",
+            "1850-01-15~",
+            "0044",
+            "-0044",
+            "-0500-03~",
+        ] {
+            assert_eq!(date(text).to_string(), text);
+        }
+
+        assert_eq!(
+            date("1850-01-15"),
+            PartialDate::from_ymd(1850, 1, 15).unwrap()
+        );
+        assert_eq!(date("1850~"), PartialDate::from_year(1850).unwrap().circa());
+        assert_eq!(date("-0044").year(), -44);
+        assert_eq!(PartialDate::from_year(-44).unwrap().to_string(), "-0044");
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        for text in [
+            "",
+            "~",
+            "-",
+            "850",
+            "18500",
+            "+1850",
+            "1850-1",
+            "1850-13",
+            "1850-00",
+            "1850-02-30",
+            "1850--15",
+            "1850-01-15-01",
+            "1850~~",
+            "~1850",
+            "--1850",
+            "1850 ",
+            "185O",
+        ] {
+            assert!(
+                text.parse::<PartialDate>().is_err(),
+                "{text:?} was accepted"
+            );
+        }
+
+        let error = "1850-13".parse::<PartialDate>().unwrap_err();
+        assert!(error.to_string().starts_with("'1850-13' is not a date"));
+        assert_eq!(PartialDate::from_year(10_000), None);
+        assert_eq!(PartialDate::new(1850, None, Some(1)), None);
+    }
+
+    #[test]
+    fn dates_are_ordered_chronologically() {
+        let dates = [
+            "-0500",
+            "-0100",
+            "-0100~",
+            "0044",
+            "1850",
+            "1850~",
+            "1850-01",
+            "1850-01-01",
+            "1850-01-15",
+            "1850-02",
+            "1851",
+        ]
+        .map(date);
+
+        for pair in dates.windows(2) {
+            assert!(pair[0] < pair[1], "{} isn't before {}", pair[0], pair[1]);
+        }
+        // Which their text doesn't
+        assert!("-0500" > "-0100" && "1850~" > "1850-01");
+
+        assert!(date("1850").is_before(&date("1851-01-01")));
+        assert!(!date("1850").is_before(&date("1850-12-31")));
+        assert!(date("-0500").is_before(&date("-0100")));
+    }
+
+    #[test]
+    fn the_latest_day_depends_on_the_precision() {
+        let latest = |text: &str| date(text).latest().to_string();
+
+        assert_eq!(latest("1850"), "1850-12-31");
+        assert_eq!(latest("1850-12"), "1850-12-31");
+        assert_eq!(latest("1850-02"), "1850-02-28");
+        assert_eq!(latest("1852-02"), "1852-02-29");
+        assert_eq!(latest("1850-02-10"), "1850-02-10");
+        assert_eq!(date("1850-02").earliest().to_string(), "1850-02-01");
+        assert_eq!(
+            date("-0044-03").latest(),
+            NaiveDate::from_ymd_opt(-44, 3, 31).unwrap()
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! The routes the app talks to, each tied to the request sent to it and the reply it gives back.
+//!
+//! Requests sent whole as the body of a `POST` to a fixed route implement [`Endpoint`]
+//! themselves. Routes with parameters in the path, or without a body, get a struct of their own
+//! here, named after the route, holding the parameters next to the body.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    auth::{
+        CreateAccount, DeleteUserReply, DeleteUserRequest, GetAllUsersReply, GetAllUsersRequest,
+        Login, LoginReply, PromoteUserRequest,
+    },
+    backups::{Backup, BackupRequest, BackupsReply},
+    books::{
+        Author, Book, BorrowReply, BorrowRequest, BorrowedByReply, BorrowsReply, BorrowsRequest,
+        ChangeAuthorDetailsRequest, ChangeBookDetailsRequest, CheckoutReply, CheckoutRequest,
+        RecommendationsReply, RecommendationsRequest, SimilarBooksReply,
+    },
+    ids::{BookId, BorrowId, UserId},
+    lists::{
+        ChangeReadingListRequest, CreateReadingListRequest, ReadingList, ReadingListEntryRequest,
+        ReadingListsReply, ReadingListsRequest, ReorderReadingListRequest,
+    },
+    reports::{
+        AuthorPopularityReply, LoanStatusReply, MostBorrowedReply, NeverBorrowedReply,
+        RegistrationsReply, ReportRequest, UtilisationReply,
+    },
+    session, Integer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// A request the server answers, along with where it's sent and what comes back
+pub trait Endpoint {
+    const METHOD: Method;
+    /// The route as the server registers it, with a `:name` segment for each parameter
+    const ROUTE: &'static str;
+
+    /// Sent as JSON, `()` for requests that don't have a body
+    type Body: Serialize;
+    type Response: Serialize + DeserializeOwned;
+
+    /// [`Self::ROUTE`] with its parameters filled in, and the query string if there is one
+    fn path(&self) -> String {
+        Self::ROUTE.to_string()
+    }
+
+    fn body(&self) -> &Self::Body;
+}
+
+/// Implements [`Endpoint`] for a request sent whole as the body of a `POST` to a fixed route
+macro_rules! post_endpoint {
+    ($request:ty, $route:literal => $response:ty) => {
+        impl Endpoint for $request {
+            const METHOD: Method = Method::Post;
+            const ROUTE: &'static str = $route;
+
+            type Body = Self;
+            type Response = $response;
+
+            fn body(&self) -> &Self {
+                self
+            }
+        }
+    };
+}
+
+post_endpoint!(Login, "/auth/login" => LoginReply);
+post_endpoint!(CreateAccount, "/auth/create-account" => LoginReply);
+post_endpoint!(GetAllUsersRequest, "/auth/all-users" => GetAllUsersReply);
+post_endpoint!(DeleteUserRequest, "/auth/delete-user" => DeleteUserReply);
+post_endpoint!(PromoteUserRequest, "/auth/promote-user" => ());
+post_endpoint!(BorrowRequest, "/borrow" => BorrowReply);
+post_endpoint!(BorrowsRequest, "/borrows" => BorrowsReply);
+post_endpoint!(CheckoutRequest, "/checkout" => CheckoutReply);
+post_endpoint!(ChangeAuthorDetailsRequest, "/change-author-details" => ());
+post_endpoint!(ChangeBookDetailsRequest, "/change-book-details" => ());
+post_endpoint!(RecommendationsRequest, "/me/recommendations" => RecommendationsReply);
+post_endpoint!(CreateReadingListRequest, "/create-reading-list" => ReadingList);
+post_endpoint!(BackupRequest, "/backups" => BackupsReply);
+
+/// Every book in the catalog
+pub struct Books;
+
+impl Endpoint for Books {
+    const METHOD: Method = Method::Get;
+    const ROUTE: &'static str = "/books";
+
+    type Body = ();
+    type Response = Vec<Book>;
+
+    fn body(&self) -> &() {
+        &()
+    }
+}
+
+pub struct Authors;
+
+impl Endpoint for Authors {
+    const METHOD: Method = Method::Get;
+    const ROUTE: &'static str = "/authors";
+
+    type Body = ();
+    type Response = Vec<Author>;
+
+    fn body(&self) -> &() {
+        &()
+    }
+}
+
+pub struct SimilarBooks {
+    pub book_id: BookId,
+}
+
+impl Endpoint for SimilarBooks {
+    const METHOD: Method = Method::Get;
+    const ROUTE: &'static str = "/books/:book_id/similar";
+
+    type Body = ();
+    type Response = SimilarBooksReply;
+
+    fn path(&self) -> String {
+        format!("/books/{}/similar", self.book_id)
+    }
+
+    fn body(&self) -> &() {
+        &()
+    }
+}
+
+/// The books a reader has borrowed
+pub struct BorrowedBy {
+    pub user_id: UserId,
+}
+
+impl Endpoint for BorrowedBy {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/borrowed-by/:user_id";
+
+    type Body = ();
+    type Response = BorrowedByReply;
+
+    fn path(&self) -> String {
+        format!("/borrowed-by/{}", self.user_id)
+    }
+
+    fn body(&self) -> &() {
+        &()
+    }
+}
+
+pub struct DeleteBook {
+    pub book_id: BookId,
+    pub cookie: session::Cookie,
+}
+
+impl Endpoint for DeleteBook {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/delete-book/:book_id";
+
+    type Body = session::Cookie;
+    type Response = ();
+
+    fn path(&self) -> String {
+        format!("/delete-book/{}", self.book_id)
+    }
+
+    fn body(&self) -> &session::Cookie {
+        &self.cookie
+    }
+}
+
+/// Pushes back when a book has to be returned, counting from when it's currently due
+pub struct LengthenBorrow {
+    pub borrow_id: BorrowId,
+    pub days: Integer,
+    pub cookie: session::Cookie,
+}
+
+impl Endpoint for LengthenBorrow {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/lengthen-borrow/:borrow_id";
+
+    type Body = session::Cookie;
+    type Response = ();
+
+    fn path(&self) -> String {
+        format!("/lengthen-borrow/{}?days={}", self.borrow_id, self.days)
+    }
+
+    fn body(&self) -> &session::Cookie {
+        &self.cookie
+    }
+}
+
+/// Makes a book due today
+pub struct EndBorrow {
+    pub borrow_id: BorrowId,
+    pub cookie: session::Cookie,
+}
+
+impl Endpoint for EndBorrow {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/end-borrow/:borrow_id";
+
+    type Body = session::Cookie;
+    type Response = ();
+
+    fn path(&self) -> String {
+        format!("/end-borrow/{}", self.borrow_id)
+    }
+
+    fn body(&self) -> &session::Cookie {
+        &self.cookie
+    }
+}
+
+pub struct UpdateChaptersRead {
+    pub borrow_id: BorrowId,
+    pub chapters_read: Integer,
+    pub cookie: session::Cookie,
+}
+
+impl Endpoint for UpdateChaptersRead {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/update-borrow-chapters-read/:borrow_id";
+
+    type Body = session::Cookie;
+    type Response = ();
+
+    fn path(&self) -> String {
+        format!(
+            "/update-borrow-chapters-read/{}?value={}",
+            self.borrow_id, self.chapters_read
+        )
+    }
+
+    fn body(&self) -> &session::Cookie {
+        &self.cookie
+    }
+}
+
+pub struct ReturnBook {
+    pub borrow_id: BorrowId,
+    pub cookie: session::Cookie,
+}
+
+impl Endpoint for ReturnBook {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/return-book/:borrow_id";
+
+    type Body = session::Cookie;
+    type Response = ();
+
+    fn path(&self) -> String {
+        format!("/return-book/{}", self.borrow_id)
+    }
+
+    fn body(&self) -> &session::Cookie {
+        &self.cookie
+    }
+}
+
+/// The lists of `user_id`, only the public ones unless they're the requester's own
+pub struct ReadingLists {
+    pub user_id: UserId,
+    pub request: ReadingListsRequest,
+}
+
+impl Endpoint for ReadingLists {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/reading-lists/:user_id";
+
+    type Body = ReadingListsRequest;
+    type Response = ReadingListsReply;
+
+    fn path(&self) -> String {
+        format!("/reading-lists/{}", self.user_id)
+    }
+
+    fn body(&self) -> &ReadingListsRequest {
+        &self.request
+    }
+}
+
+/// Implements [`Endpoint`] for a `POST` about one reading list, which is named in the path
+macro_rules! reading_list_endpoint {
+    ($(#[$attr:meta])* $name:ident, $body:ty, $route:literal => $response:ty) => {
+        $(#[$attr])*
+        pub struct $name {
+            pub list_id: Integer,
+            pub request: $body,
+        }
+
+        impl Endpoint for $name {
+            const METHOD: Method = Method::Post;
+            const ROUTE: &'static str = concat!($route, "/:list_id");
+
+            type Body = $body;
+            type Response = $response;
+
+            fn path(&self) -> String {
+                format!("{}/{}", $route, self.list_id)
+            }
+
+            fn body(&self) -> &$body {
+                &self.request
+            }
+        }
+    };
+}
+
+reading_list_endpoint!(
+    ChangeReadingList,
+    ChangeReadingListRequest,
+    "/change-reading-list" => ()
+);
+reading_list_endpoint!(
+    DeleteReadingList,
+    session::Cookie,
+    "/delete-reading-list" => ()
+);
+reading_list_endpoint!(
+    AddToReadingList,
+    ReadingListEntryRequest,
+    "/add-to-reading-list" => ()
+);
+reading_list_endpoint!(
+    RemoveFromReadingList,
+    ReadingListEntryRequest,
+    "/remove-from-reading-list" => ()
+);
+reading_list_endpoint!(
+    ReorderReadingList,
+    ReorderReadingListRequest,
+    "/reorder-reading-list" => ()
+);
+reading_list_endpoint!(
+    /// Borrows a book on the list, which then comes off it
+    BorrowFromReadingList,
+    ReadingListEntryRequest,
+    "/borrow-from-reading-list" => BorrowReply
+);
+
+/// Makes a backup right away, rather than waiting for the scheduled one
+pub struct CreateBackup(pub BackupRequest);
+
+impl Endpoint for CreateBackup {
+    const METHOD: Method = Method::Post;
+    const ROUTE: &'static str = "/create-backup";
+
+    type Body = BackupRequest;
+    type Response = Backup;
+
+    fn body(&self) -> &BackupRequest {
+        &self.0
+    }
+}
+
+/// One of the reports over a period, sent to `/reports/<NAME>`
+pub trait Report: Endpoint<Body = ReportRequest> {
+    const NAME: &'static str;
+}
+
+macro_rules! report_endpoint {
+    ($name:ident, $report:literal => $response:ty) => {
+        pub struct $name(pub ReportRequest);
+
+        impl Endpoint for $name {
+            const METHOD: Method = Method::Post;
+            const ROUTE: &'static str = concat!("/reports/", $report);
+
+            type Body = ReportRequest;
+            type Response = $response;
+
+            fn body(&self) -> &ReportRequest {
+                &self.0
+            }
+        }
+
+        impl Report for $name {
+            const NAME: &'static str = $report;
+        }
+    };
+}
+
+report_endpoint!(MostBorrowedReport, "most-borrowed" => MostBorrowedReply);
+report_endpoint!(NeverBorrowedReport, "never-borrowed" => NeverBorrowedReply);
+report_endpoint!(LoanStatusReport, "loans" => LoanStatusReply);
+report_endpoint!(AuthorPopularityReport, "author-popularity" => AuthorPopularityReply);
+report_endpoint!(RegistrationsReport, "registrations" => RegistrationsReply);
+report_endpoint!(UtilisationReport, "utilisation" => UtilisationReply);
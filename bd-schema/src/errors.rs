@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Text;
+
+/// What went wrong, sent as `code` in every error response so that clients can tell failures apart
+/// without looking at the message. The names are part of the API and don't change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // Given when nothing more specific applies, after the status code
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    #[default]
+    Internal,
+
+    /// The `Authorization` header is missing or can't be parsed
+    MissingAuthorization,
+    /// Only librarians may do this
+    NotLibrarian,
+    NoSuchBook,
+    NoSuchUser,
+    NoSuchAccount,
+    NoSuchBorrow,
+    NoSuchReadingList,
+    NoSuchReport,
+    WrongPassword,
+    /// An account needs a name, an email and a password
+    MissingAccountDetails,
+    EmailTaken,
+    AuthorIsReadOnly,
+    ExtensionTooLong,
+    /// Books are only deleted once every copy has been returned
+    BookStillBorrowed,
+    CannotDeleteSelf,
+    /// Users are only deleted once they've returned every book
+    UserStillHasBooks,
+    /// Borrows are only changed by their reader, or by a librarian
+    NotBorrower,
+    /// Reading lists are only changed by their owner
+    NotListOwner,
+    BlankListName,
+    ListNameTaken,
+    NotOnList,
+    WrongListOrder,
+    /// The reader has already borrowed this book
+    AlreadyBorrowed,
+    /// Every copy of the book is already borrowed
+    NoCopiesLeft,
+    /// Books can't be checked out to someone else from a reading list
+    CheckoutFromReadingList,
+    /// Users can only be promoted to librarians
+    InvalidUserKind,
+    /// The start of a report's period comes after its end
+    InvalidPeriod,
+    InvalidIdempotencyKey,
+    /// The idempotency key was already used for a different request
+    IdempotencyKeyReused,
+    /// A request with the same idempotency key is still being handled
+    RequestInProgress,
+    /// Some fields of the request are invalid, see `details`
+    InvalidFields,
+
+    /// Sent by a newer server, clients should fall back to the message
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    /// What to tell the user, in Romanian like the clients' interfaces, for the codes that say
+    /// more than the action that failed
+    pub fn user_message(self) -> Option<&'static str> {
+        let message = match self {
+            Self::Unauthorized | Self::MissingAuthorization => {
+                "Sesiunea nu mai este validă, autentifică-te din nou"
+            }
+            Self::NotLibrarian => "Doar bibliotecarii pot face asta",
+            Self::NoSuchBook => "Cartea nu mai există",
+            Self::NoSuchUser => "Utilizatorul nu mai există",
+            Self::NoSuchAccount => "Nu există niciun cont cu acest email",
+            Self::WrongPassword => "Parolă greșită",
+            Self::MissingAccountDetails => "Numele, emailul și parola sunt obligatorii",
+            Self::EmailTaken => "Există deja un cont cu acest email",
+            Self::BookStillBorrowed => "Cartea este încă împrumutată",
+            Self::CannotDeleteSelf => "Nu îți poți șterge propriul cont",
+            Self::UserStillHasBooks => "Utilizatorul are încă cărți împrumutate",
+            Self::ExtensionTooLong => "Împrumutul nu poate fi prelungit atât de mult",
+            Self::NoSuchBorrow => "Împrumutul nu mai există",
+            Self::NoSuchReadingList => "Lista nu mai există",
+            Self::BlankListName => "Lista trebuie să aibă un nume",
+            Self::ListNameTaken => "Ai deja o listă cu acest nume",
+            Self::AlreadyBorrowed => "Cititorul are deja această carte împrumutată",
+            Self::NoCopiesLeft => "Nu mai sunt copii disponibile din această carte",
+            Self::InvalidFields => "Unele câmpuri nu sunt completate corect",
+            _ => return None,
+        };
+
+        Some(message)
+    }
+}
+
+/// Why one field of a request was refused
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FieldErrorCode {
+    Blank,
+    /// A number below the smallest value the field takes, or a date before the earliest
+    TooSmall,
+    Invalid,
+
+    /// Sent by a newer server, clients should fall back to the message
+    #[serde(other)]
+    Unknown,
+}
+
+/// One of the `details` of an error response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// Name of the field in the request's JSON body
+    pub field: Text,
+    pub code: FieldErrorCode,
+    pub message: Text,
+}
+
+/// The body of every error response
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ErrorReply {
+    /// Meant to be read by people, may be reworded at any time
+    pub error: Text,
+    #[serde(default)]
+    pub code: ErrorCode,
+    /// What is wrong with each field, when `code` is [`ErrorCode::InvalidFields`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
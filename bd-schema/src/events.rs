@@ -0,0 +1,98 @@
+//! Changes to the library, sent to the clients following `GET /api/v1/events` as they're committed.
+//!
+//! Each one is a Server-Sent Event named after its `type`, with the whole [`Event`] as JSON in its
+//! `data`. Events carry the records as they are after the change, so a client can update what it
+//! shows without fetching anything.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::User,
+    books::{Author, Book, Borrow, BorrowedBook},
+    dates::Timestamp,
+    ids::{BookId, BorrowId, UserId},
+    Integer,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A book was added or edited, or one of its copies was lent or returned
+    BookSaved {
+        book: Book,
+    },
+    BookDeleted {
+        book_id: BookId,
+    },
+    AuthorAdded {
+        author: Author,
+    },
+    /// A book was lent, or the loan was lengthened, ended or had its chapters read changed
+    LoanSaved {
+        loan: Loan,
+    },
+    LoanReturned {
+        borrow_id: BorrowId,
+        book_id: BookId,
+        user_id: UserId,
+    },
+    /// An account was made, was promoted, or borrowed or returned a book. Readers only get their
+    /// own.
+    UserSaved {
+        user: User,
+    },
+    UserDeleted {
+        user_id: UserId,
+    },
+    /// The client fell behind and some events were dropped, so everything should be fetched again
+    Missed,
+}
+
+impl Event {
+    /// The `type` of the event, which is also the name of the Server-Sent Event
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BookSaved { .. } => "book_saved",
+            Self::BookDeleted { .. } => "book_deleted",
+            Self::AuthorAdded { .. } => "author_added",
+            Self::LoanSaved { .. } => "loan_saved",
+            Self::LoanReturned { .. } => "loan_returned",
+            Self::UserSaved { .. } => "user_saved",
+            Self::UserDeleted { .. } => "user_deleted",
+            Self::Missed => "missed",
+        }
+    }
+}
+
+/// Everything about a loan, both what librarians see of it as a [`Borrow`] and what its reader sees
+/// as a [`BorrowedBook`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Loan {
+    pub borrow_id: BorrowId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub valid_until: Timestamp,
+    pub chapters_read: Integer,
+}
+
+impl Loan {
+    pub fn borrow(&self) -> Borrow {
+        Borrow {
+            borrow_id: self.borrow_id,
+            book_id: self.book_id,
+            user_id: self.user_id,
+            valid_until: self.valid_until,
+        }
+    }
+
+    pub fn borrowed_book(&self) -> BorrowedBook {
+        BorrowedBook {
+            borrow_id: self.borrow_id,
+            book_id: self.book_id,
+            valid_until: self.valid_until,
+            chapters_read: self.chapters_read,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Ids of the library's records, one type per table so that e.g. a book's id can't be passed where
+//! a loan's is expected. In JSON and in the database they're plain integers.
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Integer;
+
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+        #[cfg_attr(feature = "sqlx", derive(sqlx::Type), sqlx(transparent))]
+        #[serde(transparent)]
+        pub struct $name(pub Integer);
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map(Self)
+            }
+        }
+    };
+}
+
+id_type!(BookId);
+id_type!(AuthorId);
+id_type!(UserId);
+id_type!(
+    /// Identifies one loan of a book, which stops existing once the book is returned
+    BorrowId
+);
+
+/// What an account may do, stored and sent as an integer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(try_from = "Integer", into = "Integer")]
+#[repr(i64)]
+pub enum UserKind {
+    #[default]
+    Reader = 1,
+    Librarian = 2,
+}
+
+impl TryFrom<Integer> for UserKind {
+    type Error = String;
+
+    fn try_from(kind: Integer) -> Result<Self, Self::Error> {
+        match kind {
+            1 => Ok(Self::Reader),
+            2 => Ok(Self::Librarian),
+            _ => Err(format!("{kind} is not a kind of user")),
+        }
+    }
+}
+
+impl From<UserKind> for Integer {
+    fn from(kind: UserKind) -> Self {
+        kind as Integer
+    }
+}
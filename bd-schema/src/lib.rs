@@ -1,13 +1,41 @@
 //! This crate contains type definitions useful both on the client and server
 
 pub mod auth;
+pub mod backups;
 pub mod books;
+pub mod dates;
+pub mod endpoints;
+pub mod errors;
+pub mod events;
+pub mod ids;
+pub mod lists;
+pub mod reports;
 pub mod session;
+pub mod validation;
 
 /// Type alias that corresponds to INTEGER in sqlite
 pub type Integer = i64;
 /// Type alias that corresponds to TEXT in sqlite
 pub type Text = String;
 
-pub const NORMAL_USER: i64 = 1;
-pub const LIBRARIAN: i64 = 2;
+/// Registers the schemas of [`Integer`] and [`Text`], which the derived schemas refer to by name
+#[cfg(feature = "openapi")]
+pub struct OpenApiAliases;
+
+#[cfg(feature = "openapi")]
+impl utoipa::Modify for OpenApiAliases {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::{KnownFormat, ObjectBuilder, SchemaFormat, SchemaType};
+
+        let integer = ObjectBuilder::new()
+            .schema_type(SchemaType::Integer)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64)));
+        let text = ObjectBuilder::new().schema_type(SchemaType::String);
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components
+            .schemas
+            .insert("Integer".to_string(), integer.into());
+        components.schemas.insert("Text".to_string(), text.into());
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ids::{BookId, UserId},
+    session, Integer, Text,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadingList {
+    pub list_id: Integer,
+    pub owner_id: UserId,
+    pub name: Text,
+    pub is_public: bool,
+    /// The ids of the books on this list, in the order the owner arranged them
+    pub book_ids: Vec<BookId>,
+}
+
+pub type ReadingListsReply = Vec<ReadingList>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReadingListsRequest {
+    pub cookie: session::Cookie,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CreateReadingListRequest {
+    pub name: Text,
+    pub is_public: bool,
+    pub cookie: session::Cookie,
+}
+
+/// Sent to `POST /api/v1/reading-lists` and `PUT /api/v1/reading-lists/:id`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadingListFields {
+    pub name: Text,
+    pub is_public: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChangeReadingListRequest {
+    pub name: Text,
+    pub is_public: bool,
+    pub cookie: session::Cookie,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReadingListEntryRequest {
+    pub book_id: BookId,
+    pub cookie: session::Cookie,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReorderReadingListRequest {
+    /// Must contain exactly the books that are already on the list
+    pub book_ids: Vec<BookId>,
+    pub cookie: session::Cookie,
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dates::Timestamp,
+    ids::{AuthorId, BookId},
+    session, Integer, Text,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReportRequest {
+    /// Start of the reported period (inclusive), unbounded if missing
+    pub from: Option<Timestamp>,
+    /// End of the reported period (exclusive), unbounded if missing
+    pub to: Option<Timestamp>,
+    pub cookie: session::Cookie,
+}
+
+/// Selected through the `format` query parameter of the report endpoints
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MostBorrowedBook {
+    pub book_id: BookId,
+    pub title: Text,
+    pub author_name: Text,
+    pub times_borrowed: Integer,
+}
+
+pub type MostBorrowedReply = Vec<MostBorrowedBook>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NeverBorrowedBook {
+    pub book_id: BookId,
+    pub title: Text,
+    pub author_name: Text,
+    pub count: Integer,
+}
+
+pub type NeverBorrowedReply = Vec<NeverBorrowedBook>;
+
+/// Loans that haven't been returned yet, split by whether they're past their due date
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LoanStatusReply {
+    pub active: Integer,
+    pub overdue: Integer,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuthorPopularity {
+    pub author_id: AuthorId,
+    pub name: Text,
+    pub times_borrowed: Integer,
+    pub distinct_readers: Integer,
+}
+
+pub type AuthorPopularityReply = Vec<AuthorPopularity>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Registrations {
+    /// Formatted as YYYY-MM-DD
+    pub day: Text,
+    pub registrations: Integer,
+}
+
+pub type RegistrationsReply = Vec<Registrations>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TitleUtilisation {
+    pub book_id: BookId,
+    pub title: Text,
+    pub count: Integer,
+    pub borrowed: Integer,
+    /// `borrowed / count`, or 0 for books the library has no copies of
+    pub utilisation: f64,
+}
+
+pub type UtilisationReply = Vec<TitleUtilisation>;
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ids::UserId;
+
 #[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct Cookie {
-    pub id: i64,
+    pub id: UserId,
     pub password: String,
 }
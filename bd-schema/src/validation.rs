@@ -0,0 +1,84 @@
+//! Checks on the contents of requests, made before the server touches the database.
+//!
+//! Each request type lists its rules in its [`Validate`] implementation, next to the type. The
+//! server refuses requests that break any of them with 422 and the [`FieldError`]s, and clients can
+//! run the same checks before sending anything.
+
+use crate::errors::{FieldError, FieldErrorCode};
+
+pub trait Validate {
+    /// Records every rule `self` breaks in `errors`
+    fn validate(&self, errors: &mut FieldErrors);
+
+    /// What is wrong with `self`, if anything
+    fn validated(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = FieldErrors::default();
+        self.validate(&mut errors);
+
+        errors.into_result()
+    }
+}
+
+/// The rules broken by a request, one error per field at most
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    /// The rules that were broken, if any
+    pub fn into_result(self) -> Result<(), Vec<FieldError>> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.0)
+        }
+    }
+
+    /// Records that `field` is wrong, unless something else is already wrong with it
+    pub fn add(&mut self, field: &str, code: FieldErrorCode, message: impl Into<String>) {
+        if self.0.iter().any(|error| error.field == field) {
+            return;
+        }
+
+        self.0.push(FieldError {
+            field: field.to_string(),
+            code,
+            message: message.into(),
+        });
+    }
+
+    /// Checks that `value` has something besides whitespace
+    pub fn not_blank(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.add(field, FieldErrorCode::Blank, "This field is required");
+        }
+        self
+    }
+
+    pub fn at_least(&mut self, field: &str, value: i64, min: i64) -> &mut Self {
+        if value < min {
+            self.add(
+                field,
+                FieldErrorCode::TooSmall,
+                format!("This field can't be less than {min}"),
+            );
+        }
+        self
+    }
+
+    /// Checks that `value` looks like an email address, which is as far as it can be checked
+    /// without sending an email
+    pub fn email(&mut self, field: &str, value: &str) -> &mut Self {
+        let looks_like_email = value
+            .trim()
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && !domain.is_empty());
+        if !looks_like_email {
+            self.add(
+                field,
+                FieldErrorCode::Invalid,
+                "This is not an email address",
+            );
+        }
+        self
+    }
+}
@@ -0,0 +1,780 @@
+//! What the terminal UI holds and what each key does, apart from the drawing in [`crate::ui`]. The
+//! flows are the app's: readers browse the catalog, borrow and keep track of their chapters,
+//! librarians manage the catalog, the loans and the accounts.
+
+use chrono::Local;
+use client::{Client, Error};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use schema::{
+    auth::{GetAllUsersReply, LoginReply, UserChanges},
+    books::{Book, BorrowedByReply, BorrowsReply, LoanChanges, NewLoan},
+    dates::Timestamp,
+    ids::{BookId, BorrowId, UserId, UserKind},
+    Integer,
+};
+
+const DAY: Integer = 24 * 60 * 60;
+/// How many days a librarian lengthens a loan by, the same as in the app
+const LENGTHEN_DAYS: Integer = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Catalog,
+    /// The reader's own loans
+    Borrowed,
+    /// Every loan, for librarians
+    Loans,
+    Users,
+}
+
+impl Tab {
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Catalog => "Catalog",
+            Tab::Borrowed => "Cărțile mele",
+            Tab::Loans => "Împrumuturi",
+            Tab::Users => "Utilizatori",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoginField {
+    #[default]
+    Email,
+    Password,
+}
+
+#[derive(Debug, Default)]
+pub struct LoginForm {
+    pub email: String,
+    pub password: String,
+    pub focus: LoginField,
+}
+
+/// Something done to the selected row once it's confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Return(BorrowId),
+    Lengthen(BorrowId),
+    EndLoan(BorrowId),
+    DeleteBook(BookId),
+    Promote(UserId),
+    DeleteUser(UserId),
+}
+
+/// A window over the current tab, which takes the keys until it's closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prompt {
+    Confirm {
+        question: String,
+        action: Action,
+    },
+    /// Asks a librarian who the book is checked out to
+    Checkout {
+        book_id: BookId,
+        reader: String,
+    },
+    BookDetails(BookId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub text: String,
+    pub is_error: bool,
+}
+
+pub struct App {
+    client: Client,
+    pub server_url: String,
+    pub account: Option<LoginReply>,
+    pub login: LoginForm,
+    pub tab: usize,
+    /// The selected row of each tab, in the order of [`App::tabs`]
+    pub selected: [usize; 3],
+    pub books: Vec<Book>,
+    pub borrowed: BorrowedByReply,
+    pub loans: BorrowsReply,
+    pub users: GetAllUsersReply,
+    pub search: String,
+    pub searching: bool,
+    pub prompt: Option<Prompt>,
+    pub status: Option<Status>,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(client: Client, server_url: String) -> Self {
+        Self {
+            client,
+            server_url,
+            account: None,
+            login: LoginForm::default(),
+            tab: 0,
+            selected: [0; 3],
+            books: Vec::new(),
+            borrowed: Vec::new(),
+            loans: Vec::new(),
+            users: Vec::new(),
+            search: String::new(),
+            searching: false,
+            prompt: None,
+            status: None,
+            quit: false,
+        }
+    }
+
+    pub fn is_librarian(&self) -> bool {
+        self.account
+            .as_ref()
+            .is_some_and(|account| account.kind == UserKind::Librarian)
+    }
+
+    pub fn tabs(&self) -> &'static [Tab] {
+        if self.is_librarian() {
+            &[Tab::Catalog, Tab::Loans, Tab::Users]
+        } else {
+            &[Tab::Catalog, Tab::Borrowed]
+        }
+    }
+
+    pub fn current_tab(&self) -> Tab {
+        self.tabs()[self.tab]
+    }
+
+    /// The books matching the search, in the order they're shown
+    pub fn visible_books(&self) -> Vec<&Book> {
+        self.books
+            .iter()
+            .filter(|book| book.matches(&self.search))
+            .collect()
+    }
+
+    pub fn book(&self, book_id: BookId) -> Option<&Book> {
+        self.books.iter().find(|book| book.book_id == book_id)
+    }
+
+    pub fn title_of(&self, book_id: BookId) -> String {
+        self.book(book_id)
+            .map_or_else(|| format!("Cartea #{book_id}"), |book| book.title.clone())
+    }
+
+    pub fn name_of(&self, user_id: UserId) -> String {
+        self.users
+            .iter()
+            .find(|user| user.id == user_id)
+            .map_or_else(
+                || format!("Utilizatorul #{user_id}"),
+                |user| user.name.clone(),
+            )
+    }
+
+    fn row_count(&self) -> usize {
+        match self.current_tab() {
+            Tab::Catalog => self.visible_books().len(),
+            Tab::Borrowed => self.borrowed.len(),
+            Tab::Loans => self.loans.len(),
+            Tab::Users => self.users.len(),
+        }
+    }
+
+    /// The selected row of the current tab, kept inside the rows it has now
+    pub fn selection(&self) -> Option<usize> {
+        let count = self.row_count();
+        (count > 0).then(|| self.selected[self.tab].min(count - 1))
+    }
+
+    fn select(&mut self, row: usize) {
+        self.selected[self.tab] = row;
+    }
+
+    fn show(&mut self, text: impl Into<String>) {
+        self.status = Some(Status {
+            text: text.into(),
+            is_error: false,
+        });
+    }
+
+    /// Says what failed, and why when the server's error code tells more
+    fn show_error(&mut self, action: &str, err: &Error) {
+        let text = match err.code().and_then(|code| code.user_message()) {
+            Some(reason) => format!("{action}: {reason}"),
+            None => format!("{action}: {err}"),
+        };
+        self.status = Some(Status {
+            text,
+            is_error: true,
+        });
+    }
+
+    pub async fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key).await;
+        } else if self.account.is_none() {
+            self.handle_login_key(key).await;
+        } else if self.searching {
+            self.handle_search_key(key);
+        } else {
+            self.handle_view_key(key).await;
+        }
+    }
+
+    async fn handle_login_key(&mut self, key: KeyEvent) {
+        let form = &mut self.login;
+        let field = match form.focus {
+            LoginField::Email => &mut form.email,
+            LoginField::Password => &mut form.password,
+        };
+
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                form.focus = match form.focus {
+                    LoginField::Email => LoginField::Password,
+                    LoginField::Password => LoginField::Email,
+                };
+            }
+            KeyCode::Char(c) => field.push(c),
+            KeyCode::Backspace => {
+                field.pop();
+            }
+            KeyCode::Enter if form.focus == LoginField::Email => {
+                form.focus = LoginField::Password;
+            }
+            KeyCode::Enter => self.log_in().await,
+            _ => {}
+        }
+    }
+
+    async fn log_in(&mut self) {
+        let reply = self
+            .client
+            .login(&self.login.email, &self.login.password)
+            .await;
+
+        match reply {
+            Ok(account) => {
+                self.client = self
+                    .client
+                    .with_credentials(account.id, self.login.password.clone());
+                self.account = Some(account);
+                self.login = LoginForm::default();
+                self.tab = 0;
+                self.selected = [0; 3];
+                self.status = None;
+                self.refresh().await;
+            }
+            Err(err) => self.show_error("Autentificare eșuată", &err),
+        }
+    }
+
+    fn log_out(&mut self) {
+        let server_url = self.server_url.clone();
+        let client = Client::new(&server_url).unwrap_or_else(|_| self.client.clone());
+
+        *self = Self::new(client, server_url);
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.search.clear();
+                self.searching = false;
+            }
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Char(c) => {
+                self.search.push(c);
+                self.select(0);
+            }
+            KeyCode::Backspace => {
+                self.search.pop();
+                self.select(0);
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_view_key(&mut self, key: KeyEvent) {
+        let tabs = self.tabs().len();
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('o') => self.log_out(),
+            KeyCode::F(5) => self.refresh().await,
+            KeyCode::Tab | KeyCode::Right => self.tab = (self.tab + 1) % tabs,
+            KeyCode::BackTab | KeyCode::Left => self.tab = (self.tab + tabs - 1) % tabs,
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(row) = self.selection() {
+                    self.select((row + 1).min(self.row_count() - 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(row) = self.selection() {
+                    self.select(row.saturating_sub(1));
+                }
+            }
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(self.row_count().saturating_sub(1)),
+            _ => match self.current_tab() {
+                Tab::Catalog => self.handle_catalog_key(key).await,
+                Tab::Borrowed => self.handle_borrowed_key(key).await,
+                Tab::Loans => self.handle_loans_key(key),
+                Tab::Users => self.handle_users_key(key),
+            },
+        }
+    }
+
+    fn selected_book(&self) -> Option<BookId> {
+        let row = self.selection()?;
+        Some(self.visible_books()[row].book_id)
+    }
+
+    async fn handle_catalog_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('/') {
+            self.searching = true;
+            return;
+        }
+
+        let Some(book_id) = self.selected_book() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => self.prompt = Some(Prompt::BookDetails(book_id)),
+            KeyCode::Char('b') if !self.is_librarian() => self.borrow(book_id, None).await,
+            KeyCode::Char('c') if self.is_librarian() => {
+                self.prompt = Some(Prompt::Checkout {
+                    book_id,
+                    reader: String::new(),
+                });
+            }
+            KeyCode::Char('d') if self.is_librarian() => {
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!(
+                        "Ești sigur că vrei să ștergi cartea „{}”?",
+                        self.title_of(book_id)
+                    ),
+                    action: Action::DeleteBook(book_id),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_borrowed_key(&mut self, key: KeyEvent) {
+        let Some(borrowed) = self.selection().map(|row| self.borrowed[row].clone()) else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Char('+') => {
+                self.update_chapters_read(borrowed.borrow_id, borrowed.chapters_read + 1)
+                    .await;
+            }
+            KeyCode::Char('-') if borrowed.chapters_read > 0 => {
+                self.update_chapters_read(borrowed.borrow_id, borrowed.chapters_read - 1)
+                    .await;
+            }
+            KeyCode::Char('r') => {
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!(
+                        "Ești sigur că vrei să înapoiezi cartea „{}”?",
+                        self.title_of(borrowed.book_id)
+                    ),
+                    action: Action::Return(borrowed.borrow_id),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_loans_key(&mut self, key: KeyEvent) {
+        let Some(loan) = self.selection().map(|row| self.loans[row].clone()) else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Char('l') => {
+                let valid_until =
+                    Timestamp::from_unix(loan.valid_until.unix() + LENGTHEN_DAYS * DAY)
+                        .unwrap_or(loan.valid_until);
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!(
+                        "Ești sigur că vrei să lungești durata acestui împrumut? Noua dată la care \
+                         va trebui înapoiată cartea este {}.",
+                        format_date(valid_until)
+                    ),
+                    action: Action::Lengthen(loan.borrow_id),
+                });
+            }
+            KeyCode::Char('t') => {
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!(
+                        "Ești sigur că vrei să termini împrumutul cărții „{}” de către {}?",
+                        self.title_of(loan.book_id),
+                        self.name_of(loan.user_id)
+                    ),
+                    action: Action::EndLoan(loan.borrow_id),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_users_key(&mut self, key: KeyEvent) {
+        let Some(user) = self.selection().map(|row| self.users[row].clone()) else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Char('p') if user.kind == UserKind::Reader => {
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!(
+                        "Ești sigur că vrei să îl faci pe {} bibliotecar?",
+                        user.name
+                    ),
+                    action: Action::Promote(user.id),
+                });
+            }
+            KeyCode::Char('d') => {
+                self.prompt = Some(Prompt::Confirm {
+                    question: format!("Ești sigur că vrei să ștergi contul lui {}?", user.name),
+                    action: Action::DeleteUser(user.id),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let is_librarian = self.is_librarian();
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+
+        match prompt {
+            Prompt::Confirm { action, .. } => match key.code {
+                KeyCode::Char('d' | 'y') | KeyCode::Enter => {
+                    let action = *action;
+                    self.prompt = None;
+                    self.run(action).await;
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.prompt = None,
+                _ => {}
+            },
+            Prompt::Checkout { book_id, reader } => match key.code {
+                KeyCode::Char(c) => reader.push(c),
+                KeyCode::Backspace => {
+                    reader.pop();
+                }
+                KeyCode::Enter => {
+                    let (book_id, reader) = (*book_id, reader.trim().to_string());
+                    self.prompt = None;
+                    self.check_out(book_id, &reader).await;
+                }
+                KeyCode::Esc => self.prompt = None,
+                _ => {}
+            },
+            Prompt::BookDetails(book_id) => match key.code {
+                KeyCode::Char('b') if !is_librarian => {
+                    let book_id = *book_id;
+                    self.prompt = None;
+                    self.borrow(book_id, None).await;
+                }
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => self.prompt = None,
+                _ => {}
+            },
+        }
+    }
+
+    /// Fetches again everything the account's tabs show
+    pub async fn refresh(&mut self) {
+        match self.client.books().await {
+            Ok(books) => self.books = books,
+            Err(err) => self.show_error("Obținerea listei de cărți a eșuat", &err),
+        }
+
+        if self.is_librarian() {
+            self.refresh_loans().await;
+            match self.client.users().await {
+                Ok(users) => self.users = users,
+                Err(err) => self.show_error(
+                    "A apărut o eroare în timpul obținerii listei de utilizatori",
+                    &err,
+                ),
+            }
+        } else {
+            self.refresh_borrowed().await;
+        }
+    }
+
+    async fn refresh_borrowed(&mut self) {
+        let Some(account) = &self.account else {
+            return;
+        };
+
+        match self.client.borrowed_by(account.id).await {
+            Ok(borrowed) => self.borrowed = borrowed,
+            Err(err) => self.show_error("Obținerea listei de cărți împrumutate a eșuat", &err),
+        }
+    }
+
+    async fn refresh_loans(&mut self) {
+        match self.client.loans().await {
+            Ok(loans) => self.loans = loans,
+            Err(err) => self.show_error(
+                "A apărut o eroare în timpul obținerii listei de împrumuturi",
+                &err,
+            ),
+        }
+    }
+
+    async fn borrow(&mut self, book_id: BookId, reader: Option<UserId>) {
+        let loan = NewLoan {
+            book_id,
+            user_id: reader,
+            reading_list_id: None,
+        };
+
+        match self.client.borrow(&loan).await {
+            Ok(loan) => {
+                self.show(format!(
+                    "Ai împrumutat „{}” până la {}",
+                    self.title_of(book_id),
+                    format_date(loan.valid_until)
+                ));
+                self.refresh().await;
+            }
+            Err(err) => self.show_error("Împrumutul nu a putut fi înregistrat", &err),
+        }
+    }
+
+    /// Checks the book out to the reader with the given id or email
+    async fn check_out(&mut self, book_id: BookId, reader: &str) {
+        let user_id = reader.parse().ok().or_else(|| {
+            self.users
+                .iter()
+                .find(|user| user.email == reader)
+                .map(|user| user.id)
+        });
+        let Some(user_id) = user_id else {
+            self.status = Some(Status {
+                text: "Trebuie să alegi un cititor".into(),
+                is_error: true,
+            });
+            return;
+        };
+
+        let loan = NewLoan {
+            book_id,
+            user_id: Some(user_id),
+            reading_list_id: None,
+        };
+        match self.client.borrow(&loan).await {
+            Ok(_) => {
+                self.show(format!(
+                    "„{}” a fost împrumutată lui {}",
+                    self.title_of(book_id),
+                    self.name_of(user_id)
+                ));
+                self.refresh().await;
+            }
+            Err(err) => self.show_error("Împrumutul nu a putut fi înregistrat", &err),
+        }
+    }
+
+    async fn update_chapters_read(&mut self, borrow_id: BorrowId, chapters_read: Integer) {
+        let changes = LoanChanges {
+            chapters_read: Some(chapters_read),
+            ..LoanChanges::default()
+        };
+
+        match self.client.update_loan(borrow_id, &changes).await {
+            Ok(()) => self.refresh_borrowed().await,
+            Err(err) => self.show_error("Modificarea numărului de capitole citite a eșuat", &err),
+        }
+    }
+
+    async fn run(&mut self, action: Action) {
+        let (result, done, failed) = match action {
+            Action::Return(borrow_id) => (
+                self.client.return_book(borrow_id).await,
+                "Cartea a fost înapoiată",
+                "Înapoierea cărții a eșuat",
+            ),
+            Action::Lengthen(borrow_id) => {
+                let changes = LoanChanges {
+                    lengthen_by_days: Some(LENGTHEN_DAYS),
+                    ..LoanChanges::default()
+                };
+                (
+                    self.client.update_loan(borrow_id, &changes).await,
+                    "Împrumutul a fost prelungit",
+                    "Prelungirea duratei împrumutului a eșuat",
+                )
+            }
+            Action::EndLoan(borrow_id) => {
+                let changes = LoanChanges {
+                    due_now: true,
+                    ..LoanChanges::default()
+                };
+                (
+                    self.client.update_loan(borrow_id, &changes).await,
+                    "Împrumutul a fost terminat",
+                    "Terminarea împrumutului a eșuat",
+                )
+            }
+            Action::DeleteBook(book_id) => (
+                self.client.delete_book(book_id).await,
+                "Cartea a fost ștearsă",
+                "Ștergerea cărții a eșuat",
+            ),
+            Action::Promote(user_id) => {
+                let changes = UserChanges {
+                    kind: UserKind::Librarian,
+                };
+                (
+                    self.client.update_user(user_id, &changes).await,
+                    "Utilizatorul a fost promovat",
+                    "Nu s-a putut realiza promovarea utilizatorului",
+                )
+            }
+            Action::DeleteUser(user_id) => (
+                self.client.delete_user(user_id).await,
+                "Contul a fost șters",
+                "Nu s-a putut realiza ștergerea contului",
+            ),
+        };
+
+        match result {
+            Ok(()) => {
+                self.show(done);
+                self.refresh().await;
+            }
+            Err(err) => self.show_error(failed, &err),
+        }
+    }
+}
+
+pub fn format_date(time: Timestamp) -> String {
+    time.to_utc()
+        .with_timezone(&Local)
+        .format("%d.%m.%Y")
+        .to_string()
+}
+
+/// Whole days left until `valid_until`, negative once it's past
+pub fn remaining_days(valid_until: Timestamp, now: Timestamp) -> Integer {
+    (valid_until.unix() - now.unix()) / DAY
+}
+
+/// When a loan is due, as the app says it
+pub fn due_label(valid_until: Timestamp, now: Timestamp) -> String {
+    let date = format_date(valid_until);
+
+    match remaining_days(valid_until, now) {
+        0 => format!("{date} (astăzi)"),
+        days if days > 0 => format!("{date} ({days} zile rămase)"),
+        days => format!("{date} (întârziere de {} zile)", -days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::KeyEvent;
+    use schema::books::Author;
+
+    use super::*;
+
+    fn app() -> App {
+        // Nothing is sent in these tests
+        let client = Client::new("http://127.0.0.1:9").unwrap();
+        App::new(client, "http://127.0.0.1:9".into())
+    }
+
+    fn book(book_id: Integer, title: &str, author: &str) -> Book {
+        Book {
+            book_id: BookId(book_id),
+            title: title.into(),
+            author: Author {
+                name: author.into(),
+                ..Author::default()
+            },
+            ..Book::default()
+        }
+    }
+
+    async fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code)).await;
+    }
+
+    #[test]
+    fn loans_say_how_long_is_left() {
+        let now = Timestamp::from_unix(1_000 * DAY).unwrap();
+        let days = |days: Integer| Timestamp::from_unix((1_000 + days) * DAY).unwrap();
+
+        assert!(due_label(days(3), now).ends_with("(3 zile rămase)"));
+        assert!(due_label(days(0), now).ends_with("(astăzi)"));
+        assert!(due_label(days(-2), now).ends_with("(întârziere de 2 zile)"));
+    }
+
+    #[tokio::test]
+    async fn librarians_and_readers_get_their_tabs() {
+        let mut app = app();
+        app.account = Some(LoginReply {
+            id: UserId(1),
+            kind: UserKind::Reader,
+        });
+        assert_eq!(app.tabs(), [Tab::Catalog, Tab::Borrowed]);
+
+        app.account = Some(LoginReply {
+            id: UserId(1),
+            kind: UserKind::Librarian,
+        });
+        press(&mut app, KeyCode::BackTab).await;
+        assert_eq!(app.current_tab(), Tab::Users);
+        press(&mut app, KeyCode::Tab).await;
+        assert_eq!(app.current_tab(), Tab::Catalog);
+    }
+
+    #[tokio::test]
+    async fn searching_narrows_the_catalog() {
+        let mut app = app();
+        app.account = Some(LoginReply {
+            id: UserId(1),
+            kind: UserKind::Librarian,
+        });
+        app.books = vec![
+            book(1, "Ion", "Liviu Rebreanu"),
+            book(2, "Baltagul", "Mihail Sadoveanu"),
+            book(3, "Răscoala", "Liviu Rebreanu"),
+        ];
+        press(&mut app, KeyCode::End).await;
+        assert_eq!(app.selection(), Some(2));
+
+        press(&mut app, KeyCode::Char('/')).await;
+        for c in "rebreanu".chars() {
+            press(&mut app, KeyCode::Char(c)).await;
+        }
+        press(&mut app, KeyCode::Enter).await;
+        let titles: Vec<_> = app.visible_books().iter().map(|book| &book.title).collect();
+        assert_eq!(titles, ["Ion", "Răscoala"]);
+        assert_eq!(app.selection(), Some(0));
+
+        press(&mut app, KeyCode::Down).await;
+        press(&mut app, KeyCode::Char('d')).await;
+        assert!(matches!(
+            app.prompt,
+            Some(Prompt::Confirm {
+                action: Action::DeleteBook(BookId(3)),
+                ..
+            })
+        ));
+        press(&mut app, KeyCode::Char('n')).await;
+        assert_eq!(app.prompt, None);
+    }
+}
@@ -0,0 +1,54 @@
+//! `bdtui`, the app's reader and librarian views in a terminal, for the machines without a
+//! graphical session. It talks to the server's `/api/v1` like `bdctl`.
+
+use clap::Parser;
+use client::Client;
+use ratatui::{
+    crossterm::event::{self, Event, KeyEventKind},
+    DefaultTerminal,
+};
+
+use crate::app::App;
+
+mod app;
+mod ui;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Library terminal interface")]
+struct Args {
+    /// URL of the server
+    #[arg(
+        long,
+        env = "BIBLIOTECA_SERVER",
+        default_value = "http://localhost:3000"
+    )]
+    server: String,
+}
+
+// Requests are awaited between key presses, nothing else runs meanwhile
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let client = Client::new(&args.server)?;
+    let mut app = App::new(client, args.server);
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app).await;
+    ratatui::restore();
+
+    result
+}
+
+async fn run(terminal: &mut DefaultTerminal, app: &mut App) -> anyhow::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key).await;
+            }
+        }
+    }
+
+    Ok(())
+}
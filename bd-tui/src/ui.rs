@@ -0,0 +1,375 @@
+//! Drawing [`App`] on the terminal
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Tabs, Wrap},
+    Frame,
+};
+use schema::{dates::Timestamp, ids::UserKind};
+
+use crate::app::{due_label, remaining_days, App, LoginField, Prompt, Tab};
+
+const SELECTED: Style = Style::new().add_modifier(Modifier::REVERSED);
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    if app.account.is_none() {
+        draw_login(frame, app);
+    } else {
+        draw_main(frame, app);
+    }
+
+    if let Some(prompt) = &app.prompt {
+        draw_prompt(frame, app, prompt);
+    }
+}
+
+fn draw_login(frame: &mut Frame, app: &App) {
+    let area = centered(frame.area(), 50, 11);
+    let [server, email, password, status, help] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    frame.render_widget(
+        Paragraph::new(format!("Server: {}", app.server_url)).dark_gray(),
+        server,
+    );
+
+    let form = &app.login;
+    let fields = [
+        (email, "Email", form.email.clone(), LoginField::Email),
+        (
+            password,
+            "Parolă",
+            "*".repeat(form.password.chars().count()),
+            LoginField::Password,
+        ),
+    ];
+    for (area, title, text, field) in fields {
+        let mut block = Block::bordered().title(title);
+        if form.focus == field {
+            block = block.border_style(Style::new().yellow());
+            frame.set_cursor_position((area.x + 1 + text.chars().count() as u16, area.y + 1));
+        }
+        frame.render_widget(Paragraph::new(text).block(block), area);
+    }
+
+    draw_status(frame, app, status);
+    frame.render_widget(
+        Paragraph::new("Tab schimbă câmpul · Enter intră · Esc iese").dark_gray(),
+        help,
+    );
+}
+
+fn draw_main(frame: &mut Frame, app: &App) {
+    let [tabs, body, search, status, help] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(u16::from(app.searching || !app.search.is_empty())),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let titles = app.tabs().iter().map(|tab| tab.title());
+    frame.render_widget(
+        Tabs::new(titles)
+            .select(app.tab)
+            .highlight_style(Style::new().bold().yellow()),
+        tabs,
+    );
+
+    let now = Timestamp::now();
+    let (header, widths, rows) = match app.current_tab() {
+        Tab::Catalog => catalog(app),
+        Tab::Borrowed => borrowed(app, now),
+        Tab::Loans => loans(app, now),
+        Tab::Users => users(app),
+    };
+    let table = Table::new(rows, widths)
+        .header(Row::new(header).bold())
+        .row_highlight_style(SELECTED)
+        .block(Block::bordered());
+    let mut state = TableState::default().with_selected(app.selection());
+    frame.render_stateful_widget(table, body, &mut state);
+
+    if search.height > 0 {
+        frame.render_widget(Paragraph::new(format!("Caută: {}", app.search)), search);
+        if app.searching {
+            let x = search.x + "Caută: ".chars().count() as u16 + app.search.chars().count() as u16;
+            frame.set_cursor_position((x, search.y));
+        }
+    }
+
+    draw_status(frame, app, status);
+    let help_text = if app.searching {
+        help_text(app).to_string()
+    } else {
+        format!(
+            "{} · F5 reîmprospătează · o deconectare · q iese",
+            help_text(app)
+        )
+    };
+    frame.render_widget(Paragraph::new(help_text).dark_gray(), help);
+}
+
+type Columns<'a> = (Vec<&'static str>, Vec<Constraint>, Vec<Row<'a>>);
+
+fn catalog(app: &App) -> Columns<'_> {
+    let rows = app
+        .visible_books()
+        .into_iter()
+        .map(|book| {
+            let available = if book.can_be_borrowed {
+                Cell::from("Disponibilă").green()
+            } else {
+                Cell::from("Indisponibilă").red()
+            };
+            Row::new([
+                Cell::from(book.title.clone()),
+                Cell::from(book.author.name.clone()),
+                Cell::from(book.publish_date.to_string()),
+                available,
+            ])
+        })
+        .collect();
+
+    (
+        vec!["Titlu", "Autor", "Publicată", "Stare"],
+        vec![
+            Constraint::Fill(3),
+            Constraint::Fill(2),
+            Constraint::Length(11),
+            Constraint::Length(13),
+        ],
+        rows,
+    )
+}
+
+fn borrowed(app: &App, now: Timestamp) -> Columns<'_> {
+    let rows = app
+        .borrowed
+        .iter()
+        .map(|borrowed| {
+            Row::new([
+                Cell::from(app.title_of(borrowed.book_id)),
+                Cell::from(borrowed.chapters_read.to_string()),
+                due_cell(borrowed.valid_until, now),
+            ])
+        })
+        .collect();
+
+    (
+        vec!["Titlu", "Capitole citite", "De înapoiat pe"],
+        vec![
+            Constraint::Fill(1),
+            Constraint::Length(15),
+            Constraint::Length(34),
+        ],
+        rows,
+    )
+}
+
+fn loans(app: &App, now: Timestamp) -> Columns<'_> {
+    let rows = app
+        .loans
+        .iter()
+        .map(|loan| {
+            Row::new([
+                Cell::from(app.title_of(loan.book_id)),
+                Cell::from(app.name_of(loan.user_id)),
+                due_cell(loan.valid_until, now),
+            ])
+        })
+        .collect();
+
+    (
+        vec!["Titlu", "Cititor", "De înapoiat pe"],
+        vec![
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(34),
+        ],
+        rows,
+    )
+}
+
+fn users(app: &App) -> Columns<'_> {
+    let rows = app
+        .users
+        .iter()
+        .map(|user| {
+            let kind = match user.kind {
+                UserKind::Reader => "Cititor",
+                UserKind::Librarian => "Bibliotecar",
+            };
+            Row::new([
+                user.name.clone(),
+                user.email.clone(),
+                user.borrowed_book_count.to_string(),
+                kind.to_string(),
+            ])
+        })
+        .collect();
+
+    (
+        vec!["Nume", "Email", "Cărți împrumutate", "Tip"],
+        vec![
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(17),
+            Constraint::Length(11),
+        ],
+        rows,
+    )
+}
+
+/// Red once three days or fewer are left, like in the app
+fn due_cell(valid_until: Timestamp, now: Timestamp) -> Cell<'static> {
+    let cell = Cell::from(due_label(valid_until, now));
+
+    match remaining_days(valid_until, now) {
+        days if days < 0 => cell.red().bold(),
+        days if days <= 3 => cell.red(),
+        _ => cell,
+    }
+}
+
+fn help_text(app: &App) -> &'static str {
+    if app.searching {
+        return "Enter păstrează căutarea · Esc o anulează";
+    }
+
+    match (app.current_tab(), app.is_librarian()) {
+        (Tab::Catalog, false) => "/ caută · Enter detalii · b împrumută",
+        (Tab::Catalog, true) => "/ caută · Enter detalii · c împrumută unui cititor · d șterge",
+        (Tab::Borrowed, _) => "+/- capitole citite · r înapoiază",
+        (Tab::Loans, _) => "l prelungește cu 30 de zile · t termină împrumutul",
+        (Tab::Users, _) => "p promovează la bibliotecar · d șterge contul",
+    }
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(status) = &app.status {
+        let style = if status.is_error {
+            Style::new().red()
+        } else {
+            Style::new().green()
+        };
+        frame.render_widget(Paragraph::new(status.text.as_str()).style(style), area);
+    }
+}
+
+fn draw_prompt(frame: &mut Frame, app: &App, prompt: &Prompt) {
+    let (title, lines, help) = match prompt {
+        Prompt::Confirm { question, .. } => (
+            "Ești sigur?",
+            vec![Line::from(question.as_str())],
+            "d/Enter da · n/Esc nu",
+        ),
+        Prompt::Checkout { book_id, reader } => (
+            "Împrumută unui cititor",
+            vec![
+                Line::from(format!("Cartea: {}", app.title_of(*book_id))),
+                Line::default(),
+                Line::from(vec![
+                    Span::raw("Cititorul (id sau email): "),
+                    Span::raw(reader.as_str()).yellow(),
+                ]),
+            ],
+            "Enter împrumută · Esc renunță",
+        ),
+        Prompt::BookDetails(book_id) => {
+            let Some(book) = app.book(*book_id) else {
+                return;
+            };
+            let mut lines = vec![
+                Line::from(book.title.clone()).bold(),
+                Line::from(format!("de {}", book.author.name)),
+                Line::default(),
+                Line::from(format!(
+                    "Publicată în {} de {}",
+                    book.publish_date, book.publisher
+                )),
+                Line::from(format!("Exemplare: {}", book.count)),
+            ];
+            if let Some(series) = &book.series {
+                lines.push(Line::from(format!("Seria: {series}")));
+            }
+            if !book.genres.is_empty() {
+                lines.push(Line::from(format!("Genuri: {}", book.genres.join(", "))));
+            }
+            if !book.synopsis.is_empty() {
+                lines.push(Line::default());
+                lines.push(Line::from(book.synopsis.clone()));
+            }
+
+            let help = if app.is_librarian() {
+                "Esc închide"
+            } else {
+                "b împrumută · Esc închide"
+            };
+            ("Detalii", lines, help)
+        }
+    };
+
+    let area = centered(frame.area(), 70, lines.len() as u16 + 6);
+    let block = Block::bordered()
+        .title(title)
+        .title_bottom(Line::from(help).dark_gray())
+        .style(Style::new().bg(Color::Black));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
+    );
+}
+
+/// A rectangle of at most `width` by `height` in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use client::Client;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use super::*;
+
+    #[test]
+    fn the_password_is_hidden() {
+        let client = Client::new("http://127.0.0.1:9").unwrap();
+        let mut app = App::new(client, "http://127.0.0.1:9".into());
+        app.login.email = "ana@example.com".into();
+        app.login.password = "parola".into();
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(screen.contains("ana@example.com"));
+        assert!(screen.contains("******"));
+        assert!(!screen.contains("parola"));
+    }
+}
@@ -0,0 +1,44 @@
+INSERT INTO Authors(author_id, name, date_of_birth, date_of_death, description) VALUES
+    (1, 'Mihai Eminescu', '1850-01-15', '1889-06-15', 'Cel mai pare poet al României'),
+    (2, 'Howard Phillips Lovecraft', '1890-08-20', '1937-03-15', 'Scriitor american, cunoscut pentru literatura sa de groază, mai ales pentru Miturile lui Chtulhu'),
+    (3, 'Eiji Mikage', '1983-07-27', NULL, 'Scriitor japonez, cunoscut pentru The Empty Box and Zeroth Maria'),
+    (4, 'Ion Creangă', '1837-03-01', '1889-12-31', 'Povestitor român, cunoscut pentru Amintiri din Copilărie și Harap-Alb'),
+    (5, 'Ion Luca Caragiale', '1852-02-01', '1912-06-09', 'Dramaturg, nuvelist, pamfletar, poet, scriitor, director de teatru, comentator politic și ziarist român.');
+
+INSERT INTO Books(title, author_id, publish_date, publisher, count, synopsis, language) VALUES
+    ('The Empty Box and Zeroth Maria, vol. 1', 3, '2009-01-07', 'Yen Press', 3, 'Kazuki Hoshino meets Aya Otonashi, whose name is later revealed to be Maria', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 2', 3, '2010-11-17', 'Yen Press', 3, 'Kazuki Hoshino''s body is possesed by a mysterious force', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 3', 3, '2011-02-03', 'Yen Press', 0, 'Kazuki Hoshino and his friends are dragged into a death game', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 4', 3, '2012-01-01', 'Yen Press', 0, 'Kazuki Hoshino and his friends witness the conclusion of the death game', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 5', 3, '2013-08-03', 'Yen Press', 0, 'One of Kazuki Hoshino''s friends gets powers that allow him to control the minds of people', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 6', 3, '2014-11-11', 'Yen Press', 2, 'Kazuki Hoshino stops his friend''s evil plans of destroying the world', 'en'),
+    ('The Empty Box and Zeroth Maria, vol. 7', 3, '2015-06-10', 'Yen Press', 2, 'Kazuki Hoshino and Maria Otonashi marry', 'en'),
+    ('Colecție de poezii', 1, '2023-01-23', 'Editura Ciuperca', 15, 'O colectie de poezii scrise de Mihai Eminescu', 'ro'),
+    ('Dl. Goe', 5, '2018-01-01', 'Galaxia Copiilor', 10, 'O pretioasa lectie de viata ambalata in hohote de ras.', 'ro'),
+    ('Nuvele si teatru', 5, '2022-01-01', 'Rolcris', 10, 'O colectie de scrieri a lui I.L. Caragiale', 'ro'),
+    ('Amintiri din copilărie', 4, '2012-02-23', 'Gramar', 10, 'Cartea descrie intr-un mod spumos copilaria lui Ion Creanga', 'ro'),
+    ('The Complete Tales of H.P. Lovecraft', 2, '2019-10-10', 'Rock Point', 3, 'The Complete Tales of H.P. Lovecraft collects the author''s novel, four novellas, and fifty-three short stories.', 'en'),
+    ('Chemarea lui Cthulhu si alte povestiri', 2, '2019-11-02', 'Polirom', 6, 'O colecție de 12 povestiri ce conține nucleul universului mitic al lui H.P. Lovecraft', 'ro');
+
+UPDATE Books SET series = 'The Empty Box and Zeroth Maria'
+WHERE title LIKE 'The Empty Box and Zeroth Maria, vol. %';
+
+INSERT INTO Genres(name) VALUES
+    ('Light novel'),
+    ('Mister'),
+    ('Poezie'),
+    ('Umor'),
+    ('Teatru'),
+    ('Memorii'),
+    ('Groază'),
+    ('Fantastic');
+
+INSERT INTO BookGenres(book_id, genre_id)
+SELECT b.book_id, g.genre_id
+FROM Books b JOIN Genres g
+WHERE (b.series = 'The Empty Box and Zeroth Maria' AND g.name IN ('Light novel', 'Mister'))
+    OR (b.title = 'Colecție de poezii' AND g.name = 'Poezie')
+    OR (b.title = 'Dl. Goe' AND g.name = 'Umor')
+    OR (b.title = 'Nuvele si teatru' AND g.name IN ('Umor', 'Teatru'))
+    OR (b.title = 'Amintiri din copilărie' AND g.name IN ('Memorii', 'Umor'))
+    OR (b.author_id = 2 AND g.name IN ('Groază', 'Fantastic'));
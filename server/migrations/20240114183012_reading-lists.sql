@@ -0,0 +1,17 @@
+CREATE TABLE ReadingLists(
+    list_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    is_public INTEGER NOT NULL CHECK (is_public IN (0, 1)),
+    UNIQUE (user_id, name),
+    FOREIGN KEY (user_id) REFERENCES Users(user_id) ON DELETE CASCADE
+) STRICT;
+
+CREATE TABLE ReadingListEntries(
+    list_id INTEGER NOT NULL,
+    book_id INTEGER NOT NULL,
+    position INTEGER NOT NULL, -- entries are shown in ascending order of position
+    PRIMARY KEY (list_id, book_id),
+    FOREIGN KEY (list_id) REFERENCES ReadingLists(list_id) ON DELETE CASCADE,
+    FOREIGN KEY (book_id) REFERENCES Books(book_id) ON DELETE CASCADE
+) STRICT;
@@ -0,0 +1,53 @@
+ALTER TABLE Books ADD COLUMN series TEXT;
+
+CREATE TABLE Genres(
+    genre_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+) STRICT;
+
+CREATE TABLE BookGenres(
+    book_id INTEGER NOT NULL,
+    genre_id INTEGER NOT NULL,
+    PRIMARY KEY (book_id, genre_id),
+    FOREIGN KEY (book_id) REFERENCES Books(book_id) ON DELETE CASCADE,
+    FOREIGN KEY (genre_id) REFERENCES Genres(genre_id) ON DELETE CASCADE
+) STRICT;
+
+-- Rows in Borrows are deleted once a book is returned, so we keep a permanent record of who
+-- borrowed what in order to be able to tell which books tend to be read together
+CREATE TABLE BorrowHistory(
+    borrow_id INTEGER PRIMARY KEY,
+    book_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    borrowed_at INTEGER NOT NULL, -- this is actually a date
+    returned_at INTEGER, -- this is actually a date
+    FOREIGN KEY (book_id) REFERENCES Books(book_id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES Users(user_id) ON DELETE CASCADE
+) STRICT;
+
+INSERT INTO BorrowHistory(borrow_id, book_id, user_id, borrowed_at)
+SELECT borrow_id, book_id, user_id, unixepoch()
+FROM Borrows;
+
+CREATE TRIGGER record_borrow_in_history AFTER INSERT ON Borrows
+BEGIN
+    INSERT INTO BorrowHistory(borrow_id, book_id, user_id, borrowed_at)
+    VALUES (NEW.borrow_id, NEW.book_id, NEW.user_id, unixepoch());
+END;
+
+CREATE TRIGGER record_return_in_history AFTER DELETE ON Borrows
+BEGIN
+    UPDATE BorrowHistory
+    SET returned_at = unixepoch()
+    WHERE borrow_id = OLD.borrow_id;
+END;
+
+-- Precomputed item-to-item similarity, periodically rebuilt by the server
+CREATE TABLE BookSimilarities(
+    book_id INTEGER NOT NULL,
+    similar_book_id INTEGER NOT NULL,
+    score REAL NOT NULL,
+    PRIMARY KEY (book_id, similar_book_id),
+    FOREIGN KEY (book_id) REFERENCES Books(book_id) ON DELETE CASCADE,
+    FOREIGN KEY (similar_book_id) REFERENCES Books(book_id) ON DELETE CASCADE
+) STRICT;
@@ -0,0 +1,4 @@
+ALTER TABLE Users ADD COLUMN registered_at INTEGER; -- this is actually a date
+
+-- We don't know when existing accounts were created, so pretend it was today
+UPDATE Users SET registered_at = unixepoch();
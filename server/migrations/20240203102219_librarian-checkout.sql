@@ -0,0 +1,11 @@
+-- The librarian that lent the book at the desk, NULL if the reader borrowed it themselves
+ALTER TABLE Borrows ADD COLUMN checked_out_by INTEGER REFERENCES Users(user_id) ON DELETE SET NULL;
+ALTER TABLE BorrowHistory ADD COLUMN checked_out_by INTEGER REFERENCES Users(user_id) ON DELETE SET NULL;
+
+DROP TRIGGER record_borrow_in_history;
+
+CREATE TRIGGER record_borrow_in_history AFTER INSERT ON Borrows
+BEGIN
+    INSERT INTO BorrowHistory(borrow_id, book_id, user_id, borrowed_at, checked_out_by)
+    VALUES (NEW.borrow_id, NEW.book_id, NEW.user_id, unixepoch(), NEW.checked_out_by);
+END;
@@ -0,0 +1,13 @@
+-- Responses to requests sent with an `Idempotency-Key` header, replayed if the request is repeated
+CREATE TABLE IdempotencyKeys(
+    idempotency_key TEXT PRIMARY KEY NOT NULL,
+    -- SHA-256 of the method, path and body of the request the key was first used for
+    fingerprint TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- All NULL while the first request is still being handled
+    status INTEGER,
+    content_type TEXT,
+    body BLOB
+) STRICT;
+
+CREATE INDEX IdempotencyKeysByAge ON IdempotencyKeys(created_at);
@@ -0,0 +1,34 @@
+-- Authors' lives and publications are dated with partial dates, like `1850-01-15`, `1850-01`,
+-- `1850` or `1850~` (circa 1850), instead of seconds since the Unix epoch. The old values were
+-- midnight in UTC or in the librarian's timezone, so they're rounded to the nearest day.
+
+-- The CHECK on the authors' dates can't be dropped in place, so the table is rebuilt. This needs
+-- foreign keys to be off, which `SqliteStore::migrate` takes care of.
+CREATE TABLE NewAuthors(
+    author_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    date_of_birth TEXT NOT NULL,
+    date_of_death TEXT,
+    description TEXT NOT NULL
+) STRICT;
+
+INSERT INTO NewAuthors(author_id, name, date_of_birth, date_of_death, description)
+SELECT
+    author_id,
+    name,
+    strftime('%Y-%m-%d', date_of_birth + 12 * 60 * 60, 'unixepoch'),
+    strftime('%Y-%m-%d', date_of_death + 12 * 60 * 60, 'unixepoch'),
+    description
+FROM Authors;
+
+-- Keeps the ids of deleted authors from being handed out again
+DELETE FROM sqlite_sequence WHERE name = 'NewAuthors';
+UPDATE sqlite_sequence SET name = 'NewAuthors' WHERE name = 'Authors';
+
+DROP TABLE Authors;
+ALTER TABLE NewAuthors RENAME TO Authors;
+
+ALTER TABLE Books ADD COLUMN new_publish_date TEXT NOT NULL DEFAULT '';
+UPDATE Books SET new_publish_date = strftime('%Y-%m-%d', publish_date + 12 * 60 * 60, 'unixepoch');
+ALTER TABLE Books DROP COLUMN publish_date;
+ALTER TABLE Books RENAME COLUMN new_publish_date TO publish_date;
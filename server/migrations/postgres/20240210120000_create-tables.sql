@@ -0,0 +1,114 @@
+-- The whole schema as it stood when PostgreSQL support was added. Dates are stored as seconds
+-- since the Unix epoch, like in SQLite, so that both backends hand out the same values.
+
+CREATE TABLE Authors(
+    author_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    date_of_birth BIGINT NOT NULL,
+    date_of_death BIGINT,
+    description TEXT NOT NULL,
+    CHECK (date_of_death IS NULL OR date_of_death > date_of_birth)
+);
+
+CREATE TABLE Books(
+    book_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    title TEXT NOT NULL,
+    author_id BIGINT NOT NULL REFERENCES Authors(author_id),
+    publish_date BIGINT NOT NULL,
+    publisher TEXT NOT NULL,
+    count BIGINT NOT NULL CHECK (count >= 0),
+    synopsis TEXT NOT NULL,
+    language TEXT NOT NULL CHECK (language IN ('ro', 'en')),
+    series TEXT
+);
+
+CREATE TABLE Users(
+    user_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    type BIGINT NOT NULL CHECK (type = 1 OR type = 2), -- regular user = 1, librarian = 2
+    email TEXT UNIQUE NOT NULL,
+    password TEXT NOT NULL,
+    registered_at BIGINT
+);
+
+CREATE TABLE Borrows(
+    borrow_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    book_id BIGINT NOT NULL REFERENCES Books(book_id),
+    user_id BIGINT NOT NULL REFERENCES Users(user_id),
+    -- The librarian that lent the book at the desk, NULL if the reader borrowed it themselves
+    checked_out_by BIGINT REFERENCES Users(user_id) ON DELETE SET NULL
+);
+
+CREATE TABLE BorrowData(
+    borrow_id BIGINT NOT NULL UNIQUE REFERENCES Borrows(borrow_id),
+    valid_until BIGINT NOT NULL,
+    chapters_read BIGINT NOT NULL
+);
+
+CREATE TABLE ReadingLists(
+    list_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES Users(user_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    is_public BOOLEAN NOT NULL,
+    UNIQUE (user_id, name)
+);
+
+CREATE TABLE ReadingListEntries(
+    list_id BIGINT NOT NULL REFERENCES ReadingLists(list_id) ON DELETE CASCADE,
+    book_id BIGINT NOT NULL REFERENCES Books(book_id) ON DELETE CASCADE,
+    position BIGINT NOT NULL, -- entries are shown in ascending order of position
+    PRIMARY KEY (list_id, book_id)
+);
+
+CREATE TABLE Genres(
+    genre_id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE BookGenres(
+    book_id BIGINT NOT NULL REFERENCES Books(book_id) ON DELETE CASCADE,
+    genre_id BIGINT NOT NULL REFERENCES Genres(genre_id) ON DELETE CASCADE,
+    PRIMARY KEY (book_id, genre_id)
+);
+
+-- Rows in Borrows are deleted once a book is returned, so we keep a permanent record of who
+-- borrowed what in order to be able to tell which books tend to be read together
+CREATE TABLE BorrowHistory(
+    borrow_id BIGINT PRIMARY KEY,
+    book_id BIGINT NOT NULL REFERENCES Books(book_id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES Users(user_id) ON DELETE CASCADE,
+    borrowed_at BIGINT NOT NULL,
+    returned_at BIGINT,
+    checked_out_by BIGINT REFERENCES Users(user_id) ON DELETE SET NULL
+);
+
+CREATE FUNCTION record_borrow_in_history() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO BorrowHistory(borrow_id, book_id, user_id, borrowed_at, checked_out_by)
+    VALUES (NEW.borrow_id, NEW.book_id, NEW.user_id, extract(epoch FROM now())::BIGINT, NEW.checked_out_by);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_borrow_in_history AFTER INSERT ON Borrows
+FOR EACH ROW EXECUTE FUNCTION record_borrow_in_history();
+
+CREATE FUNCTION record_return_in_history() RETURNS trigger AS $$
+BEGIN
+    UPDATE BorrowHistory
+    SET returned_at = extract(epoch FROM now())::BIGINT
+    WHERE borrow_id = OLD.borrow_id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_return_in_history AFTER DELETE ON Borrows
+FOR EACH ROW EXECUTE FUNCTION record_return_in_history();
+
+-- Precomputed item-to-item similarity, periodically rebuilt by the server
+CREATE TABLE BookSimilarities(
+    book_id BIGINT NOT NULL REFERENCES Books(book_id) ON DELETE CASCADE,
+    similar_book_id BIGINT NOT NULL REFERENCES Books(book_id) ON DELETE CASCADE,
+    score DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (book_id, similar_book_id)
+);
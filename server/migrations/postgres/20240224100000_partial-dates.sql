@@ -0,0 +1,14 @@
+-- Authors' lives and publications are dated with partial dates, like `1850-01-15`, `1850-01`,
+-- `1850` or `1850~` (circa 1850), instead of seconds since the Unix epoch. The old values were
+-- midnight in UTC or in the librarian's timezone, so they're rounded to the nearest day.
+ALTER TABLE Authors DROP CONSTRAINT authors_check;
+
+ALTER TABLE Authors
+    ALTER COLUMN date_of_birth TYPE TEXT
+        USING to_char(to_timestamp(date_of_birth + 12 * 60 * 60) AT TIME ZONE 'UTC', 'YYYY-MM-DD'),
+    ALTER COLUMN date_of_death TYPE TEXT
+        USING to_char(to_timestamp(date_of_death + 12 * 60 * 60) AT TIME ZONE 'UTC', 'YYYY-MM-DD');
+
+ALTER TABLE Books
+    ALTER COLUMN publish_date TYPE TEXT
+        USING to_char(to_timestamp(publish_date + 12 * 60 * 60) AT TIME ZONE 'UTC', 'YYYY-MM-DD');
@@ -0,0 +1,28 @@
+#!/bin/sh
+# Runs the server's tests against a throwaway PostgreSQL server, which is deleted afterwards.
+# Needs the PostgreSQL server binaries (initdb, pg_ctl) on the PATH. Arguments are passed on to
+# `cargo test`.
+set -eu
+
+port=${PGPORT_TEST:-54329}
+dir=$(mktemp -d)
+
+# PostgreSQL refuses to run as root
+run_pg=""
+if [ "$(id -u)" = 0 ]; then
+    chown postgres "$dir"
+    run_pg="runuser -u postgres --"
+fi
+
+cleanup() {
+    $run_pg pg_ctl -D "$dir/data" -m immediate stop >/dev/null 2>&1 || true
+    rm -rf "$dir"
+}
+trap cleanup EXIT
+
+$run_pg initdb -D "$dir/data" -U biblioteca --auth=trust >/dev/null
+$run_pg pg_ctl -D "$dir/data" -l "$dir/log" -w \
+    -o "-p $port -k $dir -c listen_addresses=localhost" start >/dev/null
+
+cd "$(dirname "$0")/.."
+BIBLIOTECA_TEST_POSTGRES_URL="postgres://biblioteca@localhost:$port/postgres" cargo test "$@"
@@ -0,0 +1,310 @@
+//! Administrative commands, which work directly on the database and don't need the server to be
+//! running
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::{Args, Subcommand};
+use schema::{
+    dates::Timestamp,
+    ids::{UserId, UserKind},
+};
+
+use crate::{
+    backup,
+    config::{BackupConfig, Config},
+    service::users::hash_password,
+    store::{Backend, NewUser, SqliteStore, Store},
+};
+
+#[derive(Subcommand, Debug)]
+pub enum AdminCommand {
+    /// Create a new librarian account
+    CreateLibrarian {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        /// Read from the standard input if not given
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Make an existing user a librarian
+    Promote { email: String },
+    /// Set a new password for a user
+    ResetPassword {
+        email: String,
+        /// Read from the standard input if not given
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// List all users
+    ListUsers,
+    /// Apply pending migrations and list the applied ones
+    Migrate,
+    /// Check the database for corruption and broken references
+    Check,
+    /// Back up the database, it's safe to do while the server is running
+    Backup {
+        /// Where to write the backup, by default it goes in the backup directory like the
+        /// scheduled ones
+        destination: Option<PathBuf>,
+    },
+}
+
+/// The arguments of `restore`, which unlike the other administrative commands runs without the
+/// database open
+#[derive(Args, Debug)]
+pub struct RestoreArgs {
+    /// The backup file to restore, by default the latest one in the backup directory
+    #[arg(conflicts_with = "at")]
+    pub backup: Option<PathBuf>,
+    /// Restore the latest backup made at or before this time, either RFC 3339 or a local
+    /// `YYYY-MM-DD[ HH:MM[:SS]]`
+    #[arg(long)]
+    pub at: Option<String>,
+}
+
+pub async fn run(store: &dyn Store, config: &Config, command: AdminCommand) -> anyhow::Result<()> {
+    match command {
+        AdminCommand::CreateLibrarian {
+            name,
+            email,
+            password,
+        } => create_librarian(store, &name, &email, password).await,
+        AdminCommand::Promote { email } => promote(store, &email).await,
+        AdminCommand::ResetPassword { email, password } => {
+            reset_password(store, &email, password).await
+        }
+        AdminCommand::ListUsers => list_users(store).await,
+        AdminCommand::Migrate => list_migrations(store).await,
+        AdminCommand::Check => {
+            let problems = store.check_integrity().await?;
+            if !problems.is_empty() {
+                bail!("The database has problems:\n{}", problems.join("\n"));
+            }
+
+            println!("No problems found");
+            Ok(())
+        }
+        AdminCommand::Backup { destination } => backup(store, &config.backups, destination).await,
+    }
+}
+
+fn password_or_prompt(password: Option<String>) -> anyhow::Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            eprint!("Password: ");
+            io::stderr().flush()?;
+
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    if password.is_empty() {
+        bail!("The password can't be empty");
+    }
+
+    Ok(password)
+}
+
+async fn create_librarian(
+    store: &dyn Store,
+    name: &str,
+    email: &str,
+    password: Option<String>,
+) -> anyhow::Result<()> {
+    let password_hash =
+        hash_password(&password_or_prompt(password)?).map_err(anyhow::Error::msg)?;
+
+    let user = NewUser {
+        name: name.to_string(),
+        kind: UserKind::Librarian,
+        email: email.to_string(),
+        password_hash,
+        registered_at: Timestamp::now(),
+    };
+    let user_id = store.create_user(&user).await.map_err(|err| match err {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            anyhow::anyhow!("There already is an account with the email {email}")
+        }
+        err => err.into(),
+    })?;
+
+    println!("Created librarian {name} with id {user_id}");
+    Ok(())
+}
+
+async fn user_id_by_email(store: &dyn Store, email: &str) -> anyhow::Result<UserId> {
+    store
+        .user_id_by_email(email)
+        .await?
+        .with_context(|| format!("No user with the email {email}"))
+}
+
+async fn promote(store: &dyn Store, email: &str) -> anyhow::Result<()> {
+    let user_id = user_id_by_email(store, email).await?;
+    store.set_user_kind(user_id, UserKind::Librarian).await?;
+
+    println!("{email} is now a librarian");
+    Ok(())
+}
+
+async fn reset_password(
+    store: &dyn Store,
+    email: &str,
+    password: Option<String>,
+) -> anyhow::Result<()> {
+    let password_hash =
+        hash_password(&password_or_prompt(password)?).map_err(anyhow::Error::msg)?;
+
+    let user_id = user_id_by_email(store, email).await?;
+    store.set_password(user_id, &password_hash).await?;
+
+    println!("Changed the password of {email}");
+    Ok(())
+}
+
+async fn list_users(store: &dyn Store) -> anyhow::Result<()> {
+    let users = store.users().await?;
+
+    println!(
+        "{:>6}  {:<10}  {:<10}  {:<30}  NAME",
+        "ID", "KIND", "REGISTERED", "EMAIL"
+    );
+    for user in users {
+        let kind = if user.kind == UserKind::Librarian {
+            "librarian"
+        } else {
+            "reader"
+        };
+        let registered_at = user.registered_at.map_or(String::new(), |time| {
+            time.to_utc().format("%Y-%m-%d").to_string()
+        });
+
+        println!(
+            "{:>6}  {:<10}  {:<10}  {:<30}  {}",
+            user.user_id, kind, registered_at, user.email, user.name
+        );
+    }
+
+    Ok(())
+}
+
+async fn list_migrations(store: &dyn Store) -> anyhow::Result<()> {
+    for (version, description) in store.applied_migrations().await? {
+        println!("{version} {description}");
+    }
+    println!("The database is up to date");
+
+    Ok(())
+}
+
+async fn backup(
+    store: &dyn Store,
+    config: &BackupConfig,
+    destination: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let (path, sha256) = match destination {
+        Some(destination) => {
+            let sha256 = backup::backup_to(store, &destination).await?;
+            (destination, sha256)
+        }
+        None => {
+            let backup = backup::create_backup(store, config).await?;
+            (config.dir.join(backup.name), backup.sha256)
+        }
+    };
+
+    println!("Backed up the database to {} ({sha256})", path.display());
+    Ok(())
+}
+
+/// Parses a moment given either in RFC 3339 or as a local `YYYY-MM-DD[ HH:MM[:SS]]`
+fn parse_time(time: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+        })
+        .with_context(|| format!("Can't make sense of the time '{time}'"))?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .with_context(|| format!("{time} doesn't exist in the local timezone"))
+}
+
+/// Replaces the database with a backup, after making sure that the backup isn't damaged. Unless
+/// a backup file is given, the latest backup made at or before `at` is used.
+pub async fn restore(
+    config: &Config,
+    backup: Option<&Path>,
+    at: Option<&str>,
+) -> anyhow::Result<()> {
+    let Backend::Sqlite(database) = Backend::from_setting(&config.database) else {
+        bail!("Only SQLite databases can be restored, use pg_restore for PostgreSQL");
+    };
+    let database = database.as_path();
+    let backup = match backup {
+        Some(backup) => backup.to_owned(),
+        None => backup::find_backup(&config.backups.dir, at.map(parse_time).transpose()?)?,
+    };
+    let backup = backup.as_path();
+
+    if !backup::verify_checksum(backup).await? {
+        eprintln!(
+            "Warning: {} has no checksum, only its integrity will be checked",
+            backup.display()
+        );
+    }
+
+    let backup_store = SqliteStore::open_read_only(backup)
+        .await
+        .with_context(|| format!("Failed to open {}", backup.display()))?;
+
+    let problems = backup_store.check_integrity().await?;
+    backup_store.close().await;
+
+    if !problems.is_empty() {
+        bail!(
+            "Refusing to restore a damaged backup:\n{}",
+            problems.join("\n")
+        );
+    }
+
+    // Copy next to the database first, so that the database is never left half written
+    let mut staging = database.as_os_str().to_owned();
+    staging.push(".restoring");
+    fs::copy(backup, &staging)?;
+    fs::rename(&staging, database)?;
+
+    // The write-ahead log of the old database doesn't apply to the restored one
+    for suffix in ["-wal", "-shm"] {
+        let mut path = database.as_os_str().to_owned();
+        path.push(suffix);
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+
+    println!("Restored {} from {}", database.display(), backup.display());
+    Ok(())
+}
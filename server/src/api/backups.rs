@@ -0,0 +1,56 @@
+use axum::{extract::State, http::StatusCode, Json};
+use schema::backups::{Backup, BackupsReply};
+
+use super::Requester;
+use crate::{
+    backup::{create_backup, describe_backups},
+    config::BackupConfig,
+    error::{IntoRouteError, RouteError},
+    service,
+    store::DynStore,
+};
+
+#[utoipa::path(
+    get,
+    path = "/backups",
+    tag = "backups",
+    responses(
+        (status = 200, description = "The backups on disk", body = [Backup]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn backups(
+    requester: Requester,
+    State(store): State<DynStore>,
+    State(config): State<BackupConfig>,
+) -> Result<Json<BackupsReply>, RouteError> {
+    service::users::require_librarian(&*store, requester.id).await?;
+
+    let backups = describe_backups(&config.dir).http_internal_error("Failed to list backups")?;
+
+    Ok(Json(backups))
+}
+
+#[utoipa::path(
+    post,
+    path = "/backups",
+    operation_id = "create_backup",
+    tag = "backups",
+    responses(
+        (status = 201, description = "The new backup", body = Backup),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn create(
+    requester: Requester,
+    State(store): State<DynStore>,
+    State(config): State<BackupConfig>,
+) -> Result<(StatusCode, Json<Backup>), RouteError> {
+    service::users::require_librarian(&*store, requester.id).await?;
+
+    let backup = create_backup(&*store, &config)
+        .await
+        .http_internal_error("Failed to back up the database")?;
+
+    Ok((StatusCode::CREATED, Json(backup)))
+}
@@ -0,0 +1,255 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use schema::{
+    books::{Author, AuthorFields, Book, BookFields, RecommendationsReply, SimilarBooksReply},
+    ids::BookId,
+};
+
+use super::Requester;
+use crate::{
+    books,
+    conditional::{CatalogReply, CatalogRevision},
+    error::RouteError,
+    events::Events,
+    recommendations, service,
+    store::{BookDetails, DynStore, NewAuthor},
+    validation::ValidJson,
+};
+
+fn details(fields: BookFields) -> BookDetails {
+    BookDetails {
+        title: fields.title,
+        author_id: fields.author_id,
+        publish_date: fields.publish_date,
+        publisher: fields.publisher,
+        count: fields.count,
+        synopsis: fields.synopsis,
+        language: "ro".into(),
+        series: fields.series,
+        genres: fields.genres,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/books",
+    tag = "books",
+    params(
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of the catalogue the client already has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The `Last-Modified` of the catalogue the client already has, ignored if `If-None-Match` is sent"),
+    ),
+    responses(
+        (status = 200, description = "The whole catalogue", body = [Book], headers(
+            ("ETag" = String, description = "The revision of the catalogue, which changes whenever a book is saved or deleted, an author is added, or a copy is lent or returned"),
+            ("Last-Modified" = String, description = "When the catalogue last changed"),
+        )),
+        (status = 304, description = "The catalogue didn't change since the client fetched it"),
+    ),
+)]
+pub async fn books(
+    store: State<DynStore>,
+    catalog: State<CatalogRevision>,
+    headers: HeaderMap,
+) -> Result<CatalogReply<Vec<Book>>, RouteError> {
+    books::books(store, catalog, headers).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    responses(
+        (status = 200, description = "The book", body = Book),
+    ),
+)]
+pub async fn book(
+    Path(book_id): Path<BookId>,
+    State(store): State<DynStore>,
+) -> Result<Json<Book>, RouteError> {
+    Ok(Json(service::books::book(&*store, book_id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/books",
+    operation_id = "create_book",
+    tag = "books",
+    request_body = BookFields,
+    responses(
+        (status = 201, description = "The new book", body = Book),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn create(
+    requester: Requester,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+    ValidJson(fields): ValidJson<BookFields>,
+) -> Result<(StatusCode, Json<Book>), RouteError> {
+    let book_id =
+        service::books::save_book(&*store, &events, requester.id, None, details(fields)).await?;
+    let book = service::books::book(&*store, book_id).await?;
+
+    Ok((StatusCode::CREATED, Json(book)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/books/{book_id}",
+    operation_id = "update_book",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    request_body = BookFields,
+    responses(
+        (status = 200, description = "The changed book", body = Book),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn update(
+    requester: Requester,
+    Path(book_id): Path<BookId>,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+    ValidJson(fields): ValidJson<BookFields>,
+) -> Result<Json<Book>, RouteError> {
+    service::books::save_book(
+        &*store,
+        &events,
+        requester.id,
+        Some(book_id),
+        details(fields),
+    )
+    .await?;
+    let book = service::books::book(&*store, book_id).await?;
+
+    Ok(Json(book))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/books/{book_id}",
+    operation_id = "delete_book",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn delete(
+    requester: Requester,
+    Path(book_id): Path<BookId>,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+) -> Result<StatusCode, RouteError> {
+    service::books::delete_book(&*store, &events, requester.id, book_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}/similar",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    responses(
+        (status = 200, description = "Books borrowed by the readers of this one", body = [Recommendation]),
+    ),
+)]
+pub async fn similar(
+    book_id: Path<BookId>,
+    store: State<DynStore>,
+) -> Result<Json<SimilarBooksReply>, RouteError> {
+    recommendations::similar_books(book_id, store).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/recommendations",
+    tag = "books",
+    responses(
+        (status = 200, description = "Books the requester might like", body = [Recommendation]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn recommendations(
+    requester: Requester,
+    State(store): State<DynStore>,
+) -> Result<Json<RecommendationsReply>, RouteError> {
+    Ok(Json(
+        recommendations::recommend(&*store, requester.id).await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/authors",
+    tag = "books",
+    params(
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of the catalogue the client already has"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The `Last-Modified` of the catalogue the client already has, ignored if `If-None-Match` is sent"),
+    ),
+    responses(
+        (status = 200, description = "Every author", body = [Author], headers(
+            ("ETag" = String, description = "The revision of the catalogue, shared with `GET /books`"),
+            ("Last-Modified" = String, description = "When the catalogue last changed"),
+        )),
+        (status = 304, description = "The catalogue didn't change since the client fetched it"),
+    ),
+)]
+pub async fn authors(
+    store: State<DynStore>,
+    catalog: State<CatalogRevision>,
+    headers: HeaderMap,
+) -> Result<CatalogReply<Vec<Author>>, RouteError> {
+    books::authors(store, catalog, headers).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/authors",
+    tag = "books",
+    request_body = AuthorFields,
+    responses(
+        (status = 201, description = "The new author", body = Author),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn add_author(
+    requester: Requester,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+    ValidJson(fields): ValidJson<AuthorFields>,
+) -> Result<(StatusCode, Json<Author>), RouteError> {
+    let author = NewAuthor {
+        name: fields.name,
+        date_of_birth: fields.date_of_birth,
+        date_of_death: fields.date_of_death,
+        description: fields.description,
+    };
+    let author_id =
+        service::books::add_author(&*store, &events, requester.id, None, &author).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Author {
+            author_id,
+            name: author.name,
+            date_of_birth: author.date_of_birth,
+            date_of_death: author.date_of_death,
+            description: author.description,
+        }),
+    ))
+}
@@ -0,0 +1,44 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+};
+use schema::events::Event;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+
+use super::Requester;
+use crate::events::{self, Events};
+
+/// Streams the changes to the library as they're committed, until the client disconnects
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    responses(
+        (status = 200, description = "Server-Sent Events named after their `type`, with the event as JSON in their `data`. Readers only get the changes to the catalogue and to their own account and loans.", content_type = "text/event-stream", body = Event),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn events(
+    requester: Requester,
+    State(events): State<Events>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(move |received| {
+        let event = match received {
+            Ok(event) => event,
+            Err(BroadcastStreamRecvError::Lagged(_)) => Event::Missed,
+        };
+        if !events::is_visible(&event, requester.id, requester.kind) {
+            return None;
+        }
+
+        let data = serde_json::to_string(&event).expect("events are always serializable");
+        Some(Ok(SseEvent::default().event(event.name()).data(data)))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
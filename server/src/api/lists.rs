@@ -0,0 +1,183 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use schema::{
+    ids::{BookId, UserId},
+    lists::{ReadingList, ReadingListFields, ReadingListsReply},
+    Integer,
+};
+
+use super::Requester;
+use crate::{error::RouteError, service, store::DynStore};
+
+/// The reading lists of `user_id`, only the public ones unless they're the requester's own
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/reading-lists",
+    tag = "reading-lists",
+    params(
+        ("user_id" = i64, Path, description = "Id of the user"),
+    ),
+    responses(
+        (status = 200, description = "The user's reading lists", body = [ReadingList]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn reading_lists(
+    requester: Requester,
+    Path(user_id): Path<UserId>,
+    State(store): State<DynStore>,
+) -> Result<Json<ReadingListsReply>, RouteError> {
+    let lists = service::lists::reading_lists(&*store, requester.id, user_id).await?;
+
+    Ok(Json(lists))
+}
+
+/// Puts the books on the list in the given order
+#[utoipa::path(
+    post,
+    path = "/reading-lists",
+    operation_id = "create_reading_list",
+    tag = "reading-lists",
+    request_body = ReadingListFields,
+    responses(
+        (status = 201, description = "The new list", body = ReadingList),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn create(
+    requester: Requester,
+    State(store): State<DynStore>,
+    Json(fields): Json<ReadingListFields>,
+) -> Result<(StatusCode, Json<ReadingList>), RouteError> {
+    let list = service::lists::create(&*store, requester.id, fields.name, fields.is_public).await?;
+
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/reading-lists/{list_id}",
+    operation_id = "update_reading_list",
+    tag = "reading-lists",
+    params(
+        ("list_id" = i64, Path, description = "Id of the reading list"),
+    ),
+    request_body = ReadingListFields,
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn update(
+    requester: Requester,
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(fields): Json<ReadingListFields>,
+) -> Result<StatusCode, RouteError> {
+    service::lists::change(
+        &*store,
+        requester.id,
+        list_id,
+        &fields.name,
+        fields.is_public,
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/reading-lists/{list_id}",
+    operation_id = "delete_reading_list",
+    tag = "reading-lists",
+    params(
+        ("list_id" = i64, Path, description = "Id of the reading list"),
+    ),
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn delete(
+    requester: Requester,
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+) -> Result<StatusCode, RouteError> {
+    service::lists::delete(&*store, requester.id, list_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/reading-lists/{list_id}/books",
+    tag = "reading-lists",
+    params(
+        ("list_id" = i64, Path, description = "Id of the reading list"),
+    ),
+    request_body = [Integer],
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn reorder(
+    requester: Requester,
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(book_ids): Json<Vec<BookId>>,
+) -> Result<StatusCode, RouteError> {
+    service::lists::reorder(&*store, requester.id, list_id, &book_ids).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/reading-lists/{list_id}/books/{book_id}",
+    tag = "reading-lists",
+    params(
+        ("list_id" = i64, Path, description = "Id of the reading list"),
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn add_book(
+    requester: Requester,
+    Path((list_id, book_id)): Path<(Integer, BookId)>,
+    State(store): State<DynStore>,
+) -> Result<StatusCode, RouteError> {
+    service::lists::add_book(&*store, requester.id, list_id, book_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/reading-lists/{list_id}/books/{book_id}",
+    tag = "reading-lists",
+    params(
+        ("list_id" = i64, Path, description = "Id of the reading list"),
+        ("book_id" = i64, Path, description = "Id of the book"),
+    ),
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn remove_book(
+    requester: Requester,
+    Path((list_id, book_id)): Path<(Integer, BookId)>,
+    State(store): State<DynStore>,
+) -> Result<StatusCode, RouteError> {
+    service::lists::remove_book(&*store, requester.id, list_id, book_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use schema::{
+    books::{BorrowedBook, BorrowedByReply, BorrowsReply, LoanChanges, NewLoan},
+    errors::ErrorCode,
+    ids::{BorrowId, UserId},
+};
+
+use super::Requester;
+use crate::{
+    config::LoanConfig,
+    error::{IntoRouteError, RouteError},
+    events::Events,
+    service,
+    store::{DynStore, LendOutcome},
+    validation::ValidJson,
+};
+
+#[utoipa::path(
+    get,
+    path = "/loans",
+    tag = "loans",
+    responses(
+        (status = 200, description = "Every loan that hasn't been returned", body = [Borrow]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn loans(
+    requester: Requester,
+    State(store): State<DynStore>,
+) -> Result<Json<BorrowsReply>, RouteError> {
+    Ok(Json(service::books::borrows(&*store, requester.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/loans",
+    tag = "loans",
+    params(
+        ("user_id" = i64, Path, description = "Id of the user"),
+    ),
+    responses(
+        (status = 200, description = "The books the user has borrowed", body = [BorrowedBook]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn borrowed_by(
+    requester: Requester,
+    Path(user_id): Path<UserId>,
+    State(store): State<DynStore>,
+) -> Result<Json<BorrowedByReply>, RouteError> {
+    let borrowed = service::books::borrowed_by(&*store, requester.id, user_id).await?;
+
+    Ok(Json(borrowed))
+}
+
+/// Borrows a book for the requester, or checks it out to a reader if a `user_id` is given
+#[utoipa::path(
+    post,
+    path = "/loans",
+    operation_id = "create_loan",
+    tag = "loans",
+    request_body = NewLoan,
+    responses(
+        (status = 201, description = "The new loan", body = BorrowedBook),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn create(
+    requester: Requester,
+    State(store): State<DynStore>,
+    State(loans): State<LoanConfig>,
+    State(events): State<Events>,
+    ValidJson(loan): ValidJson<NewLoan>,
+) -> Result<(StatusCode, Json<BorrowedBook>), RouteError> {
+    let user_id = loan.user_id.unwrap_or(requester.id);
+
+    let outcome = match (loan.user_id, loan.reading_list_id) {
+        (Some(_), Some(_)) => {
+            return Err(RouteError::new_bad_request()
+                .set_public_error_message(
+                    "Books can't be checked out to someone else from a reading list",
+                )
+                .set_error_code(ErrorCode::CheckoutFromReadingList))
+        }
+        (Some(user_id), None) => {
+            service::books::checkout(
+                &*store,
+                &loans,
+                &events,
+                requester.id,
+                loan.book_id,
+                user_id,
+            )
+            .await?
+        }
+        (None, Some(list_id)) => {
+            service::lists::borrow(
+                &*store,
+                &loans,
+                &events,
+                requester.id,
+                list_id,
+                loan.book_id,
+            )
+            .await?
+        }
+        (None, None) => {
+            service::books::lend(&*store, &loans, &events, loan.book_id, requester.id, None).await?
+        }
+    };
+
+    match outcome {
+        LendOutcome::Lent => {}
+        LendOutcome::AlreadyBorrowed => {
+            return Err(RouteError::new_conflict()
+                .set_public_error_message("The book is already borrowed by this reader")
+                .set_error_code(ErrorCode::AlreadyBorrowed))
+        }
+        LendOutcome::NoCopiesLeft => {
+            return Err(RouteError::new_conflict()
+                .set_public_error_message("Every copy of the book is already borrowed")
+                .set_error_code(ErrorCode::NoCopiesLeft))
+        }
+    }
+
+    let borrowed = store
+        .borrowed_by(user_id)
+        .await
+        .http_internal_error("Failed to fetch borrowed books")?
+        .into_iter()
+        .find(|borrowed| borrowed.book_id == loan.book_id)
+        .ok_or_else(RouteError::new_internal_server)?;
+
+    Ok((StatusCode::CREATED, Json(borrowed)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/loans/{borrow_id}",
+    operation_id = "update_loan",
+    tag = "loans",
+    params(
+        ("borrow_id" = i64, Path, description = "Id of the loan"),
+    ),
+    request_body = LoanChanges,
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn update(
+    requester: Requester,
+    Path(borrow_id): Path<BorrowId>,
+    State(store): State<DynStore>,
+    State(loans): State<LoanConfig>,
+    State(events): State<Events>,
+    ValidJson(changes): ValidJson<LoanChanges>,
+) -> Result<StatusCode, RouteError> {
+    service::books::change_loan(&*store, &loans, &events, requester.id, borrow_id, &changes)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/loans/{borrow_id}",
+    tag = "loans",
+    params(
+        ("borrow_id" = i64, Path, description = "Id of the loan"),
+    ),
+    responses(
+        (status = 204, description = "The book was returned"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn return_book(
+    requester: Requester,
+    Path(borrow_id): Path<BorrowId>,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+) -> Result<StatusCode, RouteError> {
+    service::books::return_book(&*store, &events, requester.id, borrow_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,112 @@
+//! Version 1 of the HTTP API, served under `/api/v1`.
+//!
+//! Routes name resources and the HTTP method says what happens to them, e.g. `DELETE /books/:id`
+//! instead of `POST /delete-book/:id`. The requester is given by an `Authorization: Basic` header
+//! holding `<user id>:<password>`, rather than a cookie in the JSON body. The routes the app still
+//! uses are kept in [`crate::legacy`].
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+    routing::{get, patch, post, put},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use schema::{
+    errors::ErrorCode,
+    ids::{UserId, UserKind},
+};
+
+use crate::{error::RouteError, service, store::DynStore, AppState};
+
+mod backups;
+mod books;
+mod events;
+mod lists;
+mod loans;
+pub mod openapi;
+mod reports;
+mod users;
+
+/// A route added here also needs its handler listed in the paths of [`openapi`]
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(users::login))
+        .route("/users", get(users::users).post(users::create))
+        .route(
+            "/users/:user_id",
+            patch(users::update).delete(users::delete),
+        )
+        .route("/users/:user_id/loans", get(loans::borrowed_by))
+        .route("/users/:user_id/reading-lists", get(lists::reading_lists))
+        .route("/me/recommendations", get(books::recommendations))
+        .route("/authors", get(books::authors).post(books::add_author))
+        .route("/books", get(books::books).post(books::create))
+        .route(
+            "/books/:book_id",
+            get(books::book).put(books::update).delete(books::delete),
+        )
+        .route("/books/:book_id/similar", get(books::similar))
+        .route("/loans", get(loans::loans).post(loans::create))
+        .route(
+            "/loans/:borrow_id",
+            patch(loans::update).delete(loans::return_book),
+        )
+        .route("/reading-lists", post(lists::create))
+        .route(
+            "/reading-lists/:list_id",
+            put(lists::update).delete(lists::delete),
+        )
+        .route("/reading-lists/:list_id/books", put(lists::reorder))
+        .route(
+            "/reading-lists/:list_id/books/:book_id",
+            put(lists::add_book).delete(lists::remove_book),
+        )
+        .route("/reports/:report", get(reports::report))
+        .route("/backups", get(backups::backups).post(backups::create))
+        .route("/events", get(events::events))
+}
+
+/// The account making a request, taken from its `Authorization` header once the password in it is
+/// checked
+pub struct Requester {
+    pub id: UserId,
+    pub kind: UserKind,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Requester
+where
+    DynStore: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = RouteError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let (id, password) = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|encoded| STANDARD.decode(encoded).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|credentials| {
+                let (id, password) = credentials.split_once(':')?;
+                Some((id.parse::<UserId>().ok()?, password.to_string()))
+            })
+            .ok_or_else(|| {
+                RouteError::new_unauthorized()
+                    .set_public_error_message("Missing or malformed Authorization header")
+                    .set_error_code(ErrorCode::MissingAuthorization)
+            })?;
+
+        let store = DynStore::from_ref(state);
+        let user = service::users::authenticate(&*store, id, &password).await?;
+
+        Ok(Self {
+            id,
+            kind: user.kind,
+        })
+    }
+}
@@ -0,0 +1,163 @@
+//! The OpenAPI description of [`super`], served at `/openapi.json`.
+//!
+//! Paths come from the `#[utoipa::path]` attributes on the handlers and schemas from the
+//! `bd-schema` types, so the document changes along with them. `openapi.json` next to `Cargo.toml`
+//! is a snapshot of it that tests check the served document against.
+
+use axum::Json;
+use schema::{
+    auth::{CreateAccount, Login, LoginReply, User, UserChanges},
+    backups::Backup,
+    books::{
+        Author, AuthorFields, Book, BookFields, Borrow, BorrowedBook, LoanChanges, NewLoan,
+        Recommendation,
+    },
+    dates::{PartialDate, Timestamp},
+    errors::{ErrorCode, FieldError, FieldErrorCode},
+    events::{Event, Loan},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    lists::{ReadingList, ReadingListFields},
+    reports::{
+        AuthorPopularity, LoanStatusReply, MostBorrowedBook, NeverBorrowedBook, Registrations,
+        ReportFormat, TitleUtilisation,
+    },
+    OpenApiAliases,
+};
+use utoipa::{
+    openapi::{
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        ContentBuilder, Ref, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use super as api;
+use crate::error::{RouteErrorOutput, RouteInternalErrorOutput};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Biblioteca", description = "Managing a library and the reading progress of borrowed books"),
+    servers((url = "/api/v1")),
+    paths(
+        api::users::login,
+        api::users::users,
+        api::users::create,
+        api::users::update,
+        api::users::delete,
+        api::loans::borrowed_by,
+        api::lists::reading_lists,
+        api::books::recommendations,
+        api::books::authors,
+        api::books::add_author,
+        api::books::books,
+        api::books::create,
+        api::books::book,
+        api::books::update,
+        api::books::delete,
+        api::books::similar,
+        api::loans::loans,
+        api::loans::create,
+        api::loans::update,
+        api::loans::return_book,
+        api::lists::create,
+        api::lists::update,
+        api::lists::delete,
+        api::lists::reorder,
+        api::lists::add_book,
+        api::lists::remove_book,
+        api::reports::report,
+        api::backups::backups,
+        api::backups::create,
+        api::events::events,
+    ),
+    components(schemas(
+        RouteErrorOutput<()>,
+        RouteInternalErrorOutput,
+        ErrorCode,
+        FieldError,
+        FieldErrorCode,
+        BookId,
+        AuthorId,
+        UserId,
+        BorrowId,
+        UserKind,
+        Timestamp,
+        PartialDate,
+        CreateAccount,
+        Login,
+        LoginReply,
+        User,
+        UserChanges,
+        Author,
+        AuthorFields,
+        Book,
+        BookFields,
+        Borrow,
+        BorrowedBook,
+        NewLoan,
+        LoanChanges,
+        Recommendation,
+        ReadingList,
+        ReadingListFields,
+        ReportFormat,
+        MostBorrowedBook,
+        NeverBorrowedBook,
+        LoanStatusReply,
+        AuthorPopularity,
+        Registrations,
+        TitleUtilisation,
+        Backup,
+        Event,
+        Loan,
+    )),
+    modifiers(&OpenApiAliases, &BasicAuth, &ErrorResponses),
+)]
+struct ApiDoc;
+
+/// The `Authorization: Basic` header read by [`api::Requester`]
+struct BasicAuth;
+
+impl Modify for BasicAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "basic",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)),
+        );
+    }
+}
+
+/// Every route answers errors with a [`RouteErrorOutput`], so it's given as the default response
+/// instead of listing each status code
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let response = ResponseBuilder::new()
+            .description("The request failed")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Ref::from_schema_name("RouteErrorOutput"))
+                    .build(),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in item.operations.values_mut() {
+                operation
+                    .responses
+                    .responses
+                    .insert("default".to_string(), response.clone().into());
+            }
+        }
+    }
+}
+
+pub fn spec() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(spec())
+}
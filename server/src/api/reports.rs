@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Response,
+};
+use schema::{dates::Timestamp, reports::ReportFormat};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::Requester;
+use crate::{error::RouteError, reports, service, store::DynStore};
+
+#[derive(Deserialize, IntoParams)]
+pub struct ReportQuery {
+    /// Start of the reported period (inclusive)
+    from: Option<Timestamp>,
+    /// End of the reported period (exclusive)
+    to: Option<Timestamp>,
+    #[serde(default)]
+    #[param(inline)]
+    format: ReportFormat,
+}
+
+#[utoipa::path(
+    get,
+    path = "/reports/{report}",
+    tag = "reports",
+    params(
+        ("report" = String, Path, description = "One of `most-borrowed`, `never-borrowed`, `loans`, `author-popularity`, `registrations` or `utilisation`"),
+        ReportQuery,
+    ),
+    responses(
+        (status = 200, description = "The rows of the report", content(
+            ("application/json" = Value),
+            ("text/csv" = String),
+        )),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn report(
+    requester: Requester,
+    Path(name): Path<String>,
+    Query(query): Query<ReportQuery>,
+    State(store): State<DynStore>,
+) -> Result<Response, RouteError> {
+    service::users::require_librarian(&*store, requester.id).await?;
+    let period = reports::period(query.from, query.to)?;
+
+    reports::report(&*store, &name, period, query.format).await
+}
@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use schema::{
+    auth::{CreateAccount, GetAllUsersReply, Login, LoginReply, User, UserChanges},
+    errors::ErrorCode,
+    ids::{UserId, UserKind},
+};
+
+use super::Requester;
+use crate::{error::RouteError, events::Events, service, store::DynStore, validation::ValidJson};
+
+/// Checks an email and password, replying with the id to put in the `Authorization` header
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "users",
+    request_body = Login,
+    responses(
+        (status = 200, description = "The account's id and kind", body = LoginReply),
+    ),
+)]
+pub async fn login(
+    State(store): State<DynStore>,
+    ValidJson(login): ValidJson<Login>,
+) -> Result<Json<LoginReply>, RouteError> {
+    let user = service::users::login(&*store, &login.email, &login.password).await?;
+
+    Ok(Json(LoginReply {
+        id: user.user_id,
+        kind: user.kind,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users",
+    operation_id = "create_user",
+    tag = "users",
+    request_body = CreateAccount,
+    responses(
+        (status = 201, description = "The new account", body = LoginReply),
+    ),
+)]
+pub async fn create(
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+    ValidJson(account): ValidJson<CreateAccount>,
+) -> Result<(StatusCode, Json<LoginReply>), RouteError> {
+    let id = service::users::create_account(
+        &*store,
+        &events,
+        account.name,
+        account.email,
+        &account.password,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(LoginReply {
+            id,
+            kind: UserKind::Reader,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    responses(
+        (status = 200, description = "Every account", body = [User]),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn users(
+    requester: Requester,
+    State(store): State<DynStore>,
+) -> Result<Json<GetAllUsersReply>, RouteError> {
+    let users = service::users::users(&*store, requester.id)
+        .await?
+        .into_iter()
+        .map(User::from)
+        .collect();
+
+    Ok(Json(users))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{user_id}",
+    operation_id = "update_user",
+    tag = "users",
+    params(
+        ("user_id" = i64, Path, description = "Id of the user"),
+    ),
+    request_body = UserChanges,
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn update(
+    requester: Requester,
+    Path(user_id): Path<UserId>,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+    ValidJson(changes): ValidJson<UserChanges>,
+) -> Result<StatusCode, RouteError> {
+    if changes.kind != UserKind::Librarian {
+        return Err(RouteError::new_bad_request()
+            .set_public_error_message("Users can only be promoted to librarians")
+            .set_error_code(ErrorCode::InvalidUserKind));
+    }
+
+    service::users::promote(&*store, &events, requester.id, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}",
+    operation_id = "delete_user",
+    tag = "users",
+    params(
+        ("user_id" = i64, Path, description = "Id of the user"),
+    ),
+    responses(
+        (status = 204, description = "Done"),
+    ),
+    security(("basic" = [])),
+)]
+pub async fn delete(
+    requester: Requester,
+    Path(user_id): Path<UserId>,
+    State(store): State<DynStore>,
+    State(events): State<Events>,
+) -> Result<StatusCode, RouteError> {
+    service::users::delete(&*store, &events, requester.id, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,264 @@
+//! Online backups of the database.
+//!
+//! Backups are made with `VACUUM INTO`, which gives a consistent snapshot without stopping the
+//! server. Each one is stored as `backup-<UTC time>.sqlite` in the backup directory, next to a
+//! `.sha256` file in the format of `sha256sum`. Only the most recent backups are kept.
+//!
+//! This only covers SQLite, a PostgreSQL database is backed up with its own tools.
+
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use axum::{extract::State, Json};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use schema::backups::{Backup, BackupRequest, BackupsReply};
+use sha2::{Digest, Sha256};
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    config::BackupConfig,
+    error::{IntoRouteError, RouteError},
+    store::{DynStore, Store},
+    utils::verify_user_is_librarian,
+};
+
+const NAME_PREFIX: &str = "backup-";
+const NAME_SUFFIX: &str = ".sqlite";
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Keeps a scheduled backup and one asked for by a librarian from running at the same time
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Spawns a task that makes a backup every `interval_hours`, if backups are enabled
+pub fn spawn_backup_task(store: DynStore, config: BackupConfig) {
+    if config.interval_hours == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        // The first backup is made one period after startup, otherwise restarting the server a
+        // few times in a row would push the older backups out of retention
+        let period = Duration::from_secs(config.interval_hours * 60 * 60);
+        let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+
+            match create_backup(&*store, &config).await {
+                Ok(backup) => tracing::info!("Backed up the database to {}", backup.name),
+                Err(err) => tracing::error!("Failed to back up the database: {err:?}"),
+            }
+        }
+    });
+}
+
+/// Makes a new backup in the backup directory and deletes the ones that fall out of retention
+pub async fn create_backup(store: &dyn Store, config: &BackupConfig) -> anyhow::Result<Backup> {
+    let _guard = BACKUP_LOCK.lock().await;
+
+    fs::create_dir_all(&config.dir)
+        .with_context(|| format!("Failed to create {}", config.dir.display()))?;
+
+    let created_at = Utc::now();
+    let name = format!(
+        "{NAME_PREFIX}{}{NAME_SUFFIX}",
+        created_at.format(TIME_FORMAT)
+    );
+    let path = config.dir.join(&name);
+
+    let sha256 = backup_to(store, &path).await?;
+    let size = fs::metadata(&path)?.len() as i64;
+
+    rotate(&config.dir, config.keep)?;
+
+    Ok(Backup {
+        name,
+        created_at: created_at.into(),
+        size,
+        sha256,
+    })
+}
+
+/// Copies the database to `path` and writes its checksum next to it, returning the checksum
+pub async fn backup_to(store: &dyn Store, path: &Path) -> anyhow::Result<String> {
+    if path.exists() {
+        bail!("{} already exists", path.display());
+    }
+
+    // Written under a temporary name, so a half written backup is never mistaken for a good one
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+    if partial_path.exists() {
+        fs::remove_file(&partial_path)?;
+    }
+
+    store.backup_to(&partial_path).await?;
+    fs::rename(&partial_path, path)?;
+
+    write_checksum(path).await
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.as_os_str().to_owned();
+    checksum_path.push(".sha256");
+    checksum_path.into()
+}
+
+async fn compute_checksum(path: &Path) -> anyhow::Result<String> {
+    let path = path.to_owned();
+
+    tokio::task::spawn_blocking(move || {
+        let mut hasher = Sha256::new();
+        io::copy(&mut File::open(&path)?, &mut hasher)?;
+
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await?
+}
+
+async fn write_checksum(path: &Path) -> anyhow::Result<String> {
+    let sha256 = compute_checksum(path).await?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    fs::write(checksum_path(path), format!("{sha256}  {file_name}\n"))?;
+
+    Ok(sha256)
+}
+
+/// Checks a backup against its `.sha256` file. Returns `false` if there is no checksum to check
+/// against, and an error if the checksum doesn't match.
+pub async fn verify_checksum(path: &Path) -> anyhow::Result<bool> {
+    let expected = match fs::read_to_string(checksum_path(path)) {
+        Ok(contents) => contents
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+
+    let actual = compute_checksum(path).await?;
+    if actual != expected {
+        bail!(
+            "The checksum of {} doesn't match, expected {expected} but got {actual}",
+            path.display()
+        );
+    }
+
+    Ok(true)
+}
+
+/// Lists the backups in `dir`, oldest first
+fn list_backups(dir: &Path) -> anyhow::Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut backups = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let Some(time) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(NAME_PREFIX))
+            .and_then(|name| name.strip_suffix(NAME_SUFFIX))
+            .and_then(|time| NaiveDateTime::parse_from_str(time, TIME_FORMAT).ok())
+        else {
+            continue;
+        };
+
+        backups.push((time.and_utc(), path));
+    }
+    backups.sort();
+
+    Ok(backups)
+}
+
+fn rotate(dir: &Path, keep: usize) -> anyhow::Result<()> {
+    let backups = list_backups(dir)?;
+    let expired = backups.len().saturating_sub(keep.max(1));
+
+    for (_, path) in &backups[..expired] {
+        fs::remove_file(path)?;
+        match fs::remove_file(checksum_path(path)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        tracing::info!("Deleted expired backup {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Finds the most recent backup made at or before `time`, or the most recent one overall
+pub fn find_backup(dir: &Path, time: Option<DateTime<Utc>>) -> anyhow::Result<PathBuf> {
+    list_backups(dir)?
+        .into_iter()
+        .rev()
+        .find(|(created_at, _)| time.is_none_or(|time| *created_at <= time))
+        .map(|(_, path)| path)
+        .with_context(|| match time {
+            Some(time) => format!("No backup in {} made before {time}", dir.display()),
+            None => format!("No backups in {}", dir.display()),
+        })
+}
+
+pub async fn create_backup_now(
+    State(store): State<DynStore>,
+    State(config): State<BackupConfig>,
+    Json(request): Json<BackupRequest>,
+) -> Result<Json<Backup>, RouteError> {
+    verify_user_is_librarian(&*store, request.cookie).await?;
+
+    let backup = create_backup(&*store, &config)
+        .await
+        .http_internal_error("Failed to back up the database")?;
+
+    Ok(Json(backup))
+}
+
+pub async fn backups(
+    State(store): State<DynStore>,
+    State(config): State<BackupConfig>,
+    Json(request): Json<BackupRequest>,
+) -> Result<Json<BackupsReply>, RouteError> {
+    verify_user_is_librarian(&*store, request.cookie).await?;
+
+    let backups = describe_backups(&config.dir).http_internal_error("Failed to list backups")?;
+
+    Ok(Json(backups))
+}
+
+/// The backups in `dir`, oldest first
+pub fn describe_backups(dir: &Path) -> anyhow::Result<BackupsReply> {
+    let mut backups = vec![];
+    for (created_at, path) in list_backups(dir)? {
+        let size = fs::metadata(&path)?.len() as i64;
+        let sha256 = fs::read_to_string(checksum_path(&path))
+            .ok()
+            .and_then(|contents| contents.split_whitespace().next().map(str::to_string))
+            .unwrap_or_default();
+
+        backups.push(Backup {
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            created_at: created_at.into(),
+            size,
+            sha256,
+        });
+    }
+
+    Ok(backups)
+}
@@ -0,0 +1,131 @@
+//! Conditional `GET`s of the catalog, so that a client refreshing it doesn't have every book read
+//! again when nothing changed.
+//!
+//! The catalog has a revision, counted up whenever a book is saved or deleted, an author is added,
+//! or a copy is lent or returned. `GET /books` and `GET /authors` reply with it as their `ETag` and
+//! with when it changed as their `Last-Modified`. A request whose `If-None-Match`, or lacking that,
+//! whose `If-Modified-Since` is still current gets `304 Not Modified` without the store being read.
+//!
+//! Revisions only count the changes made while the server runs, so the `ETag` also says when it
+//! started: a client can't mistake a revision from before a restart for the current one.
+
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::RouteError;
+
+/// The format of `Last-Modified` and `If-Modified-Since`
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// The current revision of the catalog, shared by every handler
+#[derive(Clone)]
+pub struct CatalogRevision(Arc<Mutex<Revision>>);
+
+impl Default for CatalogRevision {
+    fn default() -> Self {
+        let now = Utc::now();
+
+        Self(Arc::new(Mutex::new(Revision {
+            started_at: now.timestamp_millis(),
+            number: 0,
+            changed_at: now,
+        })))
+    }
+}
+
+impl CatalogRevision {
+    pub fn current(&self) -> Revision {
+        *self.0.lock().unwrap()
+    }
+
+    /// Moves on to the next revision, after a change to the catalog was committed
+    pub fn changed(&self) {
+        let mut revision = self.0.lock().unwrap();
+        revision.number += 1;
+        revision.changed_at = Utc::now();
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Revision {
+    started_at: i64,
+    number: u64,
+    changed_at: DateTime<Utc>,
+}
+
+impl Revision {
+    pub fn etag(&self) -> String {
+        format!("\"{:x}-{}\"", self.started_at, self.number)
+    }
+
+    pub fn last_modified(&self) -> String {
+        self.changed_at.format(HTTP_DATE).to_string()
+    }
+
+    /// Whether the client sending `headers` already has this revision. `If-Modified-Since` only
+    /// counts whole seconds, so it's ignored when an `If-None-Match` is sent too.
+    fn is_known_to(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            let etag = self.etag();
+            return if_none_match.to_str().is_ok_and(|tags| {
+                tags.split(',')
+                    .map(|tag| tag.trim())
+                    .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+            });
+        }
+
+        headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+            .is_some_and(|since| self.changed_at.timestamp() <= since.timestamp())
+    }
+}
+
+/// A reply to a `GET` of the catalog, with a `T` as its body unless it's `304 Not Modified`
+pub struct CatalogReply<T>(Response, PhantomData<T>);
+
+impl<T> IntoResponse for CatalogReply<T> {
+    fn into_response(self) -> Response {
+        self.0
+    }
+}
+
+/// Replies to a `GET` of the catalog with what `fetch` reads, or with `304 Not Modified` if the
+/// client sending `headers` already has the current revision
+pub async fn catalog_reply<T: Serialize>(
+    catalog: &CatalogRevision,
+    headers: &HeaderMap,
+    fetch: impl std::future::Future<Output = Result<T, RouteError>>,
+) -> Result<CatalogReply<T>, RouteError> {
+    // Taken before reading, so that a change committed meanwhile makes the next request read again
+    let revision = catalog.current();
+
+    let mut response = if revision.is_known_to(headers) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(fetch.await?).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    for (name, value) in [
+        (header::ETAG, revision.etag()),
+        (header::LAST_MODIFIED, revision.last_modified()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ] {
+        let value = HeaderValue::from_str(&value).expect("the headers are plain ASCII");
+        response_headers.insert(name, value);
+    }
+
+    Ok(CatalogReply(response, PhantomData))
+}
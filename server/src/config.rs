@@ -0,0 +1,199 @@
+//! Server configuration.
+//!
+//! Settings are layered, each layer overriding the ones before it:
+//! 1. built-in defaults
+//! 2. the TOML configuration file (`biblioteca.toml`, or the one given with `--config`)
+//! 3. `BIBLIOTECA_*` environment variables
+//! 4. command line flags
+
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+
+use crate::{
+    admin::{AdminCommand, RestoreArgs},
+    seed::SeedArgs,
+};
+
+const DEFAULT_CONFIG_PATH: &str = "biblioteca.toml";
+
+#[derive(Parser, Debug, Default)]
+#[command(version, about = "Library management server")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Configuration file to read, `biblioteca.toml` is used if it exists
+    #[arg(short, long, global = true, env = "BIBLIOTECA_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Address to listen on
+    #[arg(long, global = true, env = "BIBLIOTECA_LISTEN")]
+    listen: Option<SocketAddr>,
+
+    /// Path of the SQLite database, created if it doesn't exist, or the `postgres://` URL of a
+    /// PostgreSQL database
+    #[arg(long, global = true, env = "BIBLIOTECA_DATABASE")]
+    database: Option<String>,
+
+    /// Log filter, in the `tracing_subscriber::EnvFilter` syntax
+    #[arg(long, global = true, env = "BIBLIOTECA_LOG")]
+    log: Option<String>,
+
+    /// Directory from which media (e.g. covers) is served under `/media`
+    #[arg(long, global = true, env = "BIBLIOTECA_MEDIA_DIR")]
+    media_dir: Option<PathBuf>,
+
+    /// How many days a new loan lasts
+    #[arg(long, global = true, env = "BIBLIOTECA_LOAN_DAYS")]
+    loan_days: Option<u32>,
+
+    /// The most days a loan may be lengthened by at once
+    #[arg(long, global = true, env = "BIBLIOTECA_MAX_EXTENSION_DAYS")]
+    max_extension_days: Option<u32>,
+
+    /// Directory in which backups are stored
+    #[arg(long, global = true, env = "BIBLIOTECA_BACKUP_DIR")]
+    backup_dir: Option<PathBuf>,
+
+    /// Hours between scheduled backups, 0 turns them off
+    #[arg(long, global = true, env = "BIBLIOTECA_BACKUP_INTERVAL_HOURS")]
+    backup_interval_hours: Option<u64>,
+
+    /// How many of the most recent backups are kept
+    #[arg(long, global = true, env = "BIBLIOTECA_BACKUPS_KEPT")]
+    backups_kept: Option<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the server, this is what happens when no command is given
+    Serve,
+    /// Load a set of fixtures into the database
+    Seed(SeedArgs),
+    #[command(flatten)]
+    Admin(AdminCommand),
+    /// Replace the database with a backup, the server must be stopped first
+    Restore(RestoreArgs),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub listen: SocketAddr,
+    /// A SQLite path or a PostgreSQL URL, see [`crate::store::Backend`]
+    pub database: String,
+    pub log: String,
+    pub media_dir: PathBuf,
+    pub loans: LoanConfig,
+    pub backups: BackupConfig,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoanConfig {
+    /// How many days a new loan lasts
+    pub days: u32,
+    /// The most days a loan may be lengthened by at once
+    pub max_extension_days: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    pub dir: PathBuf,
+    /// Hours between scheduled backups, 0 turns them off
+    pub interval_hours: u64,
+    /// How many of the most recent backups are kept, older ones are deleted
+    pub keep: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen: ([0, 0, 0, 0], 3000).into(),
+            database: "data.sqlite".into(),
+            log: "info".into(),
+            media_dir: "media".into(),
+            loans: LoanConfig::default(),
+            backups: BackupConfig::default(),
+        }
+    }
+}
+
+impl Default for LoanConfig {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            max_extension_days: 90,
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            dir: "backups".into(),
+            interval_hours: 24,
+            keep: 14,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the file, the environment and the command line
+    pub fn load(args: &Args) -> anyhow::Result<Self> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        // clap already gives flags precedence over the environment
+        if let Some(listen) = args.listen {
+            config.listen = listen;
+        }
+        if let Some(database) = &args.database {
+            config.database = database.clone();
+        }
+        if let Some(log) = &args.log {
+            config.log = log.clone();
+        }
+        if let Some(media_dir) = &args.media_dir {
+            config.media_dir = media_dir.clone();
+        }
+        if let Some(days) = args.loan_days {
+            config.loans.days = days;
+        }
+        if let Some(max_extension_days) = args.max_extension_days {
+            config.loans.max_extension_days = max_extension_days;
+        }
+        if let Some(dir) = &args.backup_dir {
+            config.backups.dir = dir.clone();
+        }
+        if let Some(interval_hours) = args.backup_interval_hours {
+            config.backups.interval_hours = interval_hours;
+        }
+        if let Some(keep) = args.backups_kept {
+            config.backups.keep = keep;
+        }
+
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+}
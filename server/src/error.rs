@@ -12,7 +12,12 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use schema::errors::{ErrorCode, FieldError};
 use serde::{Deserialize, Serialize};
+use utoipa::{
+    openapi::{ArrayBuilder, ObjectBuilder, Ref, RefOr, Schema, SchemaType},
+    ToSchema,
+};
 
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
@@ -21,7 +26,7 @@ use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 /// what has gone wrong as a part of the return.
 pub type RouteInternalError<S = ()> = RouteError<S, true>;
 
-#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 pub struct RouteInternalErrorOutput {
     pub name: String,
     pub debug: String,
@@ -31,6 +36,13 @@ pub struct RouteInternalErrorOutput {
 pub struct RouteErrorOutput<S> {
     pub error: String,
 
+    #[serde(default)]
+    pub code: ErrorCode,
+
+    /// What is wrong with each refused field, when the code is [`ErrorCode::InvalidFields`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub internal_error: Option<RouteInternalErrorOutput>,
 
@@ -38,10 +50,38 @@ pub struct RouteErrorOutput<S> {
     pub extra_data: Option<S>,
 }
 
+/// Written by hand since the derive can't describe the flattened `extra_data`, which no route of
+/// the API sets
+impl<'s, S> ToSchema<'s> for RouteErrorOutput<S> {
+    fn schema() -> (&'s str, RefOr<Schema>) {
+        let error = ObjectBuilder::new()
+            .schema_type(SchemaType::String)
+            .description(Some("A message that can be shown to the user"));
+        let schema = ObjectBuilder::new()
+            .description(Some("The body of every error response"))
+            .property("error", error)
+            .required("error")
+            .property("code", Ref::from_schema_name("ErrorCode"))
+            .required("code")
+            .property(
+                "details",
+                ArrayBuilder::new().items(Ref::from_schema_name("FieldError")),
+            )
+            .property(
+                "internal_error",
+                Ref::from_schema_name("RouteInternalErrorOutput"),
+            );
+
+        ("RouteErrorOutput", schema.into())
+    }
+}
+
 impl<S> Default for RouteErrorOutput<S> {
     fn default() -> Self {
         Self {
             error: "An unknown error occurred".to_string(),
+            code: ErrorCode::default(),
+            details: Vec::new(),
             internal_error: None,
             extra_data: None,
         }
@@ -51,14 +91,15 @@ impl<S> Default for RouteErrorOutput<S> {
 /// This Rust module provides a standard error type for routes.
 /// It encapsulates information about errors that occur while handling requests.
 /// It includes a status code, error details, any extra data,
-/// and a public error message.
+/// a public error message and an [`ErrorCode`].
 ///
 /// It includes the means to output these as Json for the user.
 ///
 /// The output will be in the form:
 /// ```json
 ///     {
-///         "error": "My public error message"
+///         "error": "My public error message",
+///         "code": "not_found"
 ///     }
 /// ```
 ///
@@ -79,6 +120,8 @@ where
     error: Option<AnyhowError>,
     extra_data: Option<Box<S>>,
     public_error_message: Option<String>,
+    error_code: Option<ErrorCode>,
+    details: Vec<FieldError>,
 }
 
 impl RouteError<()> {
@@ -177,9 +220,27 @@ where
             status_code: self.status_code,
             error: self.error,
             public_error_message: self.public_error_message,
+            error_code: self.error_code,
+            details: self.details,
         }
     }
 
+    /// Set the code clients tell the error apart by.
+    ///
+    /// If this is not set, then a generic code is provided
+    /// based on the status code.
+    pub fn set_error_code(self, error_code: ErrorCode) -> Self {
+        Self {
+            error_code: Some(error_code),
+            ..self
+        }
+    }
+
+    /// Set what is wrong with each field of the request.
+    pub fn set_details(self, details: Vec<FieldError>) -> Self {
+        Self { details, ..self }
+    }
+
     /// Set the error message to display within the error.
     ///
     /// If this is not set, then an appropriate message is provided
@@ -200,6 +261,12 @@ where
         status_code_to_public_message(self.status_code())
     }
 
+    /// Returns the code that will be sent to the client.
+    pub fn error_code(&self) -> ErrorCode {
+        self.error_code
+            .unwrap_or_else(|| status_code_to_error_code(self.status_code()))
+    }
+
     /// Returns the status code for the response.
     pub const fn status_code(&self) -> StatusCode {
         self.status_code
@@ -216,6 +283,8 @@ where
             error: None,
             extra_data: None,
             public_error_message: None,
+            error_code: None,
+            details: Vec::new(),
         }
     }
 }
@@ -228,7 +297,10 @@ where
         tracing::error!("Route error: {:?}", self.error);
 
         let status = self.status_code();
+        let code = self.error_code();
+        let details = self.details;
         let extra_data = self.extra_data;
+        #[allow(clippy::unnecessary_option_map_or_else)]
         let error = self.public_error_message.map_or_else(
             || status_code_to_public_message(status).to_string(),
             |public_error_message| public_error_message,
@@ -245,6 +317,8 @@ where
 
         let output = RouteErrorOutput {
             error,
+            code,
+            details,
             internal_error,
             extra_data,
         };
@@ -341,3 +415,16 @@ const fn status_code_to_public_message(status_code: StatusCode) -> &'static str
         _ => "An unknown error occurred",
     }
 }
+
+fn status_code_to_error_code(status_code: StatusCode) -> ErrorCode {
+    match status_code {
+        StatusCode::BAD_REQUEST => ErrorCode::BadRequest,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::CONFLICT => ErrorCode::Conflict,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+        _ if status_code.is_client_error() => ErrorCode::BadRequest,
+        _ => ErrorCode::Internal,
+    }
+}
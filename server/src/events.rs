@@ -0,0 +1,220 @@
+//! Telling the clients following `GET /api/v1/events` about changes as they're committed.
+//!
+//! The service layer publishes an [`Event`] once a change is stored. Events carry the changed
+//! records, which takes a few more reads of the store, so nothing is read while no one follows.
+//! Failing those reads is only logged: the change itself already happened, and a client that
+//! misses an event catches up the next time it fetches everything.
+//!
+//! Changes to the catalog also move its [`CatalogRevision`] on, whether anyone follows or not.
+
+use schema::{
+    auth::User,
+    books::{Author, BorrowedBook},
+    events::{Event, Loan},
+    ids::{BookId, BorrowId, UserId, UserKind},
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    conditional::CatalogRevision,
+    store::{Store, StoreResult},
+};
+
+/// How many events a client may fall behind by before the oldest are dropped
+const CAPACITY: usize = 256;
+
+/// Where events are published, cloned into every handler
+#[derive(Clone)]
+pub struct Events {
+    sender: broadcast::Sender<Event>,
+    catalog: CatalogRevision,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+            catalog: CatalogRevision::default(),
+        }
+    }
+}
+
+impl Events {
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    pub fn catalog(&self) -> &CatalogRevision {
+        &self.catalog
+    }
+
+    fn is_followed(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn publish(&self, event: Event) {
+        // Only fails when no one is following
+        let _ = self.sender.send(event);
+    }
+
+    pub async fn book_saved(&self, store: &dyn Store, book_id: BookId) {
+        self.catalog.changed();
+        if self.is_followed() {
+            log_failure(self.publish_book(store, book_id).await);
+        }
+    }
+
+    pub fn book_deleted(&self, book_id: BookId) {
+        self.catalog.changed();
+        self.publish(Event::BookDeleted { book_id });
+    }
+
+    pub fn author_added(&self, author: Author) {
+        self.catalog.changed();
+        self.publish(Event::AuthorAdded { author });
+    }
+
+    /// After `user_id` borrowed `book_id`, which leaves one copy less and one more book on their
+    /// account
+    pub async fn lent(&self, store: &dyn Store, user_id: UserId, book_id: BookId) {
+        self.catalog.changed();
+        if !self.is_followed() {
+            return;
+        }
+
+        let result = async {
+            let loan = find_loan(store, user_id, |borrowed| borrowed.book_id == book_id).await?;
+            if let Some(loan) = loan {
+                self.publish(Event::LoanSaved { loan });
+            }
+            self.publish_book(store, book_id).await?;
+            self.publish_user(store, user_id).await
+        };
+        log_failure(result.await);
+    }
+
+    pub async fn loan_saved(&self, store: &dyn Store, borrow_id: BorrowId) {
+        if let Some(loan) = self.loan(store, borrow_id).await {
+            self.publish(Event::LoanSaved { loan });
+        }
+    }
+
+    /// The loan as it is before it changes, to publish [`Events::returned`] with once it's gone.
+    /// `None` if no one is following.
+    pub async fn loan(&self, store: &dyn Store, borrow_id: BorrowId) -> Option<Loan> {
+        if !self.is_followed() {
+            return None;
+        }
+
+        let result = async {
+            let Some(user_id) = store.borrower(borrow_id).await? else {
+                return Ok(None);
+            };
+            find_loan(store, user_id, |borrowed| borrowed.borrow_id == borrow_id).await
+        };
+        match result.await {
+            Ok(loan) => loan,
+            Err(err) => {
+                log_failure(Err(err));
+                None
+            }
+        }
+    }
+
+    /// After the loan [`Events::loan`] read was returned, which leaves one more copy of its book
+    pub async fn returned(&self, store: &dyn Store, loan: Option<Loan>) {
+        self.catalog.changed();
+        let Some(loan) = loan else {
+            return;
+        };
+
+        self.publish(Event::LoanReturned {
+            borrow_id: loan.borrow_id,
+            book_id: loan.book_id,
+            user_id: loan.user_id,
+        });
+
+        let result = async {
+            self.publish_book(store, loan.book_id).await?;
+            self.publish_user(store, loan.user_id).await
+        };
+        log_failure(result.await);
+    }
+
+    pub async fn user_saved(&self, store: &dyn Store, user_id: UserId) {
+        if self.is_followed() {
+            log_failure(self.publish_user(store, user_id).await);
+        }
+    }
+
+    pub fn user_deleted(&self, user_id: UserId) {
+        self.publish(Event::UserDeleted { user_id });
+    }
+
+    async fn publish_book(&self, store: &dyn Store, book_id: BookId) -> StoreResult<()> {
+        if let Some(book) = store.book(book_id).await? {
+            self.publish(Event::BookSaved { book });
+        }
+
+        Ok(())
+    }
+
+    async fn publish_user(&self, store: &dyn Store, user_id: UserId) -> StoreResult<()> {
+        if let Some(user) = store.user(user_id).await? {
+            self.publish(Event::UserSaved {
+                user: User::from(user),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `event` may be sent to the account `user_id`. Librarians get every event, readers get
+/// the catalogue and what happens to their own account and loans.
+pub fn is_visible(event: &Event, user_id: UserId, kind: UserKind) -> bool {
+    if kind == UserKind::Librarian {
+        return true;
+    }
+
+    match event {
+        Event::LoanSaved { loan } => loan.user_id == user_id,
+        Event::LoanReturned {
+            user_id: borrower_id,
+            ..
+        } => *borrower_id == user_id,
+        Event::UserSaved { user } => user.id == user_id,
+        Event::UserDeleted {
+            user_id: deleted_id,
+        } => *deleted_id == user_id,
+        Event::BookSaved { .. }
+        | Event::BookDeleted { .. }
+        | Event::AuthorAdded { .. }
+        | Event::Missed => true,
+    }
+}
+
+async fn find_loan(
+    store: &dyn Store,
+    user_id: UserId,
+    matches: impl Fn(&BorrowedBook) -> bool,
+) -> StoreResult<Option<Loan>> {
+    Ok(store
+        .borrowed_by(user_id)
+        .await?
+        .into_iter()
+        .find(matches)
+        .map(|borrowed| Loan {
+            borrow_id: borrowed.borrow_id,
+            book_id: borrowed.book_id,
+            user_id,
+            valid_until: borrowed.valid_until,
+            chapters_read: borrowed.chapters_read,
+        }))
+}
+
+fn log_failure(result: StoreResult<()>) {
+    if let Err(err) = result {
+        tracing::warn!("Failed to read a change to publish it: {err}");
+    }
+}
@@ -0,0 +1,195 @@
+//! Support for the `Idempotency-Key` header, so that a client can safely send a request again when
+//! it doesn't know whether the first attempt went through, e.g. after a network error.
+//!
+//! The response to a `POST` or a `PATCH` made with a key is stored, and a repeated request with the same key
+//! gets that response back, with an `Idempotent-Replayed: true` header, instead of doing the work
+//! again. Keys expire a day after they're first used. A key can only be used again with the same
+//! request, telling them apart by a SHA-256 of the method, the path and the body.
+//!
+//! Every account has keys of its own, so that no one gets another account's response by sending
+//! the same request with the same key. Requests are told apart by the credentials they carry,
+//! whether they're in the `Authorization` header or in the JSON body of the older routes.
+
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use schema::errors::ErrorCode;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{IntoRouteError, RouteError},
+    store::{DynStore, StoredResponse},
+};
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// How long a key is remembered for
+const KEY_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MAX_KEY_LENGTH: usize = 255;
+/// The same as axum's default limit for JSON bodies
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Deletes expired keys every hour
+pub fn spawn_cleanup_task(store: DynStore) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match store
+                .delete_expired_idempotency_keys(expired_before())
+                .await
+            {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Deleted {count} expired idempotency keys"),
+                Err(err) => tracing::error!("Failed to delete expired idempotency keys: {err:?}"),
+            }
+        }
+    });
+}
+
+fn expired_before() -> i64 {
+    Utc::now().timestamp() - KEY_LIFETIME.as_secs() as i64
+}
+
+/// Middleware replaying the stored response of `POST` and `PATCH` requests whose key was already
+/// used
+pub async fn replay(
+    State(store): State<DynStore>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RouteError> {
+    if request.method() != Method::POST && request.method() != Method::PATCH {
+        return Ok(next.run(request).await);
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return Ok(next.run(request).await);
+    };
+
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            return Err(RouteError::new_bad_request()
+                .set_public_error_message(
+                    "The idempotency key must be between 1 and 255 visible ASCII characters",
+                )
+                .set_error_code(ErrorCode::InvalidIdempotencyKey))
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .http_error("Request body too large", StatusCode::PAYLOAD_TOO_LARGE)?;
+
+    let key = format!("{}:{key}", requester_scope(&parts, &body));
+
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str());
+    hasher.update(b" ");
+    hasher.update(parts.uri.to_string());
+    hasher.update(b"\n");
+    hasher.update(&body);
+    let fingerprint = format!("{:x}", hasher.finalize());
+
+    let previous = store
+        .claim_idempotency_key(&key, &fingerprint, Utc::now().timestamp(), expired_before())
+        .await
+        .http_internal_error("Failed to access the database")?;
+
+    if let Some(previous) = previous {
+        if previous.fingerprint != fingerprint {
+            return Err(RouteError::from_status(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_public_error_message(
+                    "The idempotency key was already used for a different request",
+                )
+                .set_error_code(ErrorCode::IdempotencyKeyReused));
+        }
+
+        let Some(response) = previous.response else {
+            return Err(RouteError::new_conflict()
+                .set_public_error_message(
+                    "A request with this idempotency key is still being handled",
+                )
+                .set_error_code(ErrorCode::RequestInProgress));
+        };
+
+        return Ok(replayed(response));
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // Failures on our side aren't remembered, the request may well work when tried again
+    if response.status().is_server_error() {
+        if let Err(err) = store.release_idempotency_key(&key).await {
+            tracing::error!("Failed to release idempotency key: {err:?}");
+        }
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .http_internal_error("Failed to read the response")?;
+
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    };
+    // The work is done either way, so the client still gets its response
+    if let Err(err) = store.save_idempotent_response(&key, &stored).await {
+        tracing::error!("Failed to store the response for an idempotency key: {err:?}");
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+/// Stands for whoever made the request, a SHA-256 of the credentials it carries since they hold
+/// the password. Requests without any, like logging in, share the same scope.
+fn requester_scope(parts: &Parts, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+
+    if let Some(authorization) = parts.headers.get(header::AUTHORIZATION) {
+        hasher.update(authorization.as_bytes());
+    } else if let Some(cookie) = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body.get("cookie").cloned())
+    {
+        hasher.update(cookie.to_string());
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+fn replayed(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|content_type| HeaderValue::from_str(&content_type).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+
+    response
+}
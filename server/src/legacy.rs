@@ -0,0 +1,165 @@
+//! The routes the app was written against, kept until it moves to [`crate::api`].
+//!
+//! Most of them are `POST`s named after what they do, with the requester's cookie in the JSON
+//! body. Every response says the routes are deprecated and points to their successor. Each route
+//! is registered from its [`Endpoint`], which is what the app sends its requests through, and its
+//! handler has to take the endpoint's body and reply with its response.
+
+use std::future::Future;
+
+use axum::{
+    extract::State,
+    handler::Handler,
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware,
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use schema::{
+    auth::{CreateAccount, DeleteUserRequest, GetAllUsersRequest, Login, PromoteUserRequest},
+    backups::BackupRequest,
+    books::{
+        BorrowRequest, BorrowsRequest, ChangeAuthorDetailsRequest, ChangeBookDetailsRequest,
+        CheckoutRequest, RecommendationsRequest,
+    },
+    endpoints::{
+        AddToReadingList, AuthorPopularityReport, Authors, Books, BorrowFromReadingList,
+        BorrowedBy, ChangeReadingList, CreateBackup, DeleteBook, DeleteReadingList, EndBorrow,
+        Endpoint, LengthenBorrow, LoanStatusReport, Method, MostBorrowedReport,
+        NeverBorrowedReport, ReadingLists, RegistrationsReport, RemoveFromReadingList,
+        ReorderReadingList, Report, ReturnBook, SimilarBooks, UpdateChaptersRead,
+        UtilisationReport,
+    },
+    lists::CreateReadingListRequest,
+};
+
+use crate::{
+    auth, backup, books,
+    conditional::CatalogReply,
+    error::RouteError,
+    lists, recommendations,
+    reports::{self, legacy_report, LegacyReport},
+    validation::ValidJson,
+    AppState,
+};
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .endpoint::<Authors, _, _>(books::authors)
+        .endpoint::<Books, _, _>(books::books)
+        .endpoint::<SimilarBooks, _, _>(recommendations::similar_books)
+        .endpoint::<RecommendationsRequest, _, _>(recommendations::recommendations)
+        .endpoint::<BorrowRequest, _, _>(books::borrow)
+        .endpoint::<BorrowsRequest, _, _>(books::borrows)
+        .endpoint::<CheckoutRequest, _, _>(books::checkout)
+        .endpoint::<BorrowedBy, _, _>(books::borrowed_by)
+        .endpoint::<ChangeAuthorDetailsRequest, _, _>(books::change_author_details)
+        .endpoint::<ChangeBookDetailsRequest, _, _>(books::change_book_details)
+        .endpoint::<DeleteBook, _, _>(books::delete_book)
+        .endpoint::<LengthenBorrow, _, _>(books::lengthen_borrow_by)
+        .endpoint::<EndBorrow, _, _>(books::end_borrow)
+        .endpoint::<UpdateChaptersRead, _, _>(books::update_chapters_read)
+        .endpoint::<ReturnBook, _, _>(books::return_book)
+        .endpoint::<ReadingLists, _, _>(lists::reading_lists)
+        .endpoint::<CreateReadingListRequest, _, _>(lists::create_reading_list)
+        .endpoint::<ChangeReadingList, _, _>(lists::change_reading_list)
+        .endpoint::<DeleteReadingList, _, _>(lists::delete_reading_list)
+        .endpoint::<AddToReadingList, _, _>(lists::add_to_reading_list)
+        .endpoint::<RemoveFromReadingList, _, _>(lists::remove_from_reading_list)
+        .endpoint::<ReorderReadingList, _, _>(lists::reorder_reading_list)
+        .endpoint::<BorrowFromReadingList, _, _>(lists::borrow_from_reading_list)
+        .endpoint::<BackupRequest, _, _>(backup::backups)
+        .endpoint::<CreateBackup, _, _>(backup::create_backup_now)
+        .endpoint::<Login, _, _>(auth::login)
+        .endpoint::<CreateAccount, _, _>(auth::create_account)
+        .endpoint::<GetAllUsersRequest, _, _>(auth::get_all_users)
+        .endpoint::<DeleteUserRequest, _, _>(auth::delete_user)
+        .endpoint::<PromoteUserRequest, _, _>(auth::promote_user)
+        .endpoint::<MostBorrowedReport, _, _>(legacy_report::<MostBorrowedReport>)
+        .endpoint::<NeverBorrowedReport, _, _>(legacy_report::<NeverBorrowedReport>)
+        .endpoint::<LoanStatusReport, _, _>(legacy_report::<LoanStatusReport>)
+        .endpoint::<AuthorPopularityReport, _, _>(legacy_report::<AuthorPopularityReport>)
+        .endpoint::<RegistrationsReport, _, _>(legacy_report::<RegistrationsReport>)
+        .endpoint::<UtilisationReport, _, _>(legacy_report::<UtilisationReport>)
+        .route("/reports/:report", post(reports::unknown_report))
+        .layer(middleware::map_response(mark_deprecated))
+}
+
+trait EndpointRouter {
+    /// Routes requests for `E` to `handler`
+    fn endpoint<E: Endpoint, T: 'static, A>(
+        self,
+        handler: impl Handler<T, AppState> + EndpointHandler<E, A>,
+    ) -> Self;
+}
+
+impl EndpointRouter for Router<AppState> {
+    fn endpoint<E: Endpoint, T: 'static, A>(
+        self,
+        handler: impl Handler<T, AppState> + EndpointHandler<E, A>,
+    ) -> Self {
+        let method_router = match E::METHOD {
+            Method::Get => get(handler),
+            Method::Post => post(handler),
+        };
+
+        self.route(E::ROUTE, method_router)
+    }
+}
+
+/// A handler whose last argument extracts the body of `E` and which replies with its response.
+/// `A` are the handler's arguments.
+trait EndpointHandler<E: Endpoint, A> {}
+
+/// Implements [`EndpointHandler`] for the async functions taking `$part`s before the body
+macro_rules! endpoint_handler {
+    ($($part:ident),*) => {
+        impl<E, F, Fut, $($part,)* B, R> EndpointHandler<E, ($($part,)* B,)> for F
+        where
+            E: Endpoint,
+            F: FnOnce($($part,)* B) -> Fut,
+            Fut: Future<Output = Result<R, RouteError>>,
+            B: ExtractsBody<E::Body>,
+            R: Replies<E::Response>,
+        {
+        }
+    };
+}
+
+endpoint_handler!();
+endpoint_handler!(T1);
+endpoint_handler!(T1, T2);
+endpoint_handler!(T1, T2, T3);
+endpoint_handler!(T1, T2, T3, T4);
+endpoint_handler!(T1, T2, T3, T4, T5);
+
+/// An extractor that reads a `B` from the body of the request
+trait ExtractsBody<B> {}
+
+impl<B> ExtractsBody<B> for Json<B> {}
+impl<B> ExtractsBody<B> for ValidJson<B> {}
+// Requests without a body end with some other extractor
+impl<S> ExtractsBody<()> for State<S> {}
+impl ExtractsBody<()> for HeaderMap {}
+
+/// A reply whose body is an `R`
+trait Replies<R> {}
+
+impl<R> Replies<R> for Json<R> {}
+impl Replies<()> for () {}
+impl<R> Replies<R> for CatalogReply<R> {}
+impl<R: Report> Replies<R::Response> for LegacyReport<R> {}
+
+async fn mark_deprecated(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, HeaderValue::from_static("true"));
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_static("</api/v1>; rel=\"successor-version\""),
+    );
+
+    response
+}
@@ -0,0 +1,123 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use schema::{
+    books::BorrowReply,
+    ids::UserId,
+    lists::{
+        ChangeReadingListRequest, CreateReadingListRequest, ReadingList, ReadingListEntryRequest,
+        ReadingListsReply, ReadingListsRequest, ReorderReadingListRequest,
+    },
+    session, Integer,
+};
+
+use crate::{
+    config::LoanConfig,
+    error::RouteError,
+    events::Events,
+    service,
+    store::{DynStore, LendOutcome},
+};
+
+/// Returns the reading lists of `user_id` which the requester is allowed to see
+pub async fn reading_lists(
+    Path(user_id): Path<UserId>,
+    State(store): State<DynStore>,
+    Json(request): Json<ReadingListsRequest>,
+) -> Result<Json<ReadingListsReply>, RouteError> {
+    let lists = service::lists::reading_lists(&*store, request.cookie.id, user_id).await?;
+
+    Ok(Json(lists))
+}
+
+pub async fn create_reading_list(
+    State(store): State<DynStore>,
+    Json(request): Json<CreateReadingListRequest>,
+) -> Result<Json<ReadingList>, RouteError> {
+    let list =
+        service::lists::create(&*store, request.cookie.id, request.name, request.is_public).await?;
+
+    Ok(Json(list))
+}
+
+pub async fn change_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(request): Json<ChangeReadingListRequest>,
+) -> Result<(), RouteError> {
+    service::lists::change(
+        &*store,
+        request.cookie.id,
+        list_id,
+        &request.name,
+        request.is_public,
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(cookie): Json<session::Cookie>,
+) -> Result<(), RouteError> {
+    service::lists::delete(&*store, cookie.id, list_id).await?;
+
+    Ok(())
+}
+
+pub async fn add_to_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(request): Json<ReadingListEntryRequest>,
+) -> Result<(), RouteError> {
+    service::lists::add_book(&*store, request.cookie.id, list_id, request.book_id).await?;
+
+    Ok(())
+}
+
+pub async fn remove_from_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(request): Json<ReadingListEntryRequest>,
+) -> Result<(), RouteError> {
+    service::lists::remove_book(&*store, request.cookie.id, list_id, request.book_id).await?;
+
+    Ok(())
+}
+
+pub async fn reorder_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    Json(request): Json<ReorderReadingListRequest>,
+) -> Result<(), RouteError> {
+    service::lists::reorder(&*store, request.cookie.id, list_id, &request.book_ids).await?;
+
+    Ok(())
+}
+
+/// Borrows a book straight from a reading list, taking it off the list if the borrow went through
+pub async fn borrow_from_reading_list(
+    Path(list_id): Path<Integer>,
+    State(store): State<DynStore>,
+    State(loans): State<LoanConfig>,
+    State(events): State<Events>,
+    Json(request): Json<ReadingListEntryRequest>,
+) -> Result<Json<BorrowReply>, RouteError> {
+    let outcome = service::lists::borrow(
+        &*store,
+        &loans,
+        &events,
+        request.cookie.id,
+        list_id,
+        request.book_id,
+    )
+    .await?;
+
+    Ok(Json(BorrowReply {
+        already_borrowed: outcome == LendOutcome::AlreadyBorrowed,
+        no_copies_left: outcome == LendOutcome::NoCopiesLeft,
+    }))
+}
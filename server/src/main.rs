@@ -1,59 +1,126 @@
-use std::str::FromStr;
-
-use axum::{
-    routing::{get, post},
-    Router,
-};
+use axum::{extract::FromRef, middleware, routing::get, Router};
+use clap::Parser;
+use conditional::CatalogRevision;
+use config::{Args, BackupConfig, Command, Config, LoanConfig};
 use error::RouteError;
-use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+use events::Events;
+use store::{Backend, DynStore};
+use tower_http::services::ServeDir;
+use tracing_subscriber::EnvFilter;
 
+mod admin;
+mod api;
 mod auth;
+mod backup;
 mod books;
+mod conditional;
+mod config;
 mod error;
+mod events;
+mod idempotency;
+mod legacy;
+mod lists;
+mod recommendations;
+mod reports;
+mod seed;
+mod service;
+mod store;
+#[cfg(test)]
+mod tests;
 mod utils;
+mod validation;
+
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    store: DynStore,
+    loans: LoanConfig,
+    backups: BackupConfig,
+    events: Events,
+}
+
+impl FromRef<AppState> for CatalogRevision {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.catalog().clone()
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
-
-    let pool = SqlitePool::connect_with(
-        SqliteConnectOptions::from_str("sqlite://data.sqlite?mode=rwc")
-            .unwrap()
-            .foreign_keys(true),
-    )
-    .await
-    .expect("Failed to open database");
-
-    sqlx::migrate!()
-        .run(&pool)
+    let args = Args::parse();
+    let config = Config::load(&args).expect("Failed to load configuration");
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config.log))
+        .init();
+
+    match args.command {
+        // Restoring replaces the database, so it's the one command that doesn't open it
+        Some(Command::Restore(restore)) => exit_on_error(
+            admin::restore(&config, restore.backup.as_deref(), restore.at.as_deref()).await,
+        ),
+        Some(Command::Seed(seed_args)) => {
+            let store = open_store(&config).await;
+            exit_on_error(seed::run(&*store, &config.loans, seed_args).await)
+        }
+        Some(Command::Admin(command)) => {
+            let store = open_store(&config).await;
+            exit_on_error(admin::run(&*store, &config, command).await)
+        }
+        Some(Command::Serve) | None => {
+            let store = open_store(&config).await;
+            serve(config, store).await
+        }
+    }
+}
+
+async fn open_store(config: &Config) -> DynStore {
+    store::open(&config.database)
         .await
-        .expect("failed to run migrations");
-
-    let app = Router::new()
-        .route("/authors", get(books::authors))
-        .route("/books", get(books::books))
-        .route("/borrow", post(books::borrow))
-        .route("/borrows", post(books::borrows))
-        .route("/borrowed-by/:user_id", post(books::borrowed_by))
-        .route("/change-author-details", post(books::change_author_details))
-        .route("/change-book-details", post(books::change_book_details))
-        .route("/delete-book/:book_id", post(books::delete_book))
-        .route(
-            "/lengthen-borrow/:borrow_id",
-            post(books::lengthen_borrow_by),
-        )
-        .route("/end-borrow/:borrow_id", post(books::end_borrow))
-        .route(
-            "/update-borrow-chapters-read/:borrow_id",
-            post(books::update_chapters_read),
-        )
-        .route("/return-book/:borrow_id", post(books::return_book))
-        .nest("/auth", auth::router(pool.clone()))
+        .expect("Failed to open database")
+}
+
+async fn serve(config: Config, store: DynStore) {
+    recommendations::spawn_refresh_task(store.clone());
+    idempotency::spawn_cleanup_task(store.clone());
+    if let Backend::Sqlite(_) = Backend::from_setting(&config.database) {
+        backup::spawn_backup_task(store.clone(), config.backups.clone());
+    }
+
+    let listen = config.listen;
+    let app = app(config, store);
+
+    tracing::info!("Listening on {listen}");
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .expect("Failed to bind the listening address");
+    axum::serve(listener, app).await.expect("Failed to serve");
+}
+
+/// All the routes of the server
+fn app(config: Config, store: DynStore) -> Router {
+    Router::new()
+        .merge(legacy::router())
+        .nest("/api/v1", api::router())
+        .route("/openapi.json", get(api::openapi::openapi_json))
+        .nest_service("/media", ServeDir::new(&config.media_dir))
         .fallback(fallback)
-        .with_state(pool);
+        .layer(middleware::from_fn_with_state(
+            store.clone(),
+            idempotency::replay,
+        ))
+        .with_state(AppState {
+            store,
+            loans: config.loans,
+            backups: config.backups,
+            events: Events::default(),
+        })
+}
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+fn exit_on_error(result: anyhow::Result<()>) {
+    if let Err(err) = result {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
 }
 
 async fn fallback() -> RouteError {
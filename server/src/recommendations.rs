@@ -0,0 +1,246 @@
+//! "Similar books" recommendations.
+//!
+//! Two books are considered similar if they tend to be borrowed by the same readers, if they
+//! share an author or a series and if their genres overlap. Scoring every pair of books is too
+//! expensive to do on each request, so the scores are stored in `BookSimilarities` and rebuilt
+//! periodically by a background task.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use schema::{
+    books::{RecommendationsReply, RecommendationsRequest, SimilarBooksReply},
+    ids::{AuthorId, BookId, UserId},
+    Integer,
+};
+
+use crate::{
+    error::{IntoRouteError, RouteError},
+    service,
+    store::{DynStore, Store},
+    validation::ValidJson,
+};
+
+const CO_BORROWING_WEIGHT: f64 = 1.0;
+const SAME_SERIES_WEIGHT: f64 = 0.5;
+const SHARED_GENRES_WEIGHT: f64 = 0.4;
+const SAME_AUTHOR_WEIGHT: f64 = 0.3;
+
+/// How many similar books are kept for every book
+const SIMILAR_BOOKS_PER_BOOK: usize = 10;
+/// How many books are recommended to a reader at once
+const RECOMMENDATIONS_PER_READER: i64 = 10;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawns a task that rebuilds the similarity table right away and then every [`REFRESH_INTERVAL`]
+pub fn spawn_refresh_task(store: DynStore) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match refresh_similarities(&*store).await {
+                Ok(count) => tracing::info!("Refreshed book similarities, {count} pairs stored"),
+                Err(err) => tracing::error!("Failed to refresh book similarities: {err:?}"),
+            }
+        }
+    });
+}
+
+#[derive(Default)]
+struct BookFeatures {
+    author_id: AuthorId,
+    series: Option<String>,
+    genres: HashSet<Integer>,
+    readers: HashSet<UserId>,
+}
+
+/// Recomputes the similarity between all pairs of books, returning the number of pairs stored
+pub async fn refresh_similarities(store: &dyn Store) -> anyhow::Result<usize> {
+    let inputs = store.similarity_inputs().await?;
+
+    let mut books = inputs
+        .books
+        .into_iter()
+        .map(|(book_id, author_id, series)| {
+            let features = BookFeatures {
+                author_id,
+                series,
+                ..Default::default()
+            };
+
+            (book_id, features)
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (book_id, genre_id) in inputs.genres {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.genres.insert(genre_id);
+        }
+    }
+
+    for (book_id, user_id) in inputs.readers {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.readers.insert(user_id);
+        }
+    }
+
+    let similarities = tokio::task::spawn_blocking(move || compute_similarities(&books)).await?;
+
+    store.replace_similarities(&similarities).await?;
+
+    Ok(similarities.len())
+}
+
+/// A book similar to another, ordered so that the least similar one is on top of a [`BinaryHeap`]
+struct Neighbour {
+    book_id: BookId,
+    score: f64,
+}
+
+impl Ord for Neighbour {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.total_cmp(&self.score)
+    }
+}
+
+impl PartialOrd for Neighbour {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Neighbour {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Neighbour {}
+
+fn compute_similarities(books: &HashMap<BookId, BookFeatures>) -> Vec<(BookId, BookId, f64)> {
+    // Only the most similar books are kept while going through the pairs, most pairs of a large
+    // catalog share an author or a genre
+    let mut neighbours = HashMap::<BookId, BinaryHeap<Neighbour>>::new();
+    let mut keep = |book_id: BookId, neighbour: Neighbour| {
+        let similar = neighbours.entry(book_id).or_default();
+        similar.push(neighbour);
+        if similar.len() > SIMILAR_BOOKS_PER_BOOK {
+            similar.pop();
+        }
+    };
+
+    let books = books.iter().collect::<Vec<_>>();
+    for (index, &(&a_id, a)) in books.iter().enumerate() {
+        for &(&b_id, b) in &books[index + 1..] {
+            let score = similarity(a, b);
+
+            if score > 0.0 {
+                keep(
+                    a_id,
+                    Neighbour {
+                        book_id: b_id,
+                        score,
+                    },
+                );
+                keep(
+                    b_id,
+                    Neighbour {
+                        book_id: a_id,
+                        score,
+                    },
+                );
+            }
+        }
+    }
+
+    neighbours
+        .into_iter()
+        .flat_map(|(book_id, similar)| {
+            similar
+                .into_sorted_vec()
+                .into_iter()
+                .map(move |neighbour| (book_id, neighbour.book_id, neighbour.score))
+        })
+        .collect()
+}
+
+fn similarity(a: &BookFeatures, b: &BookFeatures) -> f64 {
+    let mut score = 0.0;
+
+    // Cosine similarity between the sets of readers of the two books
+    if !a.readers.is_empty() && !b.readers.is_empty() {
+        let common_readers = a.readers.intersection(&b.readers).count() as f64;
+        let norm = ((a.readers.len() * b.readers.len()) as f64).sqrt();
+        score += CO_BORROWING_WEIGHT * common_readers / norm;
+    }
+
+    // Jaccard index of the two sets of genres
+    if !a.genres.is_empty() || !b.genres.is_empty() {
+        let common_genres = a.genres.intersection(&b.genres).count() as f64;
+        let all_genres = a.genres.union(&b.genres).count() as f64;
+        score += SHARED_GENRES_WEIGHT * common_genres / all_genres;
+    }
+
+    if a.series.is_some() && a.series == b.series {
+        score += SAME_SERIES_WEIGHT;
+    }
+
+    if a.author_id == b.author_id {
+        score += SAME_AUTHOR_WEIGHT;
+    }
+
+    score
+}
+
+pub async fn similar_books(
+    Path(book_id): Path<BookId>,
+    State(store): State<DynStore>,
+) -> Result<Json<SimilarBooksReply>, RouteError> {
+    let similar = store
+        .similar_books(book_id)
+        .await
+        .http_internal_error("Failed to fetch similar books")?;
+
+    Ok(Json(similar))
+}
+
+pub async fn recommendations(
+    State(store): State<DynStore>,
+    ValidJson(request): ValidJson<RecommendationsRequest>,
+) -> Result<Json<RecommendationsReply>, RouteError> {
+    let cookie = request.cookie;
+    let reader = service::users::authenticate(&*store, cookie.id, &cookie.password).await?;
+
+    Ok(Json(recommend(&*store, reader.user_id).await?))
+}
+
+/// Recommends books similar to those the reader has borrowed in the past, falling back to the
+/// most popular books they haven't read when none of the similar books are new to them
+pub async fn recommend(
+    store: &dyn Store,
+    user_id: UserId,
+) -> Result<RecommendationsReply, RouteError> {
+    let recommendations = store
+        .recommendations(user_id, RECOMMENDATIONS_PER_READER)
+        .await
+        .http_internal_error("Failed to fetch recommendations")?;
+
+    if !recommendations.is_empty() {
+        return Ok(recommendations);
+    }
+
+    store
+        .popular_books(user_id, RECOMMENDATIONS_PER_READER)
+        .await
+        .http_internal_error("Failed to fetch popular books")
+}
@@ -0,0 +1,159 @@
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use schema::{
+    dates::Timestamp,
+    endpoints::Report,
+    errors::ErrorCode,
+    reports::{ReportFormat, ReportRequest},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{IntoRouteError, RouteError},
+    store::{DynStore, Store},
+    utils::verify_user_is_librarian,
+};
+
+#[derive(Deserialize)]
+pub struct ReportParams {
+    #[serde(default)]
+    format: ReportFormat,
+}
+
+/// Returns the reported period as `[from, to)` in unix seconds, unbounded on the missing sides
+pub fn period(from: Option<Timestamp>, to: Option<Timestamp>) -> Result<(i64, i64), RouteError> {
+    let from = from.map_or(i64::MIN, |from| from.unix());
+    let to = to.map_or(i64::MAX, |to| to.unix());
+
+    if from > to {
+        return Err(RouteError::new_bad_request()
+            .set_public_error_message("The start of the period must come before its end")
+            .set_error_code(ErrorCode::InvalidPeriod));
+    }
+
+    Ok((from, to))
+}
+
+fn into_report<T: Serialize>(
+    name: &str,
+    rows: Vec<T>,
+    format: ReportFormat,
+) -> Result<Response, RouteError> {
+    match format {
+        ReportFormat::Json => Ok(Json(rows).into_response()),
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for row in rows {
+                writer
+                    .serialize(row)
+                    .http_internal_error("Failed to write CSV")?;
+            }
+            let body = writer
+                .into_inner()
+                .http_internal_error("Failed to write CSV")?;
+
+            Ok((
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{name}.csv\""),
+                    ),
+                ],
+                body,
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Computes the report called `name` over `[from, to)`
+pub async fn report(
+    store: &dyn Store,
+    name: &str,
+    (from, to): (i64, i64),
+    format: ReportFormat,
+) -> Result<Response, RouteError> {
+    match name {
+        "most-borrowed" => {
+            let rows = store
+                .most_borrowed(from, to)
+                .await
+                .http_internal_error("Failed to compute most borrowed books")?;
+            into_report(name, rows, format)
+        }
+        "never-borrowed" => {
+            let rows = store
+                .never_borrowed(from, to)
+                .await
+                .http_internal_error("Failed to compute never borrowed books")?;
+            into_report(name, rows, format)
+        }
+        "loans" => {
+            let row = store
+                .loan_status(from, to)
+                .await
+                .http_internal_error("Failed to compute loan status")?;
+            match format {
+                ReportFormat::Json => Ok(Json(row).into_response()),
+                ReportFormat::Csv => into_report(name, vec![row], format),
+            }
+        }
+        "author-popularity" => {
+            let rows = store
+                .author_popularity(from, to)
+                .await
+                .http_internal_error("Failed to compute author popularity")?;
+            into_report(name, rows, format)
+        }
+        "registrations" => {
+            let rows = store
+                .registrations(from, to)
+                .await
+                .http_internal_error("Failed to compute registrations")?;
+            into_report(name, rows, format)
+        }
+        "utilisation" => {
+            let rows = store
+                .utilisation(from, to)
+                .await
+                .http_internal_error("Failed to compute utilisation")?;
+            into_report(name, rows, format)
+        }
+        _ => Err(RouteError::new_not_found().set_error_code(ErrorCode::NoSuchReport)),
+    }
+}
+
+/// The report `R`, as its JSON reply or as CSV
+pub struct LegacyReport<R>(Response, PhantomData<R>);
+
+impl<R> IntoResponse for LegacyReport<R> {
+    fn into_response(self) -> Response {
+        self.0
+    }
+}
+
+/// Sends the report `R`, taking the requester and the period from the body
+pub async fn legacy_report<R: Report>(
+    Query(params): Query<ReportParams>,
+    State(store): State<DynStore>,
+    Json(request): Json<ReportRequest>,
+) -> Result<LegacyReport<R>, RouteError> {
+    verify_user_is_librarian(&*store, request.cookie).await?;
+    let period = period(request.from, request.to)?;
+
+    let response = report(&*store, R::NAME, period, params.format).await?;
+    Ok(LegacyReport(response, PhantomData))
+}
+
+/// Answers the legacy report routes that aren't one of the [`Report`]s
+pub async fn unknown_report() -> RouteError {
+    RouteError::new_not_found().set_error_code(ErrorCode::NoSuchReport)
+}
@@ -0,0 +1,380 @@
+//! Fixture sets for filling a database with data, used through the `seed` command.
+//!
+//! * `demo`: a handful of real authors and books, handy while working on the app
+//! * `empty`: no data at all, combined with `--reset` it wipes a database
+//! * `large`: a synthetic library of configurable size for performance testing. The generator is
+//!   seeded, so the same arguments always produce the same data, give or take dates, which are
+//!   relative to the moment the data is generated.
+
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::bail;
+use argon2::{
+    password_hash::{PasswordHasher, SaltString},
+    Argon2,
+};
+use chrono::Utc;
+use clap::ValueEnum;
+use schema::{
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, UserKind},
+};
+
+use crate::{
+    config::LoanConfig,
+    store::{BookDetails, NewAuthor, NewUser, Store},
+};
+
+/// Password of every user created by the synthetic fixture set
+const SYNTHETIC_PASSWORD: &str = "parola";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Fixture {
+    Demo,
+    Empty,
+    Large,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SeedArgs {
+    /// The fixture set to load
+    fixture: Fixture,
+
+    /// Delete all existing data first, otherwise only an empty database can be seeded
+    #[arg(long)]
+    reset: bool,
+
+    /// Seed of the synthetic data generator
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// Number of synthetic authors
+    #[arg(long, default_value_t = 1_000)]
+    authors: usize,
+
+    /// Number of synthetic books
+    #[arg(long, default_value_t = 10_000)]
+    books: usize,
+
+    /// Number of synthetic readers
+    #[arg(long, default_value_t = 5_000)]
+    users: usize,
+
+    /// Number of synthetic loans, a fifth of them still active and the rest already returned
+    #[arg(long, default_value_t = 20_000)]
+    loans: usize,
+}
+
+pub async fn run(store: &dyn Store, loans: &LoanConfig, args: SeedArgs) -> anyhow::Result<()> {
+    if args.reset {
+        store.reset().await?;
+    } else if !store.is_empty().await? {
+        bail!("The database already contains data, pass --reset to replace it");
+    }
+
+    match args.fixture {
+        Fixture::Demo => store.load_demo().await?,
+        Fixture::Empty => {}
+        Fixture::Large => {
+            let library = generate(loans, &args)?;
+            store.load_library(&library).await?;
+
+            tracing::info!(
+                "Generated {} authors, {} books, {} readers and {} loans, every user's password \
+                 is '{SYNTHETIC_PASSWORD}' and the librarian is bibliotecar@example.com",
+                library.authors.len(),
+                library.books.len(),
+                library.users.len() - 1,
+                library.loans.len()
+            );
+        }
+    }
+
+    tracing::info!("Loaded the {:?} fixture set", args.fixture);
+
+    Ok(())
+}
+
+/// SplitMix64, small and good enough for test data, and unlike an OS backed generator it's
+/// reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty
+    fn range(&mut self, range: std::ops::Range<i64>) -> i64 {
+        range.start + (self.next() % (range.end - range.start) as u64) as i64
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.next() as usize % items.len()]
+    }
+}
+
+const FIRST_NAMES: &[&str] = &[
+    "Ana", "Andrei", "Elena", "Mihai", "Ioana", "Vlad", "Maria", "Radu", "Irina", "Tudor", "Sofia",
+    "Matei", "Oana", "Stefan", "Daria", "Luca",
+];
+const LAST_NAMES: &[&str] = &[
+    "Popescu",
+    "Ionescu",
+    "Dumitru",
+    "Stan",
+    "Stoica",
+    "Gheorghe",
+    "Rusu",
+    "Munteanu",
+    "Matei",
+    "Constantin",
+    "Serban",
+    "Moldovan",
+    "Lazar",
+    "Florea",
+];
+const TITLE_WORDS: &[&str] = &[
+    "Umbra", "Casa", "Drumul", "Noaptea", "Marea", "Ceasul", "Orașul", "Grădina", "Tăcerea",
+    "Focul", "Iarna", "Cartea", "Insula", "Pădurea", "Visul", "Zidul",
+];
+const TITLE_COMPLEMENTS: &[&str] = &[
+    "uitată",
+    "de sticlă",
+    "fără nume",
+    "din nord",
+    "pierdută",
+    "de argint",
+    "ascunsă",
+    "tăcută",
+    "de dincolo",
+    "de ieri",
+];
+const PUBLISHERS: &[&str] = &[
+    "Polirom",
+    "Humanitas",
+    "Nemira",
+    "Litera",
+    "Art",
+    "Trei",
+    "Corint",
+    "Paralela 45",
+];
+const GENRES: &[&str] = &[
+    "Roman",
+    "Poezie",
+    "Mister",
+    "Fantastic",
+    "Science fiction",
+    "Istorie",
+    "Biografie",
+    "Umor",
+    "Teatru",
+    "Groază",
+    "Aventură",
+    "Eseu",
+];
+
+/// A synthetic library, in which records refer to each other by their index
+pub struct Library {
+    pub authors: Vec<NewAuthor>,
+    pub books: Vec<LibraryBook>,
+    /// The first user is the librarian
+    pub users: Vec<NewUser>,
+    pub loans: Vec<LibraryLoan>,
+}
+
+pub struct LibraryBook {
+    /// Index into [`Library::authors`], `details.author_id` is ignored
+    pub author: usize,
+    pub details: BookDetails,
+}
+
+pub struct LibraryLoan {
+    /// Index into [`Library::books`]
+    pub book: usize,
+    /// Index into [`Library::users`]
+    pub user: usize,
+    pub borrowed_at: i64,
+    /// `None` for loans that are still active
+    pub returned_at: Option<i64>,
+    pub valid_until: i64,
+    pub chapters_read: i64,
+}
+
+fn generate(loans: &LoanConfig, args: &SeedArgs) -> anyhow::Result<Library> {
+    if args.authors == 0 && args.books > 0 {
+        bail!("Books can't be generated without authors");
+    }
+
+    let mut rng = Rng(args.seed);
+    let now = Utc::now().timestamp();
+
+    let mut authors = vec![];
+    for index in 0..args.authors {
+        let name = format!("{} {} {index}", rng.pick(FIRST_NAMES), rng.pick(LAST_NAMES));
+        let born = rng.range(-170 * 365..-20 * 365) * SECONDS_PER_DAY;
+        let died = rng
+            .chance(40)
+            .then(|| born + rng.range(30 * 365..90 * 365) * SECONDS_PER_DAY)
+            .filter(|&date| date < now);
+        // Some of the older authors are only known to be born around some year
+        let date_of_birth = if born < -100 * 365 * SECONDS_PER_DAY && rng.chance(20) {
+            PartialDate::from_year(day(born).year()).unwrap().circa()
+        } else {
+            day(born)
+        };
+
+        authors.push(NewAuthor {
+            name,
+            date_of_birth,
+            date_of_death: died.map(day),
+            description: format!("Autor generat automat (#{index})"),
+        });
+    }
+
+    let mut books = vec![];
+    for index in 0..args.books {
+        let title = format!(
+            "{} {} {index}",
+            rng.pick(TITLE_WORDS),
+            rng.pick(TITLE_COMPLEMENTS)
+        );
+        let author = rng.range(0..authors.len() as i64) as usize;
+        let publish_date = now - rng.range(0..100 * 365) * SECONDS_PER_DAY;
+        let publisher = rng.pick(PUBLISHERS).to_string();
+        let count = rng.range(0..8);
+        let synopsis = format!("Rezumatul cărții „{title}”");
+        let language = if rng.chance(70) { "ro" } else { "en" }.to_string();
+        let series = rng
+            .chance(15)
+            .then(|| format!("Seria {}", rng.range(0..(args.books as i64 / 5).max(1))));
+        let genres = (0..rng.range(1..4))
+            .map(|_| rng.pick(GENRES).to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        books.push(LibraryBook {
+            author,
+            details: BookDetails {
+                title,
+                author_id: AuthorId::default(),
+                publish_date: day(publish_date),
+                publisher,
+                count,
+                synopsis,
+                language,
+                series,
+                genres,
+            },
+        });
+    }
+
+    // Hashing is slow on purpose, so every synthetic user shares the same hash. The salt is fixed
+    // to keep the output reproducible.
+    let salt = SaltString::from_b64("c3ludGhldGljdXNlcnM").map_err(anyhow::Error::msg)?;
+    let password_hash = Argon2::default()
+        .hash_password(SYNTHETIC_PASSWORD.as_bytes(), &salt)
+        .map_err(anyhow::Error::msg)?
+        .to_string();
+
+    let mut users = vec![NewUser {
+        name: "Bibliotecar".into(),
+        kind: UserKind::Librarian,
+        email: "bibliotecar@example.com".into(),
+        password_hash: password_hash.clone(),
+        registered_at: timestamp(now),
+    }];
+    for index in 0..args.users {
+        let name = format!("{} {}", rng.pick(FIRST_NAMES), rng.pick(LAST_NAMES));
+        let registered_at = now - rng.range(0..3 * 365) * SECONDS_PER_DAY;
+
+        users.push(NewUser {
+            name,
+            kind: UserKind::Reader,
+            email: format!("cititor{index}@example.com"),
+            password_hash: password_hash.clone(),
+            registered_at: timestamp(registered_at),
+        });
+    }
+
+    let mut copies = books
+        .iter()
+        .map(|book| book.details.count)
+        .collect::<Vec<_>>();
+    let mut active = HashSet::<(usize, usize)>::new();
+    let mut lent = vec![];
+    // Gives up on loans that can't be placed instead of looping forever on a library that's too
+    // small for the requested number of loans
+    for _ in 0..args.loans * 4 {
+        if lent.len() == args.loans || books.is_empty() || args.users == 0 {
+            break;
+        }
+
+        let book = rng.range(0..books.len() as i64) as usize;
+        // Readers only, the librarian doesn't borrow
+        let user = rng.range(1..users.len() as i64) as usize;
+        let is_returned = rng.chance(80);
+
+        let loan = if is_returned {
+            let borrowed_at = now - rng.range(31..2 * 365) * SECONDS_PER_DAY;
+            let returned_at = borrowed_at + rng.range(1..30) * SECONDS_PER_DAY;
+
+            LibraryLoan {
+                book,
+                user,
+                borrowed_at,
+                returned_at: Some(returned_at),
+                valid_until: borrowed_at + i64::from(loans.days) * SECONDS_PER_DAY,
+                chapters_read: 0,
+            }
+        } else {
+            if copies[book] == 0 || active.contains(&(book, user)) {
+                continue;
+            }
+            let borrowed_at = now - rng.range(0..45) * SECONDS_PER_DAY;
+            let chapters_read = rng.range(0..20);
+
+            copies[book] -= 1;
+            active.insert((book, user));
+
+            LibraryLoan {
+                book,
+                user,
+                borrowed_at,
+                returned_at: None,
+                valid_until: borrowed_at + i64::from(loans.days) * SECONDS_PER_DAY,
+                chapters_read,
+            }
+        };
+
+        lent.push(loan);
+    }
+
+    Ok(Library {
+        authors,
+        books,
+        users,
+        loans: lent,
+    })
+}
+
+fn timestamp(unix: i64) -> Timestamp {
+    Timestamp::from_unix(unix).expect("generated dates are recent")
+}
+
+/// The day `unix` falls on in UTC
+fn day(unix: i64) -> PartialDate {
+    timestamp(unix).to_utc().date_naive().into()
+}
@@ -0,0 +1,309 @@
+use chrono::Days;
+use schema::{
+    books::{Author, Book, Borrow, BorrowedBook, LoanChanges},
+    dates::Timestamp,
+    errors::{FieldError, FieldErrorCode},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+};
+
+use super::{users::require_librarian, ServiceError, ServiceResult};
+use crate::{
+    config::LoanConfig,
+    events::Events,
+    store::{BookDetails, LendOutcome, NewAuthor, SaveOutcome, Store},
+};
+
+/// When a borrow made at `now` has to be returned, the same time of day `loans.days` later
+pub fn due_date(loans: &LoanConfig, now: Timestamp) -> Timestamp {
+    (now.to_utc() + Days::new(loans.days.into())).into()
+}
+
+pub async fn book(store: &dyn Store, book_id: BookId) -> ServiceResult<Book> {
+    store.book(book_id).await?.ok_or(ServiceError::NoSuchBook)
+}
+
+/// Lends a copy of `book_id` to `user_id` for the configured number of days, see
+/// [`Store::lend_book`]. `checked_out_by` is the librarian that handed the book over, if any.
+pub async fn lend(
+    store: &dyn Store,
+    loans: &LoanConfig,
+    events: &Events,
+    book_id: BookId,
+    user_id: UserId,
+    checked_out_by: Option<UserId>,
+) -> ServiceResult<LendOutcome> {
+    let outcome = store
+        .lend_book(
+            book_id,
+            user_id,
+            checked_out_by,
+            due_date(loans, Timestamp::now()),
+        )
+        .await?
+        .ok_or(ServiceError::NoSuchBook)?;
+
+    if outcome == LendOutcome::Lent {
+        events.lent(store, user_id, book_id).await;
+    }
+
+    Ok(outcome)
+}
+
+/// Lends a book to a reader at the desk, on the librarian's behalf
+pub async fn checkout(
+    store: &dyn Store,
+    loans: &LoanConfig,
+    events: &Events,
+    librarian_id: UserId,
+    book_id: BookId,
+    user_id: UserId,
+) -> ServiceResult<LendOutcome> {
+    require_librarian(store, librarian_id).await?;
+
+    if store.user_kind(user_id).await?.is_none() {
+        return Err(ServiceError::NoSuchUser);
+    }
+
+    lend(store, loans, events, book_id, user_id, Some(librarian_id)).await
+}
+
+/// The books `user_id` has borrowed, which only they and librarians may see
+pub async fn borrowed_by(
+    store: &dyn Store,
+    requester_id: UserId,
+    user_id: UserId,
+) -> ServiceResult<Vec<BorrowedBook>> {
+    if requester_id != user_id {
+        require_librarian(store, requester_id).await?;
+    }
+
+    Ok(store.borrowed_by(user_id).await?)
+}
+
+pub async fn borrows(store: &dyn Store, requester_id: UserId) -> ServiceResult<Vec<Borrow>> {
+    require_librarian(store, requester_id).await?;
+
+    Ok(store.borrows().await?)
+}
+
+/// Updates `book_id`, or adds a new book if it's `None`. Blank series and genres are dropped.
+pub async fn save_book(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    book_id: Option<BookId>,
+    mut details: BookDetails,
+) -> ServiceResult<BookId> {
+    require_librarian(store, requester_id).await?;
+
+    if let Some(book_id) = book_id {
+        if store.book(book_id).await?.is_none() {
+            return Err(ServiceError::NoSuchBook);
+        }
+    }
+
+    details.series = details
+        .series
+        .map(|series| series.trim().to_string())
+        .filter(|series| !series.is_empty());
+    details.genres = details
+        .genres
+        .iter()
+        .map(|genre| genre.trim())
+        .filter(|genre| !genre.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let book_id = match store.save_book(book_id, &details).await? {
+        SaveOutcome::Saved(book_id) => book_id,
+        SaveOutcome::CopiesLent(lent) => {
+            return Err(ServiceError::InvalidFields(vec![FieldError {
+                field: "count".to_string(),
+                code: FieldErrorCode::TooSmall,
+                message: format!("{lent} copies of this book are borrowed right now"),
+            }]));
+        }
+    };
+    events.book_saved(store, book_id).await;
+
+    Ok(book_id)
+}
+
+/// Deletes a book, as long as none of its copies are borrowed
+pub async fn delete_book(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    book_id: BookId,
+) -> ServiceResult<()> {
+    require_librarian(store, requester_id).await?;
+
+    if !store.delete_book(book_id).await? {
+        return Err(ServiceError::BookStillBorrowed);
+    }
+    events.book_deleted(book_id);
+
+    Ok(())
+}
+
+/// Adds an author. Giving the id of an existing one is refused, authors can't be edited.
+pub async fn add_author(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    author_id: Option<AuthorId>,
+    author: &NewAuthor,
+) -> ServiceResult<AuthorId> {
+    require_librarian(store, requester_id).await?;
+
+    if author_id.is_some() {
+        return Err(ServiceError::AuthorIsReadOnly);
+    }
+
+    let author_id = store.add_author(author).await?;
+    events.author_added(Author {
+        author_id,
+        name: author.name.clone(),
+        date_of_birth: author.date_of_birth,
+        date_of_death: author.date_of_death,
+        description: author.description.clone(),
+    });
+
+    Ok(author_id)
+}
+
+/// Makes a borrow due right away
+pub async fn end_borrow(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+) -> ServiceResult<()> {
+    require_librarian(store, requester_id).await?;
+    require_borrow(store, borrow_id).await?;
+
+    store.set_valid_until(borrow_id, Timestamp::now()).await?;
+    events.loan_saved(store, borrow_id).await;
+
+    Ok(())
+}
+
+/// Pushes the due date of a borrow back by `days`, up to the configured maximum at once
+pub async fn lengthen_borrow(
+    store: &dyn Store,
+    loans: &LoanConfig,
+    events: &Events,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+    days: i64,
+) -> ServiceResult<()> {
+    require_librarian(store, requester_id).await?;
+
+    if days > loans.max_extension_days.into() {
+        return Err(ServiceError::ExtensionTooLong {
+            max_days: loans.max_extension_days,
+        });
+    }
+    require_borrow(store, borrow_id).await?;
+
+    store.lengthen_borrow(borrow_id, days).await?;
+    events.loan_saved(store, borrow_id).await;
+
+    Ok(())
+}
+
+/// Makes every change to a loan, or none of them: who may make each one is checked before any is
+/// made. Lengthening and ending loans is for librarians, chapters are also set by the borrower.
+pub async fn change_loan(
+    store: &dyn Store,
+    loans: &LoanConfig,
+    events: &Events,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+    changes: &LoanChanges,
+) -> ServiceResult<()> {
+    if changes.lengthen_by_days.is_some() || changes.due_now {
+        require_librarian(store, requester_id).await?;
+    }
+    if let Some(days) = changes.lengthen_by_days {
+        if days > loans.max_extension_days.into() {
+            return Err(ServiceError::ExtensionTooLong {
+                max_days: loans.max_extension_days,
+            });
+        }
+    }
+    require_borrower(store, requester_id, borrow_id).await?;
+
+    if let Some(days) = changes.lengthen_by_days {
+        store.lengthen_borrow(borrow_id, days).await?;
+    }
+    if changes.due_now {
+        store.set_valid_until(borrow_id, Timestamp::now()).await?;
+    }
+    if let Some(chapters_read) = changes.chapters_read {
+        store.set_chapters_read(borrow_id, chapters_read).await?;
+    }
+    events.loan_saved(store, borrow_id).await;
+
+    Ok(())
+}
+
+/// Checks that `borrow_id` exists
+async fn require_borrow(store: &dyn Store, borrow_id: BorrowId) -> ServiceResult<()> {
+    match store.borrower(borrow_id).await? {
+        Some(_) => Ok(()),
+        None => Err(ServiceError::NoSuchBorrow),
+    }
+}
+
+/// Checks that `borrow_id` exists and is either the requester's own or they're a librarian
+async fn require_borrower(
+    store: &dyn Store,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+) -> ServiceResult<()> {
+    let borrower_id = store
+        .borrower(borrow_id)
+        .await?
+        .ok_or(ServiceError::NoSuchBorrow)?;
+
+    if borrower_id == requester_id {
+        return Ok(());
+    }
+
+    match store.user_kind(requester_id).await? {
+        None => Err(ServiceError::UnknownRequester),
+        Some(UserKind::Librarian) => Ok(()),
+        Some(_) => Err(ServiceError::NotBorrower),
+    }
+}
+
+pub async fn set_chapters_read(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+    chapters_read: i64,
+) -> ServiceResult<()> {
+    require_borrower(store, requester_id, borrow_id).await?;
+
+    store.set_chapters_read(borrow_id, chapters_read).await?;
+    events.loan_saved(store, borrow_id).await;
+
+    Ok(())
+}
+
+pub async fn return_book(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    borrow_id: BorrowId,
+) -> ServiceResult<()> {
+    require_borrower(store, requester_id, borrow_id).await?;
+
+    let loan = events.loan(store, borrow_id).await;
+    store.return_book(borrow_id).await?;
+    events.returned(store, loan).await;
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use schema::{
+    ids::{BookId, UserId},
+    lists::ReadingList,
+    Integer,
+};
+
+use super::{books::lend, ServiceError, ServiceResult};
+use crate::{
+    config::LoanConfig,
+    events::Events,
+    store::{LendOutcome, Store},
+};
+
+/// Checks that `list_id` exists and belongs to `requester_id`
+async fn require_owner(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+) -> ServiceResult<()> {
+    match store.reading_list_owner(list_id).await? {
+        None => Err(ServiceError::NoSuchReadingList),
+        Some(owner_id) if owner_id != requester_id => Err(ServiceError::NotListOwner),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Lists are unique by name for each owner
+fn duplicate_name_is_taken(err: sqlx::Error) -> ServiceError {
+    match err {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            ServiceError::ListNameTaken
+        }
+        err => err.into(),
+    }
+}
+
+/// The reading lists of `user_id` which the requester is allowed to see, that is all of them if
+/// they're asking for their own lists, and only the public ones otherwise
+pub async fn reading_lists(
+    store: &dyn Store,
+    requester_id: UserId,
+    user_id: UserId,
+) -> ServiceResult<Vec<ReadingList>> {
+    Ok(store.reading_lists(user_id, requester_id).await?)
+}
+
+pub async fn create(
+    store: &dyn Store,
+    requester_id: UserId,
+    name: String,
+    is_public: bool,
+) -> ServiceResult<ReadingList> {
+    if name.trim().is_empty() {
+        return Err(ServiceError::BlankListName);
+    }
+
+    let list_id = store
+        .create_reading_list(requester_id, &name, is_public)
+        .await
+        .map_err(duplicate_name_is_taken)?;
+
+    Ok(ReadingList {
+        list_id,
+        owner_id: requester_id,
+        name,
+        is_public,
+        book_ids: vec![],
+    })
+}
+
+pub async fn change(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+    name: &str,
+    is_public: bool,
+) -> ServiceResult<()> {
+    require_owner(store, requester_id, list_id).await?;
+
+    if name.trim().is_empty() {
+        return Err(ServiceError::BlankListName);
+    }
+
+    store
+        .change_reading_list(list_id, name, is_public)
+        .await
+        .map_err(duplicate_name_is_taken)
+}
+
+pub async fn delete(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+) -> ServiceResult<()> {
+    require_owner(store, requester_id, list_id).await?;
+
+    Ok(store.delete_reading_list(list_id).await?)
+}
+
+pub async fn add_book(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+    book_id: BookId,
+) -> ServiceResult<()> {
+    require_owner(store, requester_id, list_id).await?;
+
+    store
+        .add_to_reading_list(list_id, book_id)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+                ServiceError::NoSuchBook
+            }
+            err => err.into(),
+        })
+}
+
+pub async fn remove_book(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+    book_id: BookId,
+) -> ServiceResult<()> {
+    require_owner(store, requester_id, list_id).await?;
+
+    Ok(store.remove_from_reading_list(list_id, book_id).await?)
+}
+
+/// Puts the books on a list in the order of `book_ids`, which must hold exactly the books on it
+pub async fn reorder(
+    store: &dyn Store,
+    requester_id: UserId,
+    list_id: Integer,
+    book_ids: &[BookId],
+) -> ServiceResult<()> {
+    require_owner(store, requester_id, list_id).await?;
+
+    if !store.reorder_reading_list(list_id, book_ids).await? {
+        return Err(ServiceError::WrongListOrder);
+    }
+
+    Ok(())
+}
+
+/// Borrows a book straight from a reading list, taking it off the list if the borrow went through
+pub async fn borrow(
+    store: &dyn Store,
+    loans: &LoanConfig,
+    events: &Events,
+    requester_id: UserId,
+    list_id: Integer,
+    book_id: BookId,
+) -> ServiceResult<LendOutcome> {
+    require_owner(store, requester_id, list_id).await?;
+
+    if !store.reading_list_contains(list_id, book_id).await? {
+        return Err(ServiceError::NotOnList);
+    }
+
+    let outcome = lend(store, loans, events, book_id, requester_id, None).await?;
+
+    if outcome == LendOutcome::Lent {
+        store.remove_from_reading_list(list_id, book_id).await?;
+    }
+
+    Ok(outcome)
+}
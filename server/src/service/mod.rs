@@ -0,0 +1,176 @@
+//! The library's rules, apart from HTTP.
+//!
+//! Handlers take the request apart and turn the outcome into a reply, everything in between
+//! happens here: who may do what, when a book can be lent or deleted, and so on. Failures are
+//! [`ServiceError`]s, which say what went wrong in terms of the library and are turned into a
+//! [`RouteError`] with the matching status code at the edge.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use axum::http::StatusCode;
+use schema::errors::{ErrorCode, FieldError};
+
+use crate::error::RouteError;
+
+pub mod books;
+pub mod lists;
+#[cfg(test)]
+mod tests;
+pub mod users;
+
+pub type ServiceResult<T> = Result<T, ServiceError>;
+
+#[derive(Debug)]
+pub enum ServiceError {
+    /// The account making the request doesn't exist
+    UnknownRequester,
+    /// Only librarians may do this
+    NotLibrarian,
+    NoSuchBook,
+    NoSuchUser,
+    NoSuchAccount,
+    WrongPassword,
+    /// An account needs a name, an email and a password
+    MissingAccountDetails,
+    EmailTaken,
+    /// Authors can only be added, not edited
+    AuthorIsReadOnly,
+    ExtensionTooLong {
+        max_days: u32,
+    },
+    /// Books are only deleted once every copy has been returned
+    BookStillBorrowed,
+    CannotDeleteSelf,
+    /// Users are only deleted once they've returned every book
+    UserStillHasBooks,
+    NoSuchBorrow,
+    /// Borrows are only changed by their reader, or by a librarian
+    NotBorrower,
+    NoSuchReadingList,
+    /// Reading lists are only changed by their owner
+    NotListOwner,
+    BlankListName,
+    ListNameTaken,
+    NotOnList,
+    /// A reading list can only be reordered into an arrangement of the books already on it
+    WrongListOrder,
+    /// The request breaks rules that depend on what is already stored
+    InvalidFields(Vec<FieldError>),
+    Database(sqlx::Error),
+    Internal(anyhow::Error),
+}
+
+impl ServiceError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnknownRequester | Self::WrongPassword => StatusCode::UNAUTHORIZED,
+            Self::NotLibrarian
+            | Self::BookStillBorrowed
+            | Self::NotBorrower
+            | Self::NotListOwner => StatusCode::FORBIDDEN,
+            Self::NoSuchBook
+            | Self::NoSuchUser
+            | Self::NoSuchAccount
+            | Self::NoSuchBorrow
+            | Self::NoSuchReadingList
+            | Self::NotOnList => StatusCode::NOT_FOUND,
+            Self::MissingAccountDetails
+            | Self::AuthorIsReadOnly
+            | Self::ExtensionTooLong { .. }
+            | Self::BlankListName
+            | Self::WrongListOrder => StatusCode::BAD_REQUEST,
+            Self::EmailTaken
+            | Self::CannotDeleteSelf
+            | Self::UserStillHasBooks
+            | Self::ListNameTaken => StatusCode::CONFLICT,
+            Self::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Database(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::UnknownRequester => ErrorCode::Unauthorized,
+            Self::NotLibrarian => ErrorCode::NotLibrarian,
+            Self::NoSuchBook => ErrorCode::NoSuchBook,
+            Self::NoSuchUser => ErrorCode::NoSuchUser,
+            Self::NoSuchAccount => ErrorCode::NoSuchAccount,
+            Self::WrongPassword => ErrorCode::WrongPassword,
+            Self::MissingAccountDetails => ErrorCode::MissingAccountDetails,
+            Self::EmailTaken => ErrorCode::EmailTaken,
+            Self::AuthorIsReadOnly => ErrorCode::AuthorIsReadOnly,
+            Self::ExtensionTooLong { .. } => ErrorCode::ExtensionTooLong,
+            Self::BookStillBorrowed => ErrorCode::BookStillBorrowed,
+            Self::CannotDeleteSelf => ErrorCode::CannotDeleteSelf,
+            Self::UserStillHasBooks => ErrorCode::UserStillHasBooks,
+            Self::NoSuchBorrow => ErrorCode::NoSuchBorrow,
+            Self::NotBorrower => ErrorCode::NotBorrower,
+            Self::NoSuchReadingList => ErrorCode::NoSuchReadingList,
+            Self::NotListOwner => ErrorCode::NotListOwner,
+            Self::BlankListName => ErrorCode::BlankListName,
+            Self::ListNameTaken => ErrorCode::ListNameTaken,
+            Self::NotOnList => ErrorCode::NotOnList,
+            Self::WrongListOrder => ErrorCode::WrongListOrder,
+            Self::InvalidFields(_) => ErrorCode::InvalidFields,
+            Self::Database(_) | Self::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+impl Display for ServiceError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::UnknownRequester => write!(f, "You are not authorised to access this endpoint"),
+            Self::NotLibrarian => write!(f, "Only librarians can do this"),
+            Self::NoSuchBook => write!(f, "No book with given id"),
+            Self::NoSuchUser => write!(f, "No user with given id"),
+            Self::NoSuchAccount => write!(f, "No account with given email"),
+            Self::WrongPassword => write!(f, "Wrong password"),
+            Self::MissingAccountDetails => write!(f, "The name, email and password are required"),
+            Self::EmailTaken => write!(f, "An account with this email already exists"),
+            Self::AuthorIsReadOnly => write!(f, "Authors can't be edited"),
+            Self::ExtensionTooLong { max_days } => write!(
+                f,
+                "A borrow can be lengthened by at most {max_days} days at once"
+            ),
+            Self::BookStillBorrowed => write!(f, "Some copies of this book are still borrowed"),
+            Self::CannotDeleteSelf => write!(f, "You can't delete your own account"),
+            Self::UserStillHasBooks => write!(f, "The user still has borrowed books"),
+            Self::NoSuchBorrow => write!(f, "No borrow with given id"),
+            Self::NotBorrower => write!(f, "This book was borrowed by someone else"),
+            Self::NoSuchReadingList => write!(f, "No reading list with given id"),
+            Self::NotListOwner => write!(f, "This reading list belongs to someone else"),
+            Self::BlankListName => write!(f, "The name of a reading list can't be blank"),
+            Self::ListNameTaken => write!(f, "You already have a reading list with this name"),
+            Self::NotOnList => write!(f, "The book is not on this reading list"),
+            Self::WrongListOrder => write!(
+                f,
+                "The new order must contain exactly the books on the list"
+            ),
+            Self::InvalidFields(_) => write!(f, "Some fields are invalid"),
+            Self::Database(_) => write!(f, "Failed to access the database"),
+            Self::Internal(_) => write!(f, "An unexpected error occurred"),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ServiceError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<ServiceError> for RouteError {
+    fn from(err: ServiceError) -> Self {
+        let route_error = RouteError::from_status(err.status_code())
+            .set_public_error_message(&err.to_string())
+            .set_error_code(err.code());
+
+        match err {
+            ServiceError::Database(err) => route_error.set_error(err.into()),
+            ServiceError::Internal(err) => route_error.set_error(err),
+            ServiceError::InvalidFields(details) => route_error.set_details(details),
+            _ => route_error,
+        }
+    }
+}
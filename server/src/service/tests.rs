@@ -0,0 +1,361 @@
+//! The rules of the service layer, checked against an in-memory SQLite database
+
+use chrono::{TimeZone, Utc};
+use schema::{
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    Integer,
+};
+
+use super::{books, users, ServiceError};
+use crate::{
+    config::LoanConfig,
+    events::Events,
+    store::{BookDetails, LendOutcome, NewAuthor, NewUser, SaveOutcome, SqliteStore, Store},
+};
+
+const DAY: i64 = 24 * 60 * 60;
+
+const LOANS: LoanConfig = LoanConfig {
+    days: 14,
+    max_extension_days: 30,
+};
+
+async fn add_user(store: &dyn Store, email: &str, kind: UserKind) -> UserId {
+    let user = NewUser {
+        name: email.split('@').next().unwrap().into(),
+        kind,
+        email: email.into(),
+        password_hash: "hash".into(),
+        registered_at: Timestamp::default(),
+    };
+
+    store.create_user(&user).await.unwrap()
+}
+
+fn new_author(name: &str) -> NewAuthor {
+    NewAuthor {
+        name: name.into(),
+        date_of_birth: PartialDate::from_year(1850).unwrap(),
+        date_of_death: None,
+        description: String::new(),
+    }
+}
+
+fn book_details(author_id: AuthorId, count: Integer) -> BookDetails {
+    BookDetails {
+        title: "Enigma Otiliei".into(),
+        author_id,
+        publish_date: PartialDate::from_year(1938).unwrap(),
+        publisher: "Polirom".into(),
+        count,
+        synopsis: String::new(),
+        language: "ro".into(),
+        series: None,
+        genres: vec![],
+    }
+}
+
+/// A store with a librarian, a reader and a book with `count` copies, returned in this order
+async fn library(count: Integer) -> (SqliteStore, UserId, UserId, BookId) {
+    let store = SqliteStore::in_memory().await.unwrap();
+    let librarian_id = add_user(&store, "bibliotecar@example.com", UserKind::Librarian).await;
+    let reader_id = add_user(&store, "ana@example.com", UserKind::Reader).await;
+    let author_id = store.add_author(&new_author("Autor")).await.unwrap();
+    let outcome = store
+        .save_book(None, &book_details(author_id, count))
+        .await
+        .unwrap();
+    let SaveOutcome::Saved(book_id) = outcome else {
+        panic!("the book wasn't added: {outcome:?}");
+    };
+
+    (store, librarian_id, reader_id, book_id)
+}
+
+#[test]
+fn loans_are_due_after_the_configured_days() {
+    let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let due = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+
+    assert_eq!(books::due_date(&LOANS, now.into()), due.into());
+}
+
+#[tokio::test]
+async fn only_librarians_pass() {
+    let (store, librarian_id, reader_id, _) = library(1).await;
+
+    assert!(users::require_librarian(&store, librarian_id).await.is_ok());
+    assert!(matches!(
+        users::require_librarian(&store, reader_id).await,
+        Err(ServiceError::NotLibrarian)
+    ));
+    assert!(matches!(
+        users::require_librarian(&store, UserId(1000)).await,
+        Err(ServiceError::UnknownRequester)
+    ));
+}
+
+#[tokio::test]
+async fn a_book_is_lent_while_copies_are_left() {
+    let (store, librarian_id, reader_id, book_id) = library(2).await;
+    let events = Events::default();
+    let other_reader_id = add_user(&store, "ion@example.com", UserKind::Reader).await;
+
+    let outcome = books::lend(&store, &LOANS, &events, book_id, reader_id, None).await;
+    assert_eq!(outcome.unwrap(), LendOutcome::Lent);
+
+    let outcome = books::lend(&store, &LOANS, &events, book_id, reader_id, None).await;
+    assert_eq!(outcome.unwrap(), LendOutcome::AlreadyBorrowed);
+
+    let outcome = books::lend(&store, &LOANS, &events, book_id, librarian_id, None).await;
+    assert_eq!(outcome.unwrap(), LendOutcome::Lent);
+
+    let outcome = books::lend(&store, &LOANS, &events, book_id, other_reader_id, None).await;
+    assert_eq!(outcome.unwrap(), LendOutcome::NoCopiesLeft);
+
+    assert!(matches!(
+        books::lend(&store, &LOANS, &events, BookId(1000), reader_id, None).await,
+        Err(ServiceError::NoSuchBook)
+    ));
+}
+
+#[tokio::test]
+async fn books_are_checked_out_to_existing_readers() {
+    let (store, librarian_id, reader_id, book_id) = library(1).await;
+    let events = Events::default();
+
+    assert!(matches!(
+        books::checkout(&store, &LOANS, &events, reader_id, book_id, reader_id).await,
+        Err(ServiceError::NotLibrarian)
+    ));
+    assert!(matches!(
+        books::checkout(&store, &LOANS, &events, librarian_id, book_id, UserId(1000)).await,
+        Err(ServiceError::NoSuchUser)
+    ));
+
+    let outcome = books::checkout(&store, &LOANS, &events, librarian_id, book_id, reader_id).await;
+    assert_eq!(outcome.unwrap(), LendOutcome::Lent);
+
+    let borrows = store.borrows().await.unwrap();
+    assert_eq!(borrows[0].user_id, reader_id);
+}
+
+#[tokio::test]
+async fn a_borrowed_book_cant_be_deleted() {
+    let (store, librarian_id, reader_id, book_id) = library(2).await;
+    let events = Events::default();
+    books::lend(&store, &LOANS, &events, book_id, reader_id, None)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        books::delete_book(&store, &events, librarian_id, book_id).await,
+        Err(ServiceError::BookStillBorrowed)
+    ));
+
+    let borrow_id = store.borrowed_by(reader_id).await.unwrap()[0].borrow_id;
+    store.return_book(borrow_id).await.unwrap();
+
+    assert!(matches!(
+        books::delete_book(&store, &events, reader_id, book_id).await,
+        Err(ServiceError::NotLibrarian)
+    ));
+    books::delete_book(&store, &events, librarian_id, book_id)
+        .await
+        .unwrap();
+    assert!(store.books().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn users_are_deleted_once_they_returned_everything() {
+    let (store, librarian_id, reader_id, book_id) = library(1).await;
+    let events = Events::default();
+    books::lend(&store, &LOANS, &events, book_id, reader_id, None)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        users::delete(&store, &events, librarian_id, librarian_id).await,
+        Err(ServiceError::CannotDeleteSelf)
+    ));
+    assert!(matches!(
+        users::delete(&store, &events, librarian_id, reader_id).await,
+        Err(ServiceError::UserStillHasBooks)
+    ));
+    assert!(matches!(
+        users::delete(&store, &events, librarian_id, UserId(1000)).await,
+        Err(ServiceError::NoSuchUser)
+    ));
+
+    let borrow_id = store.borrowed_by(reader_id).await.unwrap()[0].borrow_id;
+    store.return_book(borrow_id).await.unwrap();
+
+    users::delete(&store, &events, librarian_id, reader_id)
+        .await
+        .unwrap();
+    assert_eq!(store.user_kind(reader_id).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn borrows_are_lengthened_up_to_the_limit() {
+    let (store, librarian_id, reader_id, book_id) = library(1).await;
+    let events = Events::default();
+    books::lend(&store, &LOANS, &events, book_id, reader_id, None)
+        .await
+        .unwrap();
+    let borrowed = store.borrowed_by(reader_id).await.unwrap().remove(0);
+
+    assert!(matches!(
+        books::lengthen_borrow(
+            &store,
+            &LOANS,
+            &events,
+            librarian_id,
+            borrowed.borrow_id,
+            31
+        )
+        .await,
+        Err(ServiceError::ExtensionTooLong { max_days: 30 })
+    ));
+
+    books::lengthen_borrow(
+        &store,
+        &LOANS,
+        &events,
+        librarian_id,
+        borrowed.borrow_id,
+        30,
+    )
+    .await
+    .unwrap();
+    let lengthened = store.borrowed_by(reader_id).await.unwrap().remove(0);
+    assert_eq!(
+        lengthened.valid_until.unix(),
+        borrowed.valid_until.unix() + 30 * DAY
+    );
+
+    assert!(matches!(
+        books::lengthen_borrow(&store, &LOANS, &events, librarian_id, BorrowId(1000), 1).await,
+        Err(ServiceError::NoSuchBorrow)
+    ));
+}
+
+#[tokio::test]
+async fn borrows_are_ended_by_librarians() {
+    let (store, librarian_id, reader_id, book_id) = library(1).await;
+    let events = Events::default();
+    books::lend(&store, &LOANS, &events, book_id, reader_id, None)
+        .await
+        .unwrap();
+    let borrow_id = store.borrowed_by(reader_id).await.unwrap()[0].borrow_id;
+
+    assert!(matches!(
+        books::end_borrow(&store, &events, reader_id, borrow_id).await,
+        Err(ServiceError::NotLibrarian)
+    ));
+    assert!(matches!(
+        books::end_borrow(&store, &events, librarian_id, BorrowId(1000)).await,
+        Err(ServiceError::NoSuchBorrow)
+    ));
+
+    let before = Timestamp::now();
+    books::end_borrow(&store, &events, librarian_id, borrow_id)
+        .await
+        .unwrap();
+    let ended = store.borrowed_by(reader_id).await.unwrap().remove(0);
+    assert!(ended.valid_until <= Timestamp::now());
+    assert!(ended.valid_until >= before);
+}
+
+#[tokio::test]
+async fn blank_series_and_genres_are_dropped() {
+    let (store, librarian_id, _, book_id) = library(1).await;
+    let events = Events::default();
+    let author_id = store.authors().await.unwrap()[0].author_id;
+
+    let details = BookDetails {
+        series: Some("  ".into()),
+        genres: vec![" Roman ".into(), "".into()],
+        ..book_details(author_id, 3)
+    };
+    books::save_book(&store, &events, librarian_id, Some(book_id), details)
+        .await
+        .unwrap();
+
+    let book = &store.books().await.unwrap()[0];
+    assert_eq!(book.count, 3);
+    assert_eq!(book.series, None);
+    assert_eq!(book.genres, ["Roman"]);
+}
+
+#[tokio::test]
+async fn authors_are_only_added() {
+    let (store, librarian_id, _, _) = library(1).await;
+    let events = Events::default();
+    let author_id = store.authors().await.unwrap()[0].author_id;
+
+    assert!(matches!(
+        books::add_author(
+            &store,
+            &events,
+            librarian_id,
+            Some(author_id),
+            &new_author("Altul")
+        )
+        .await,
+        Err(ServiceError::AuthorIsReadOnly)
+    ));
+
+    books::add_author(&store, &events, librarian_id, None, &new_author("Altul"))
+        .await
+        .unwrap();
+    assert_eq!(store.authors().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn accounts_are_made_and_logged_into() {
+    let store = SqliteStore::in_memory().await.unwrap();
+    let events = Events::default();
+
+    assert!(matches!(
+        users::create_account(&store, &events, "Ana".into(), "".into(), "parola").await,
+        Err(ServiceError::MissingAccountDetails)
+    ));
+
+    let user_id = users::create_account(
+        &store,
+        &events,
+        "Ana".into(),
+        "ana@example.com".into(),
+        "parola",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        users::create_account(
+            &store,
+            &events,
+            "Ana".into(),
+            "ana@example.com".into(),
+            "alta"
+        )
+        .await,
+        Err(ServiceError::EmailTaken)
+    ));
+
+    let credentials = users::login(&store, "ana@example.com", "parola")
+        .await
+        .unwrap();
+    assert_eq!(credentials.user_id, user_id);
+    assert_eq!(credentials.kind, UserKind::Reader);
+
+    assert!(matches!(
+        users::login(&store, "ana@example.com", "gresita").await,
+        Err(ServiceError::WrongPassword)
+    ));
+    assert!(matches!(
+        users::login(&store, "ion@example.com", "parola").await,
+        Err(ServiceError::NoSuchAccount)
+    ));
+}
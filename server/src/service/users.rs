@@ -0,0 +1,149 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use schema::{
+    dates::Timestamp,
+    ids::{UserId, UserKind},
+};
+
+use super::{ServiceError, ServiceResult};
+use crate::{
+    events::Events,
+    store::{Credentials, NewUser, Store, UserRecord},
+};
+
+/// Checks that `requester_id` belongs to a librarian
+pub async fn require_librarian(store: &dyn Store, requester_id: UserId) -> ServiceResult<()> {
+    match store.user_kind(requester_id).await? {
+        None => Err(ServiceError::UnknownRequester),
+        Some(UserKind::Librarian) => Ok(()),
+        Some(_) => Err(ServiceError::NotLibrarian),
+    }
+}
+
+/// Hashes a password with a fresh salt, in the format stored in `Users.password`
+pub fn hash_password(password: &str) -> argon2::password_hash::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Returns the credentials of the account with `email` if `password` is its password
+pub async fn login(store: &dyn Store, email: &str, password: &str) -> ServiceResult<Credentials> {
+    let user = store
+        .credentials(email)
+        .await?
+        .ok_or(ServiceError::NoSuchAccount)?;
+    verify_password(&user, password)?;
+
+    Ok(user)
+}
+
+/// Returns the credentials of the account `user_id` if `password` is its password, for the
+/// requests that say who they're made by with each request instead of logging in
+pub async fn authenticate(
+    store: &dyn Store,
+    user_id: UserId,
+    password: &str,
+) -> ServiceResult<Credentials> {
+    let user = store
+        .credentials_by_id(user_id)
+        .await?
+        .ok_or(ServiceError::UnknownRequester)?;
+    verify_password(&user, password)?;
+
+    Ok(user)
+}
+
+fn verify_password(user: &Credentials, password: &str) -> ServiceResult<()> {
+    let parsed_hash =
+        PasswordHash::new(&user.password_hash).map_err(|err| ServiceError::Internal(err.into()))?;
+
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| ServiceError::WrongPassword)
+}
+
+/// Makes an account for a new reader, returning its id
+pub async fn create_account(
+    store: &dyn Store,
+    events: &Events,
+    name: String,
+    email: String,
+    password: &str,
+) -> ServiceResult<UserId> {
+    if name.is_empty() || email.is_empty() || password.is_empty() {
+        return Err(ServiceError::MissingAccountDetails);
+    }
+
+    let password_hash =
+        hash_password(password).map_err(|err| ServiceError::Internal(err.into()))?;
+
+    let user = NewUser {
+        name,
+        kind: UserKind::Reader,
+        email,
+        password_hash,
+        registered_at: Timestamp::now(),
+    };
+
+    let user_id = store.create_user(&user).await.map_err(|err| match err {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            ServiceError::EmailTaken
+        }
+        err => err.into(),
+    })?;
+    events.user_saved(store, user_id).await;
+
+    Ok(user_id)
+}
+
+pub async fn users(store: &dyn Store, requester_id: UserId) -> ServiceResult<Vec<UserRecord>> {
+    require_librarian(store, requester_id).await?;
+
+    Ok(store.users().await?)
+}
+
+pub async fn promote(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    user_id: UserId,
+) -> ServiceResult<()> {
+    require_librarian(store, requester_id).await?;
+
+    store.set_user_kind(user_id, UserKind::Librarian).await?;
+    events.user_saved(store, user_id).await;
+
+    Ok(())
+}
+
+/// Deletes an account, which can't be the requester's own, once it has no books borrowed
+pub async fn delete(
+    store: &dyn Store,
+    events: &Events,
+    requester_id: UserId,
+    user_id: UserId,
+) -> ServiceResult<()> {
+    require_librarian(store, requester_id).await?;
+
+    if user_id == requester_id {
+        return Err(ServiceError::CannotDeleteSelf);
+    }
+
+    if store.user_kind(user_id).await?.is_none() {
+        return Err(ServiceError::NoSuchUser);
+    }
+
+    if store.count_borrowed_by(user_id).await? > 0 {
+        return Err(ServiceError::UserStillHasBooks);
+    }
+
+    store.delete_user(user_id).await?;
+    events.user_deleted(user_id);
+
+    Ok(())
+}
@@ -0,0 +1,325 @@
+//! Storage of the library's data.
+//!
+//! Handlers don't talk to the database directly but through a [`Store`], which has a SQLite and a
+//! PostgreSQL implementation. Which one is used follows from the `database` setting: a
+//! `postgres://` URL selects PostgreSQL, anything else is taken as the path of a SQLite database.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use schema::{
+    auth::User,
+    books::{Author, Book, Borrow, BorrowedBook, Recommendation},
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    lists::ReadingList,
+    reports::{
+        AuthorPopularity, LoanStatusReply, MostBorrowedBook, NeverBorrowedBook, Registrations,
+        TitleUtilisation,
+    },
+    Integer,
+};
+
+use crate::seed::Library;
+
+mod postgres;
+mod sqlite;
+#[cfg(test)]
+mod tests;
+
+pub use postgres::PostgresStore;
+pub use sqlite::SqliteStore;
+
+pub type DynStore = Arc<dyn Store>;
+
+/// Errors are passed through as they come from the database, handlers look at them to tell e.g.
+/// a duplicate name apart from a failure
+pub type StoreResult<T> = Result<T, sqlx::Error>;
+
+/// Where the data is kept, as given by the `database` setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite(PathBuf),
+    Postgres(String),
+}
+
+impl Backend {
+    pub fn from_setting(database: &str) -> Self {
+        if database.starts_with("postgres://") || database.starts_with("postgresql://") {
+            Self::Postgres(database.to_string())
+        } else {
+            let path = database.strip_prefix("sqlite://").unwrap_or(database);
+            Self::Sqlite(path.into())
+        }
+    }
+}
+
+/// Opens the database named by the `database` setting, applying pending migrations
+pub async fn open(database: &str) -> anyhow::Result<DynStore> {
+    Ok(match Backend::from_setting(database) {
+        Backend::Sqlite(path) => Arc::new(SqliteStore::open(&path).await?),
+        Backend::Postgres(url) => Arc::new(PostgresStore::open(&url).await?),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LendOutcome {
+    Lent,
+    AlreadyBorrowed,
+    NoCopiesLeft,
+}
+
+/// What came of saving a book
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+    Saved(BookId),
+    /// The book was left as it was, because it would have had fewer copies than the number given,
+    /// which are borrowed right now
+    CopiesLent(i64),
+}
+
+/// Everything about a book that can be edited
+#[derive(Debug, Clone)]
+pub struct BookDetails {
+    pub title: String,
+    pub author_id: AuthorId,
+    pub publish_date: PartialDate,
+    pub publisher: String,
+    pub count: Integer,
+    pub synopsis: String,
+    /// Only set when the book is added
+    pub language: String,
+    pub series: Option<String>,
+    /// Genres that don't exist yet are created
+    pub genres: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAuthor {
+    pub name: String,
+    pub date_of_birth: PartialDate,
+    pub date_of_death: Option<PartialDate>,
+    pub description: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub name: String,
+    pub kind: UserKind,
+    pub email: String,
+    pub password_hash: String,
+    pub registered_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub user_id: UserId,
+    pub kind: UserKind,
+    pub password_hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserRecord {
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+    pub kind: UserKind,
+    /// Unknown for some accounts made before registration dates were recorded
+    pub registered_at: Option<Timestamp>,
+    pub borrowed_book_count: i64,
+}
+
+impl From<UserRecord> for User {
+    fn from(user: UserRecord) -> Self {
+        Self {
+            id: user.user_id,
+            name: user.name,
+            email: user.email,
+            kind: user.kind,
+            borrowed_book_count: user.borrowed_book_count,
+        }
+    }
+}
+
+/// What the similarity between books is computed from
+#[derive(Debug, Default)]
+pub struct SimilarityInputs {
+    /// `(book_id, author_id, series)`
+    pub books: Vec<(BookId, AuthorId, Option<String>)>,
+    /// `(book_id, genre_id)`
+    pub genres: Vec<(BookId, Integer)>,
+    /// `(book_id, user_id)` for everyone that has ever borrowed a book
+    pub readers: Vec<(BookId, UserId)>,
+}
+
+/// A request made with an `Idempotency-Key`, as found when the key is used again
+#[derive(Debug, Clone)]
+pub struct IdempotentRequest {
+    /// Identifies the request the key was first used for, see [`crate::idempotency`]
+    pub fingerprint: String,
+    /// `None` while the first request is still being handled
+    pub response: Option<StoredResponse>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Everything the server needs from a database
+#[async_trait]
+pub trait Store: Send + Sync {
+    // Users
+
+    async fn user_kind(&self, user_id: UserId) -> StoreResult<Option<UserKind>>;
+    async fn credentials(&self, email: &str) -> StoreResult<Option<Credentials>>;
+    async fn credentials_by_id(&self, user_id: UserId) -> StoreResult<Option<Credentials>>;
+    async fn user_id_by_email(&self, email: &str) -> StoreResult<Option<UserId>>;
+    async fn create_user(&self, user: &NewUser) -> StoreResult<UserId>;
+    async fn users(&self) -> StoreResult<Vec<UserRecord>>;
+    async fn user(&self, user_id: UserId) -> StoreResult<Option<UserRecord>>;
+    async fn set_user_kind(&self, user_id: UserId, kind: UserKind) -> StoreResult<()>;
+    async fn set_password(&self, user_id: UserId, password_hash: &str) -> StoreResult<()>;
+    async fn delete_user(&self, user_id: UserId) -> StoreResult<()>;
+
+    // Catalog
+
+    async fn books(&self) -> StoreResult<Vec<Book>>;
+    async fn book(&self, book_id: BookId) -> StoreResult<Option<Book>>;
+    async fn authors(&self) -> StoreResult<Vec<Author>>;
+    async fn add_author(&self, author: &NewAuthor) -> StoreResult<AuthorId>;
+    /// Updates `book_id`, or adds a new book if it's `None`, unless that would leave it with fewer
+    /// copies than are borrowed
+    async fn save_book(
+        &self,
+        book_id: Option<BookId>,
+        book: &BookDetails,
+    ) -> StoreResult<SaveOutcome>;
+    /// Deletes a book unless some of its copies are borrowed, returning whether it was deleted
+    async fn delete_book(&self, book_id: BookId) -> StoreResult<bool>;
+
+    // Loans
+
+    /// Lends a copy of `book_id` to `user_id`, provided that there's a free copy and that the
+    /// user doesn't already have this book borrowed. Returns `None` if there's no such book.
+    async fn lend_book(
+        &self,
+        book_id: BookId,
+        user_id: UserId,
+        checked_out_by: Option<UserId>,
+        valid_until: Timestamp,
+    ) -> StoreResult<Option<LendOutcome>>;
+    async fn borrows(&self) -> StoreResult<Vec<Borrow>>;
+    async fn borrowed_by(&self, user_id: UserId) -> StoreResult<Vec<BorrowedBook>>;
+    /// The user that has `borrow_id` borrowed
+    async fn borrower(&self, borrow_id: BorrowId) -> StoreResult<Option<UserId>>;
+    async fn count_borrowed_by(&self, user_id: UserId) -> StoreResult<i64>;
+    /// How many copies of `book_id` are borrowed right now
+    async fn count_lent_copies(&self, book_id: BookId) -> StoreResult<i64>;
+    async fn set_valid_until(&self, borrow_id: BorrowId, valid_until: Timestamp)
+        -> StoreResult<()>;
+    async fn lengthen_borrow(&self, borrow_id: BorrowId, days: i64) -> StoreResult<()>;
+    async fn set_chapters_read(&self, borrow_id: BorrowId, chapters_read: i64) -> StoreResult<()>;
+    async fn return_book(&self, borrow_id: BorrowId) -> StoreResult<()>;
+
+    // Reading lists
+
+    /// The lists of `user_id` that `requester_id` may see
+    async fn reading_lists(
+        &self,
+        user_id: UserId,
+        requester_id: UserId,
+    ) -> StoreResult<Vec<ReadingList>>;
+    async fn reading_list_owner(&self, list_id: Integer) -> StoreResult<Option<UserId>>;
+    async fn create_reading_list(
+        &self,
+        user_id: UserId,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<Integer>;
+    async fn change_reading_list(
+        &self,
+        list_id: Integer,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<()>;
+    async fn delete_reading_list(&self, list_id: Integer) -> StoreResult<()>;
+    async fn reading_list_contains(&self, list_id: Integer, book_id: BookId) -> StoreResult<bool>;
+    /// Adds a book at the end of a list, a book that's already on the list keeps its position
+    async fn add_to_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()>;
+    async fn remove_from_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()>;
+    /// Puts the books on a list in the given order. Returns `false` without changing anything if
+    /// `book_ids` aren't exactly the books on the list.
+    async fn reorder_reading_list(
+        &self,
+        list_id: Integer,
+        book_ids: &[BookId],
+    ) -> StoreResult<bool>;
+
+    // Recommendations
+
+    async fn similarity_inputs(&self) -> StoreResult<SimilarityInputs>;
+    /// Replaces all stored similarities, given as `(book_id, similar_book_id, score)`
+    async fn replace_similarities(&self, similarities: &[(BookId, BookId, f64)])
+        -> StoreResult<()>;
+    async fn similar_books(&self, book_id: BookId) -> StoreResult<Vec<Recommendation>>;
+    /// Books similar to those the reader has borrowed, which they haven't borrowed yet
+    async fn recommendations(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> StoreResult<Vec<Recommendation>>;
+    /// The most borrowed books of all time which the reader hasn't borrowed yet, scored by the
+    /// number of times they were borrowed
+    async fn popular_books(&self, user_id: UserId, limit: i64) -> StoreResult<Vec<Recommendation>>;
+
+    // Reports, over the period `[from, to)`
+
+    async fn most_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<MostBorrowedBook>>;
+    async fn never_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<NeverBorrowedBook>>;
+    async fn loan_status(&self, from: i64, to: i64) -> StoreResult<LoanStatusReply>;
+    async fn author_popularity(&self, from: i64, to: i64) -> StoreResult<Vec<AuthorPopularity>>;
+    async fn registrations(&self, from: i64, to: i64) -> StoreResult<Vec<Registrations>>;
+    async fn utilisation(&self, from: i64, to: i64) -> StoreResult<Vec<TitleUtilisation>>;
+
+    // Idempotency keys
+
+    /// Takes `key` for a new request, unless it is already used by a request made at or after
+    /// `expired_before`, which is then returned instead
+    async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: i64,
+        expired_before: i64,
+    ) -> StoreResult<Option<IdempotentRequest>>;
+    async fn save_idempotent_response(
+        &self,
+        key: &str,
+        response: &StoredResponse,
+    ) -> StoreResult<()>;
+    /// Frees a key whose request failed, so that it can be tried again
+    async fn release_idempotency_key(&self, key: &str) -> StoreResult<()>;
+    /// Returns how many keys were deleted
+    async fn delete_expired_idempotency_keys(&self, expired_before: i64) -> StoreResult<u64>;
+
+    // Maintenance
+
+    /// `(version, description)` of the applied migrations
+    async fn applied_migrations(&self) -> StoreResult<Vec<(i64, String)>>;
+    /// Runs the database's own consistency checks, returning the problems found
+    async fn check_integrity(&self) -> StoreResult<Vec<String>>;
+    /// Writes a consistent copy of the database to `path`
+    async fn backup_to(&self, path: &Path) -> anyhow::Result<()>;
+    async fn is_empty(&self) -> StoreResult<bool>;
+    /// Deletes all data, keeping the schema
+    async fn reset(&self) -> StoreResult<()>;
+    async fn load_demo(&self) -> StoreResult<()>;
+    /// Inserts a generated library in a single transaction
+    async fn load_library(&self, library: &Library) -> StoreResult<()>;
+}
@@ -0,0 +1,1358 @@
+//! The queries here are checked at runtime rather than at compile time like the SQLite ones, so
+//! that building the server doesn't need a running PostgreSQL. The shared tests in
+//! `store::tests` are what keeps them honest.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::bail;
+use async_trait::async_trait;
+use schema::{
+    books::{Author, Book, Borrow, BorrowedBook, Recommendation},
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    lists::ReadingList,
+    reports::{
+        AuthorPopularity, LoanStatusReply, MostBorrowedBook, NeverBorrowedBook, Registrations,
+        TitleUtilisation,
+    },
+    Integer, Text,
+};
+use sqlx::{postgres::PgConnectOptions, Executor, PgPool, Postgres, Transaction};
+
+use super::{
+    BookDetails, Credentials, IdempotentRequest, LendOutcome, NewAuthor, NewUser, SaveOutcome,
+    SimilarityInputs, Store, StoreResult, StoredResponse, UserRecord,
+};
+use crate::seed::Library;
+
+const DEMO: &str = include_str!("../../fixtures/demo.postgres.sql");
+
+#[derive(Clone)]
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// Connects to the database at `url` and applies pending migrations
+    pub async fn open(url: &str) -> anyhow::Result<Self> {
+        Self::open_with(url.parse()?).await
+    }
+
+    pub async fn open_with(options: PgConnectOptions) -> anyhow::Result<Self> {
+        let pool = PgPool::connect_with(options).await?;
+        sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    #[cfg(test)]
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// The genres of `book_id`, or of every book if it's `None`
+    async fn genres_by_book(
+        &self,
+        book_id: Option<BookId>,
+    ) -> StoreResult<HashMap<BookId, Vec<Text>>> {
+        let records = sqlx::query_as::<_, (BookId, String)>(
+            "
+SELECT bg.book_id, g.name
+FROM BookGenres bg JOIN Genres g ON bg.genre_id = g.genre_id
+WHERE $1::BIGINT IS NULL OR bg.book_id = $1
+ORDER BY g.name
+    ",
+        )
+        .bind(book_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut genres = HashMap::<BookId, Vec<Text>>::new();
+        for (book_id, name) in records {
+            genres.entry(book_id).or_default().push(name);
+        }
+
+        Ok(genres)
+    }
+
+    /// The user with `user_id`, or every user if it's `None`
+    async fn fetch_users(&self, user_id: Option<UserId>) -> StoreResult<Vec<UserRecord>> {
+        let records =
+            sqlx::query_as::<_, (UserId, String, String, UserKind, Option<Timestamp>, i64)>(
+                "
+SELECT
+    u.user_id,
+    u.name,
+    u.email,
+    u.type,
+    u.registered_at,
+    (SELECT COUNT(*) FROM Borrows bo WHERE bo.user_id = u.user_id)
+FROM Users u
+WHERE $1::BIGINT IS NULL OR u.user_id = $1
+ORDER BY u.user_id
+    ",
+            )
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(user_id, name, email, kind, registered_at, borrowed_book_count)| UserRecord {
+                    user_id,
+                    name,
+                    email,
+                    kind,
+                    registered_at,
+                    borrowed_book_count,
+                },
+            )
+            .collect())
+    }
+
+    /// The book with `book_id`, or every book if it's `None`
+    async fn fetch_books(&self, book_id: Option<BookId>) -> StoreResult<Vec<Book>> {
+        let mut genres = self.genres_by_book(book_id).await?;
+
+        #[allow(clippy::type_complexity)]
+        let records = sqlx::query_as::<
+            _,
+            (
+                BookId,
+                String,
+                PartialDate,
+                String,
+                Integer,
+                String,
+                Option<String>,
+                AuthorId,
+                String,
+                PartialDate,
+                Option<PartialDate>,
+                String,
+                bool,
+            ),
+        >(
+            "
+SELECT
+b.book_id, b.title, b.publish_date, b.publisher, b.count, b.synopsis, b.series,
+a.author_id, a.name, a.date_of_birth, a.date_of_death, a.description,
+b.count > (SELECT COUNT(*) FROM Borrows bo WHERE bo.book_id = b.book_id)
+FROM Books b JOIN Authors a ON b.author_id = a.author_id
+WHERE $1::BIGINT IS NULL OR b.book_id = $1
+ORDER BY b.book_id
+",
+        )
+        .bind(book_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(
+                    book_id,
+                    title,
+                    publish_date,
+                    publisher,
+                    count,
+                    synopsis,
+                    series,
+                    author_id,
+                    name,
+                    date_of_birth,
+                    date_of_death,
+                    description,
+                    can_be_borrowed,
+                )| Book {
+                    book_id,
+                    title,
+                    author: Author {
+                        author_id,
+                        name,
+                        date_of_birth,
+                        date_of_death,
+                        description,
+                    },
+                    publish_date,
+                    publisher,
+                    count,
+                    synopsis,
+                    can_be_borrowed,
+                    series,
+                    genres: genres.remove(&book_id).unwrap_or_default(),
+                },
+            )
+            .collect())
+    }
+}
+
+async fn insert_author(
+    tx: &mut Transaction<'_, Postgres>,
+    author: &NewAuthor,
+) -> StoreResult<AuthorId> {
+    sqlx::query_scalar(
+        "
+INSERT INTO Authors(name, date_of_birth, date_of_death, description)
+VALUES ($1, $2, $3, $4)
+RETURNING author_id
+    ",
+    )
+    .bind(&author.name)
+    .bind(author.date_of_birth)
+    .bind(author.date_of_death)
+    .bind(&author.description)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+async fn insert_user(tx: &mut Transaction<'_, Postgres>, user: &NewUser) -> StoreResult<UserId> {
+    sqlx::query_scalar(
+        "
+INSERT INTO Users(name, type, email, password, registered_at) VALUES ($1, $2, $3, $4, $5)
+RETURNING user_id
+    ",
+    )
+    .bind(&user.name)
+    .bind(user.kind)
+    .bind(&user.email)
+    .bind(&user.password_hash)
+    .bind(user.registered_at)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+async fn save_book(
+    tx: &mut Transaction<'_, Postgres>,
+    book_id: Option<BookId>,
+    book: &BookDetails,
+) -> StoreResult<BookId> {
+    let book_id = if let Some(book_id) = book_id {
+        sqlx::query(
+            "
+UPDATE Books SET
+    title = $1,
+    author_id = $2,
+    publish_date = $3,
+    publisher = $4,
+    count = $5,
+    synopsis = $6,
+    series = $7
+WHERE book_id = $8
+        ",
+        )
+        .bind(&book.title)
+        .bind(book.author_id)
+        .bind(book.publish_date)
+        .bind(&book.publisher)
+        .bind(book.count)
+        .bind(&book.synopsis)
+        .bind(&book.series)
+        .bind(book_id)
+        .execute(&mut **tx)
+        .await?;
+
+        book_id
+    } else {
+        sqlx::query_scalar(
+            "
+INSERT INTO Books(title, author_id, publish_date, publisher, count, synopsis, language, series)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+RETURNING book_id
+        ",
+        )
+        .bind(&book.title)
+        .bind(book.author_id)
+        .bind(book.publish_date)
+        .bind(&book.publisher)
+        .bind(book.count)
+        .bind(&book.synopsis)
+        .bind(&book.language)
+        .bind(&book.series)
+        .fetch_one(&mut **tx)
+        .await?
+    };
+
+    sqlx::query("DELETE FROM BookGenres WHERE book_id = $1")
+        .bind(book_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for genre in &book.genres {
+        sqlx::query("INSERT INTO Genres(name) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(genre)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            "
+INSERT INTO BookGenres(book_id, genre_id)
+SELECT $1, genre_id
+FROM Genres
+WHERE name = $2
+ON CONFLICT DO NOTHING
+        ",
+        )
+        .bind(book_id)
+        .bind(genre)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(book_id)
+}
+
+fn into_recommendations(records: Vec<(BookId, String, String, f64)>) -> Vec<Recommendation> {
+    records
+        .into_iter()
+        .map(|(book_id, title, author_name, score)| Recommendation {
+            book_id,
+            title,
+            author_name,
+            score,
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Store for PostgresStore {
+    async fn user_kind(&self, user_id: UserId) -> StoreResult<Option<UserKind>> {
+        sqlx::query_scalar("SELECT type FROM Users WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn credentials(&self, email: &str) -> StoreResult<Option<Credentials>> {
+        let record = sqlx::query_as::<_, (UserId, UserKind, String)>(
+            "SELECT user_id, type, password FROM Users WHERE email = $1",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|(user_id, kind, password_hash)| Credentials {
+            user_id,
+            kind,
+            password_hash,
+        }))
+    }
+
+    async fn credentials_by_id(&self, user_id: UserId) -> StoreResult<Option<Credentials>> {
+        let record = sqlx::query_as::<_, (UserKind, String)>(
+            "SELECT type, password FROM Users WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|(kind, password_hash)| Credentials {
+            user_id,
+            kind,
+            password_hash,
+        }))
+    }
+
+    async fn user_id_by_email(&self, email: &str) -> StoreResult<Option<UserId>> {
+        sqlx::query_scalar("SELECT user_id FROM Users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_user(&self, user: &NewUser) -> StoreResult<UserId> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = insert_user(&mut tx, user).await?;
+        tx.commit().await?;
+
+        Ok(user_id)
+    }
+
+    async fn users(&self) -> StoreResult<Vec<UserRecord>> {
+        self.fetch_users(None).await
+    }
+
+    async fn user(&self, user_id: UserId) -> StoreResult<Option<UserRecord>> {
+        Ok(self.fetch_users(Some(user_id)).await?.pop())
+    }
+
+    async fn set_user_kind(&self, user_id: UserId, kind: UserKind) -> StoreResult<()> {
+        sqlx::query("UPDATE Users SET type = $1 WHERE user_id = $2")
+            .bind(kind)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_password(&self, user_id: UserId, password_hash: &str) -> StoreResult<()> {
+        sqlx::query("UPDATE Users SET password = $1 WHERE user_id = $2")
+            .bind(password_hash)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: UserId) -> StoreResult<()> {
+        sqlx::query("DELETE FROM Users WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn books(&self) -> StoreResult<Vec<Book>> {
+        self.fetch_books(None).await
+    }
+
+    async fn book(&self, book_id: BookId) -> StoreResult<Option<Book>> {
+        Ok(self.fetch_books(Some(book_id)).await?.pop())
+    }
+
+    async fn authors(&self) -> StoreResult<Vec<Author>> {
+        let records =
+            sqlx::query_as::<_, (AuthorId, String, PartialDate, Option<PartialDate>, String)>(
+                "
+SELECT author_id, name, date_of_birth, date_of_death, description
+FROM Authors
+ORDER BY author_id
+    ",
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(author_id, name, date_of_birth, date_of_death, description)| Author {
+                    author_id,
+                    name,
+                    date_of_birth,
+                    date_of_death,
+                    description,
+                },
+            )
+            .collect())
+    }
+
+    async fn add_author(&self, author: &NewAuthor) -> StoreResult<AuthorId> {
+        let mut tx = self.pool.begin().await?;
+        let author_id = insert_author(&mut tx, author).await?;
+        tx.commit().await?;
+
+        Ok(author_id)
+    }
+
+    async fn save_book(
+        &self,
+        book_id: Option<BookId>,
+        book: &BookDetails,
+    ) -> StoreResult<SaveOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(book_id) = book_id {
+            // Locks the book like `lend_book` does, so that no copy can be lent in the meantime
+            sqlx::query("SELECT 1 FROM Books WHERE book_id = $1 FOR UPDATE")
+                .bind(book_id)
+                .execute(&mut *tx)
+                .await?;
+
+            let lent: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Borrows WHERE book_id = $1")
+                .bind(book_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            if book.count < lent {
+                return Ok(SaveOutcome::CopiesLent(lent));
+            }
+        }
+
+        let book_id = save_book(&mut tx, book_id, book).await?;
+        tx.commit().await?;
+
+        Ok(SaveOutcome::Saved(book_id))
+    }
+
+    async fn delete_book(&self, book_id: BookId) -> StoreResult<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locking the book keeps it from being lent while it's being deleted
+        sqlx::query("SELECT 1 FROM Books WHERE book_id = $1 FOR UPDATE")
+            .bind(book_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let borrowed_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM Borrows WHERE book_id = $1")
+                .bind(book_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        if borrowed_count > 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM Books WHERE book_id = $1")
+            .bind(book_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn lend_book(
+        &self,
+        book_id: BookId,
+        user_id: UserId,
+        checked_out_by: Option<UserId>,
+        valid_until: Timestamp,
+    ) -> StoreResult<Option<LendOutcome>> {
+        let mut tx = self.pool.begin().await?;
+
+        // The row lock makes concurrent loans of the same book wait for each other, otherwise two
+        // of them could both see the last copy as free
+        let Some(count) =
+            sqlx::query_scalar::<_, i64>("SELECT count FROM Books WHERE book_id = $1 FOR UPDATE")
+                .bind(book_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let (borrowed, borrowed_by_user) = sqlx::query_as::<_, (i64, i64)>(
+            "
+SELECT COUNT(*), COUNT(*) FILTER (WHERE user_id = $2)
+FROM Borrows
+WHERE book_id = $1
+        ",
+        )
+        .bind(book_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if borrowed >= count {
+            return Ok(Some(LendOutcome::NoCopiesLeft));
+        }
+
+        if borrowed_by_user >= 1 {
+            return Ok(Some(LendOutcome::AlreadyBorrowed));
+        }
+
+        let borrow_id: BorrowId = sqlx::query_scalar(
+            "
+INSERT INTO Borrows(book_id, user_id, checked_out_by) VALUES ($1, $2, $3)
+RETURNING borrow_id
+        ",
+        )
+        .bind(book_id)
+        .bind(user_id)
+        .bind(checked_out_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO BorrowData(borrow_id, valid_until, chapters_read) VALUES ($1, $2, 0)",
+        )
+        .bind(borrow_id)
+        .bind(valid_until)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(LendOutcome::Lent))
+    }
+
+    async fn borrows(&self) -> StoreResult<Vec<Borrow>> {
+        let records = sqlx::query_as::<_, (BorrowId, BookId, UserId, Timestamp)>(
+            "
+SELECT b.borrow_id, b.book_id, b.user_id, d.valid_until
+FROM Borrows b JOIN BorrowData d ON b.borrow_id = d.borrow_id
+ORDER BY b.borrow_id
+    ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(borrow_id, book_id, user_id, valid_until)| Borrow {
+                borrow_id,
+                book_id,
+                user_id,
+                valid_until,
+            })
+            .collect())
+    }
+
+    async fn borrowed_by(&self, user_id: UserId) -> StoreResult<Vec<BorrowedBook>> {
+        let records = sqlx::query_as::<_, (BorrowId, BookId, Timestamp, Integer)>(
+            "
+SELECT d.borrow_id, b.book_id, d.valid_until, d.chapters_read
+FROM Borrows b JOIN BorrowData d ON b.borrow_id = d.borrow_id
+WHERE b.user_id = $1
+ORDER BY d.borrow_id
+    ",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(borrow_id, book_id, valid_until, chapters_read)| BorrowedBook {
+                    borrow_id,
+                    book_id,
+                    valid_until,
+                    chapters_read,
+                },
+            )
+            .collect())
+    }
+
+    async fn borrower(&self, borrow_id: BorrowId) -> StoreResult<Option<UserId>> {
+        sqlx::query_scalar("SELECT user_id FROM Borrows WHERE borrow_id = $1")
+            .bind(borrow_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn count_borrowed_by(&self, user_id: UserId) -> StoreResult<i64> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM Borrows WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn count_lent_copies(&self, book_id: BookId) -> StoreResult<i64> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM Borrows WHERE book_id = $1")
+            .bind(book_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn set_valid_until(
+        &self,
+        borrow_id: BorrowId,
+        valid_until: Timestamp,
+    ) -> StoreResult<()> {
+        sqlx::query("UPDATE BorrowData SET valid_until = $1 WHERE borrow_id = $2")
+            .bind(valid_until)
+            .bind(borrow_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn lengthen_borrow(&self, borrow_id: BorrowId, days: i64) -> StoreResult<()> {
+        sqlx::query(
+            "UPDATE BorrowData SET valid_until = valid_until + $1 * 86400 WHERE borrow_id = $2",
+        )
+        .bind(days)
+        .bind(borrow_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_chapters_read(&self, borrow_id: BorrowId, chapters_read: i64) -> StoreResult<()> {
+        sqlx::query("UPDATE BorrowData SET chapters_read = $1 WHERE borrow_id = $2")
+            .bind(chapters_read)
+            .bind(borrow_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn return_book(&self, borrow_id: BorrowId) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM BorrowData WHERE borrow_id = $1")
+            .bind(borrow_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM Borrows WHERE borrow_id = $1")
+            .bind(borrow_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    async fn reading_lists(
+        &self,
+        user_id: UserId,
+        requester_id: UserId,
+    ) -> StoreResult<Vec<ReadingList>> {
+        let lists = sqlx::query_as::<_, (Integer, UserId, String, bool)>(
+            "
+SELECT list_id, user_id, name, is_public
+FROM ReadingLists
+WHERE user_id = $1 AND (is_public OR user_id = $2)
+ORDER BY name
+    ",
+        )
+        .bind(user_id)
+        .bind(requester_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let entries = sqlx::query_as::<_, (Integer, BookId)>(
+            "
+SELECT e.list_id, e.book_id
+FROM ReadingListEntries e JOIN ReadingLists l ON e.list_id = l.list_id
+WHERE l.user_id = $1
+ORDER BY e.position
+    ",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut book_ids_by_list = HashMap::<Integer, Vec<BookId>>::new();
+        for (list_id, book_id) in entries {
+            book_ids_by_list.entry(list_id).or_default().push(book_id);
+        }
+
+        Ok(lists
+            .into_iter()
+            .map(|(list_id, owner_id, name, is_public)| ReadingList {
+                list_id,
+                owner_id,
+                name,
+                is_public,
+                book_ids: book_ids_by_list.remove(&list_id).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn reading_list_owner(&self, list_id: Integer) -> StoreResult<Option<UserId>> {
+        sqlx::query_scalar("SELECT user_id FROM ReadingLists WHERE list_id = $1")
+            .bind(list_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_reading_list(
+        &self,
+        user_id: UserId,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<Integer> {
+        sqlx::query_scalar(
+            "INSERT INTO ReadingLists(user_id, name, is_public) VALUES ($1, $2, $3) RETURNING list_id",
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(is_public)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn change_reading_list(
+        &self,
+        list_id: Integer,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<()> {
+        sqlx::query("UPDATE ReadingLists SET name = $1, is_public = $2 WHERE list_id = $3")
+            .bind(name)
+            .bind(is_public)
+            .bind(list_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_reading_list(&self, list_id: Integer) -> StoreResult<()> {
+        sqlx::query("DELETE FROM ReadingLists WHERE list_id = $1")
+            .bind(list_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn reading_list_contains(&self, list_id: Integer, book_id: BookId) -> StoreResult<bool> {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM ReadingListEntries WHERE list_id = $1 AND book_id = $2)",
+        )
+        .bind(list_id)
+        .bind(book_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn add_to_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()> {
+        sqlx::query(
+            "
+INSERT INTO ReadingListEntries(list_id, book_id, position)
+SELECT $1, $2, COALESCE(MAX(position), 0) + 1
+FROM ReadingListEntries
+WHERE list_id = $1
+ON CONFLICT DO NOTHING
+    ",
+        )
+        .bind(list_id)
+        .bind(book_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_from_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()> {
+        sqlx::query("DELETE FROM ReadingListEntries WHERE list_id = $1 AND book_id = $2")
+            .bind(list_id)
+            .bind(book_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn reorder_reading_list(
+        &self,
+        list_id: Integer,
+        book_ids: &[BookId],
+    ) -> StoreResult<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = sqlx::query_scalar::<_, BookId>(
+            "SELECT book_id FROM ReadingListEntries WHERE list_id = $1 FOR UPDATE",
+        )
+        .bind(list_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+        let requested = book_ids.iter().copied().collect::<HashSet<_>>();
+        if requested.len() != book_ids.len() || requested != current {
+            return Ok(false);
+        }
+
+        for (position, book_id) in (1_i64..).zip(book_ids) {
+            sqlx::query(
+                "UPDATE ReadingListEntries SET position = $1 WHERE list_id = $2 AND book_id = $3",
+            )
+            .bind(position)
+            .bind(list_id)
+            .bind(book_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn similarity_inputs(&self) -> StoreResult<SimilarityInputs> {
+        let books = sqlx::query_as("SELECT book_id, author_id, series FROM Books")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let genres = sqlx::query_as("SELECT book_id, genre_id FROM BookGenres")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let readers = sqlx::query_as("SELECT DISTINCT book_id, user_id FROM BorrowHistory")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(SimilarityInputs {
+            books,
+            genres,
+            readers,
+        })
+    }
+
+    async fn replace_similarities(
+        &self,
+        similarities: &[(BookId, BookId, f64)],
+    ) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM BookSimilarities")
+            .execute(&mut *tx)
+            .await?;
+
+        for &(book_id, similar_book_id, score) in similarities {
+            sqlx::query(
+                "INSERT INTO BookSimilarities(book_id, similar_book_id, score) VALUES ($1, $2, $3)",
+            )
+            .bind(book_id)
+            .bind(similar_book_id)
+            .bind(score)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    async fn similar_books(&self, book_id: BookId) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query_as(
+            "
+SELECT b.book_id, b.title, a.name, s.score
+FROM BookSimilarities s
+    JOIN Books b ON s.similar_book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE s.book_id = $1
+ORDER BY s.score DESC
+    ",
+        )
+        .bind(book_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(into_recommendations(records))
+    }
+
+    async fn recommendations(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query_as(
+            "
+SELECT b.book_id, b.title, a.name, SUM(s.score)
+FROM BookSimilarities s
+    JOIN Books b ON s.similar_book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE s.book_id IN (SELECT book_id FROM BorrowHistory WHERE user_id = $1)
+    AND s.similar_book_id NOT IN (SELECT book_id FROM BorrowHistory WHERE user_id = $1)
+GROUP BY b.book_id, a.name
+ORDER BY 4 DESC
+LIMIT $2
+    ",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(into_recommendations(records))
+    }
+
+    async fn popular_books(&self, user_id: UserId, limit: i64) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query_as(
+            "
+SELECT b.book_id, b.title, a.name, COUNT(h.borrow_id)::DOUBLE PRECISION
+FROM Books b
+    JOIN Authors a ON b.author_id = a.author_id
+    LEFT JOIN BorrowHistory h ON b.book_id = h.book_id
+WHERE b.book_id NOT IN (SELECT book_id FROM BorrowHistory WHERE user_id = $1)
+GROUP BY b.book_id, a.name
+ORDER BY 4 DESC
+LIMIT $2
+    ",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(into_recommendations(records))
+    }
+
+    async fn most_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<MostBorrowedBook>> {
+        let records = sqlx::query_as::<_, (BookId, String, String, i64)>(
+            "
+SELECT b.book_id, b.title, a.name, COUNT(*)
+FROM BorrowHistory h
+    JOIN Books b ON h.book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE h.borrowed_at >= $1 AND h.borrowed_at < $2
+GROUP BY b.book_id, a.name
+ORDER BY 4 DESC, b.title
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(book_id, title, author_name, times_borrowed)| MostBorrowedBook {
+                    book_id,
+                    title,
+                    author_name,
+                    times_borrowed,
+                },
+            )
+            .collect())
+    }
+
+    async fn never_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<NeverBorrowedBook>> {
+        let records = sqlx::query_as::<_, (BookId, String, String, Integer)>(
+            "
+SELECT b.book_id, b.title, a.name, b.count
+FROM Books b JOIN Authors a ON b.author_id = a.author_id
+WHERE NOT EXISTS (
+    SELECT 1
+    FROM BorrowHistory h
+    WHERE h.book_id = b.book_id AND h.borrowed_at >= $1 AND h.borrowed_at < $2
+)
+ORDER BY b.title
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(book_id, title, author_name, count)| NeverBorrowedBook {
+                book_id,
+                title,
+                author_name,
+                count,
+            })
+            .collect())
+    }
+
+    async fn loan_status(&self, from: i64, to: i64) -> StoreResult<LoanStatusReply> {
+        let (active, overdue) = sqlx::query_as::<_, (i64, i64)>(
+            "
+SELECT
+    COUNT(*) FILTER (WHERE d.valid_until >= extract(epoch FROM now())),
+    COUNT(*) FILTER (WHERE d.valid_until < extract(epoch FROM now()))
+FROM Borrows bo
+    JOIN BorrowData d ON bo.borrow_id = d.borrow_id
+    JOIN BorrowHistory h ON bo.borrow_id = h.borrow_id
+WHERE h.borrowed_at >= $1 AND h.borrowed_at < $2
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(LoanStatusReply { active, overdue })
+    }
+
+    async fn author_popularity(&self, from: i64, to: i64) -> StoreResult<Vec<AuthorPopularity>> {
+        let records = sqlx::query_as::<_, (AuthorId, String, i64, i64)>(
+            "
+SELECT a.author_id, a.name, COUNT(h.borrow_id), COUNT(DISTINCT h.user_id)
+FROM Authors a
+    JOIN Books b ON b.author_id = a.author_id
+    LEFT JOIN BorrowHistory h
+        ON h.book_id = b.book_id AND h.borrowed_at >= $1 AND h.borrowed_at < $2
+GROUP BY a.author_id
+ORDER BY 3 DESC, a.name
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(author_id, name, times_borrowed, distinct_readers)| AuthorPopularity {
+                    author_id,
+                    name,
+                    times_borrowed,
+                    distinct_readers,
+                },
+            )
+            .collect())
+    }
+
+    async fn registrations(&self, from: i64, to: i64) -> StoreResult<Vec<Registrations>> {
+        let records = sqlx::query_as::<_, (String, i64)>(
+            "
+SELECT to_char(to_timestamp(registered_at) AT TIME ZONE 'UTC', 'YYYY-MM-DD'), COUNT(*)
+FROM Users
+WHERE registered_at >= $1 AND registered_at < $2
+GROUP BY 1
+ORDER BY 1
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(day, registrations)| Registrations { day, registrations })
+            .collect())
+    }
+
+    async fn utilisation(&self, from: i64, to: i64) -> StoreResult<Vec<TitleUtilisation>> {
+        let records = sqlx::query_as::<_, (BookId, String, Integer, i64)>(
+            "
+SELECT
+    b.book_id,
+    b.title,
+    b.count,
+    (
+        SELECT COUNT(*)
+        FROM Borrows bo JOIN BorrowHistory h ON bo.borrow_id = h.borrow_id
+        WHERE bo.book_id = b.book_id AND h.borrowed_at >= $1 AND h.borrowed_at < $2
+    )
+FROM Books b
+ORDER BY b.title
+    ",
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(book_id, title, count, borrowed)| TitleUtilisation {
+                book_id,
+                title,
+                count,
+                borrowed,
+                utilisation: if count > 0 {
+                    borrowed as f64 / count as f64
+                } else {
+                    0.0
+                },
+            })
+            .collect())
+    }
+
+    async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: i64,
+        expired_before: i64,
+    ) -> StoreResult<Option<IdempotentRequest>> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM IdempotencyKeys WHERE idempotency_key = $1 AND created_at < $2")
+            .bind(key)
+            .bind(expired_before)
+            .execute(&mut *tx)
+            .await?;
+
+        // Waits for a concurrent request that took the key first to commit
+        let claimed = sqlx::query(
+            "
+INSERT INTO IdempotencyKeys(idempotency_key, fingerprint, created_at)
+VALUES ($1, $2, $3)
+ON CONFLICT (idempotency_key) DO NOTHING
+        ",
+        )
+        .bind(key)
+        .bind(fingerprint)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            == 1;
+
+        if claimed {
+            tx.commit().await?;
+            return Ok(None);
+        }
+
+        let (fingerprint, status, content_type, body) =
+            sqlx::query_as::<_, (String, Option<i32>, Option<String>, Option<Vec<u8>>)>(
+                "SELECT fingerprint, status, content_type, body FROM IdempotencyKeys WHERE idempotency_key = $1",
+            )
+            .bind(key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let response = match (status, body) {
+            (Some(status), Some(body)) => Some(StoredResponse {
+                status: status as u16,
+                content_type,
+                body,
+            }),
+            _ => None,
+        };
+
+        Ok(Some(IdempotentRequest {
+            fingerprint,
+            response,
+        }))
+    }
+
+    async fn save_idempotent_response(
+        &self,
+        key: &str,
+        response: &StoredResponse,
+    ) -> StoreResult<()> {
+        sqlx::query(
+            "UPDATE IdempotencyKeys SET status = $1, content_type = $2, body = $3 WHERE idempotency_key = $4",
+        )
+        .bind(i32::from(response.status))
+        .bind(&response.content_type)
+        .bind(&response.body)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str) -> StoreResult<()> {
+        sqlx::query("DELETE FROM IdempotencyKeys WHERE idempotency_key = $1")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_idempotency_keys(&self, expired_before: i64) -> StoreResult<u64> {
+        let result = sqlx::query("DELETE FROM IdempotencyKeys WHERE created_at < $1")
+            .bind(expired_before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn applied_migrations(&self) -> StoreResult<Vec<(i64, String)>> {
+        sqlx::query_as("SELECT version, description FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn check_integrity(&self) -> StoreResult<Vec<String>> {
+        // PostgreSQL enforces foreign keys on its own and has no cheap equivalent of SQLite's
+        // `integrity_check`, reaching the database is as much as can be checked from here
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        Ok(vec![])
+    }
+
+    async fn backup_to(&self, _path: &Path) -> anyhow::Result<()> {
+        bail!("PostgreSQL databases are backed up with pg_dump, not by the server")
+    }
+
+    async fn is_empty(&self) -> StoreResult<bool> {
+        sqlx::query_scalar(
+            "
+SELECT NOT EXISTS (SELECT 1 FROM Authors)
+    AND NOT EXISTS (SELECT 1 FROM Books)
+    AND NOT EXISTS (SELECT 1 FROM Users)
+    ",
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn reset(&self) -> StoreResult<()> {
+        self.pool
+            .execute(
+                "
+TRUNCATE ReadingListEntries, ReadingLists, BookSimilarities, BookGenres, Genres, BorrowData,
+    Borrows, BorrowHistory, Books, Authors, Users, IdempotencyKeys
+RESTART IDENTITY
+        ",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn load_demo(&self) -> StoreResult<()> {
+        self.pool.execute(DEMO).await?;
+
+        Ok(())
+    }
+
+    async fn load_library(&self, library: &Library) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut author_ids = vec![];
+        for author in &library.authors {
+            author_ids.push(insert_author(&mut tx, author).await?);
+        }
+
+        let mut book_ids = vec![];
+        for book in &library.books {
+            let details = BookDetails {
+                author_id: author_ids[book.author],
+                ..book.details.clone()
+            };
+            book_ids.push(save_book(&mut tx, None, &details).await?);
+        }
+
+        let mut user_ids = vec![];
+        for user in &library.users {
+            user_ids.push(insert_user(&mut tx, user).await?);
+        }
+
+        for loan in &library.loans {
+            let borrow_id: BorrowId = sqlx::query_scalar(
+                "INSERT INTO Borrows(book_id, user_id) VALUES ($1, $2) RETURNING borrow_id",
+            )
+            .bind(book_ids[loan.book])
+            .bind(user_ids[loan.user])
+            .fetch_one(&mut *tx)
+            .await?;
+
+            match loan.returned_at {
+                Some(returned_at) => {
+                    // Deleting the borrow is what marks it as returned in the history
+                    sqlx::query("DELETE FROM Borrows WHERE borrow_id = $1")
+                        .bind(borrow_id)
+                        .execute(&mut *tx)
+                        .await?;
+
+                    sqlx::query(
+                        "UPDATE BorrowHistory SET borrowed_at = $1, returned_at = $2 WHERE borrow_id = $3",
+                    )
+                    .bind(loan.borrowed_at)
+                    .bind(returned_at)
+                    .bind(borrow_id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query(
+                        "INSERT INTO BorrowData(borrow_id, valid_until, chapters_read) VALUES ($1, $2, $3)",
+                    )
+                    .bind(borrow_id)
+                    .bind(loan.valid_until)
+                    .bind(loan.chapters_read)
+                    .execute(&mut *tx)
+                    .await?;
+
+                    sqlx::query("UPDATE BorrowHistory SET borrowed_at = $1 WHERE borrow_id = $2")
+                        .bind(loan.borrowed_at)
+                        .bind(borrow_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        tx.commit().await
+    }
+}
@@ -0,0 +1,1580 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::{Deref, DerefMut},
+    path::Path,
+};
+
+use async_trait::async_trait;
+use schema::{
+    books::{Author, Book, Borrow, BorrowedBook, Recommendation},
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    lists::ReadingList,
+    reports::{
+        AuthorPopularity, LoanStatusReply, MostBorrowedBook, NeverBorrowedBook, Registrations,
+        TitleUtilisation,
+    },
+    Integer, Text,
+};
+use sqlx::{
+    pool::PoolConnection,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Executor, Row, Sqlite, SqliteConnection, SqlitePool, Transaction,
+};
+
+use super::{
+    BookDetails, Credentials, IdempotentRequest, LendOutcome, NewAuthor, NewUser, SaveOutcome,
+    SimilarityInputs, Store, StoreResult, StoredResponse, UserRecord,
+};
+use crate::seed::Library;
+
+const DEMO: &str = include_str!("../../fixtures/demo.sql");
+
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it if needed, and applies pending migrations
+    pub async fn open(path: &Path) -> anyhow::Result<Self> {
+        let pool = SqlitePool::connect_with(
+            SqliteConnectOptions::new()
+                .filename(path)
+                .create_if_missing(true)
+                .foreign_keys(true),
+        )
+        .await?;
+
+        Self::migrate(pool).await
+    }
+
+    /// Opens a fresh database that lives only as long as the store
+    #[cfg(test)]
+    pub async fn in_memory() -> anyhow::Result<Self> {
+        // Every connection to `:memory:` gets its own database, so there must be only one
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::new().foreign_keys(true))
+            .await?;
+
+        Self::migrate(pool).await
+    }
+
+    /// Opens a database without touching it, e.g. to check a backup before restoring it
+    pub async fn open_read_only(path: &Path) -> anyhow::Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .connect_with(SqliteConnectOptions::new().filename(path).read_only(true))
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    async fn migrate(pool: SqlitePool) -> anyhow::Result<Self> {
+        // Databases created before the demo data was moved out of the migrations still have the
+        // old migration recorded
+        let mut migrator = sqlx::migrate!();
+        migrator.set_ignore_missing(true);
+
+        // Migrations that rebuild a table need foreign keys off, which can't be done from inside
+        // the transaction each migration runs in, so they're off for the whole run and the
+        // references are checked at the end instead
+        let mut conn = pool.acquire().await?;
+        conn.execute("PRAGMA foreign_keys = OFF").await?;
+        let migrated = migrator.run(&mut *conn).await;
+        let broken_references = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&mut *conn)
+            .await;
+        conn.execute("PRAGMA foreign_keys = ON").await?;
+
+        migrated?;
+        if !broken_references?.is_empty() {
+            anyhow::bail!("The migrations left references to missing rows behind");
+        }
+
+        Ok(Self { pool })
+    }
+
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Starts a transaction that holds the write lock from its first statement. sqlx starts
+    /// transactions with a plain `BEGIN`, which only takes the lock on the first write: two
+    /// transactions could then read the same state, and the one that writes second fails with
+    /// "database is locked" instead of waiting for the first one.
+    async fn begin_immediate(&self) -> StoreResult<ImmediateTransaction> {
+        let mut conn = self.pool.acquire().await?;
+        // Waits for the lock up to the busy timeout
+        conn.execute("BEGIN IMMEDIATE").await?;
+
+        Ok(ImmediateTransaction { conn: Some(conn) })
+    }
+
+    /// The genres of `book_id`, or of every book if it's `None`
+    async fn genres_by_book(
+        &self,
+        book_id: Option<BookId>,
+    ) -> StoreResult<HashMap<BookId, Vec<Text>>> {
+        let records = sqlx::query!(
+            r#"
+SELECT bg.book_id AS "book_id: BookId", g.name
+FROM BookGenres bg JOIN Genres g ON bg.genre_id = g.genre_id
+WHERE ?1 IS NULL OR bg.book_id = ?1
+ORDER BY g.name;
+    "#,
+            book_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut genres = HashMap::<BookId, Vec<Text>>::new();
+        for record in records {
+            genres.entry(record.book_id).or_default().push(record.name);
+        }
+
+        Ok(genres)
+    }
+
+    /// The user with `user_id`, or every user if it's `None`
+    async fn fetch_users(&self, user_id: Option<UserId>) -> StoreResult<Vec<UserRecord>> {
+        let records = sqlx::query!(
+            r#"
+SELECT
+    u.user_id AS "user_id!: UserId",
+    u.name,
+    u.email,
+    u.type AS "type: UserKind",
+    u.registered_at AS "registered_at: Timestamp",
+    (SELECT COUNT(*) FROM Borrows bo WHERE bo.user_id = u.user_id) AS "borrowed_book_count!: i64"
+FROM Users u
+WHERE ?1 IS NULL OR u.user_id = ?1
+ORDER BY u.user_id;
+    "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| UserRecord {
+                user_id: record.user_id,
+                name: record.name,
+                email: record.email,
+                kind: record.r#type,
+                registered_at: record.registered_at,
+                borrowed_book_count: record.borrowed_book_count,
+            })
+            .collect())
+    }
+
+    /// The book with `book_id`, or every book if it's `None`
+    async fn fetch_books(&self, book_id: Option<BookId>) -> StoreResult<Vec<Book>> {
+        let mut genres = self.genres_by_book(book_id).await?;
+
+        let records = sqlx::query!(
+            r#"
+SELECT
+b.book_id AS "book_id!: BookId", b.title, b.publish_date AS "publish_date: PartialDate", b.publisher, b.count, b.synopsis, b.language, b.series,
+a.author_id AS "author_id!: AuthorId", a.name, a.date_of_birth AS "date_of_birth: PartialDate", a.date_of_death AS "date_of_death: PartialDate", a.description,
+b.count > (SELECT COUNT(*) FROM Borrows bo WHERE bo.book_id = b.book_id) AS "can_be_borrowed!: bool"
+FROM Books b JOIN Authors a ON b.author_id = a.author_id
+WHERE ?1 IS NULL OR b.book_id = ?1;
+"#,
+            book_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Book {
+                book_id: record.book_id,
+                title: record.title,
+                author: Author {
+                    author_id: record.author_id,
+                    name: record.name,
+                    date_of_birth: record.date_of_birth,
+                    date_of_death: record.date_of_death,
+                    description: record.description,
+                },
+                publish_date: record.publish_date,
+                publisher: record.publisher,
+                count: record.count,
+                synopsis: record.synopsis,
+                can_be_borrowed: record.can_be_borrowed,
+                series: record.series,
+                genres: genres.remove(&record.book_id).unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+/// A transaction started with `BEGIN IMMEDIATE`, which sqlx 0.7 can't do with its own
+/// [`Transaction`]. Like those, it's rolled back unless it's committed.
+struct ImmediateTransaction {
+    conn: Option<PoolConnection<Sqlite>>,
+}
+
+impl ImmediateTransaction {
+    async fn commit(mut self) -> StoreResult<()> {
+        self.execute("COMMIT").await?;
+        // Back to the pool, with nothing left to roll back
+        self.conn = None;
+
+        Ok(())
+    }
+}
+
+impl Deref for ImmediateTransaction {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn
+            .as_ref()
+            .expect("only taken when the transaction ends")
+    }
+}
+
+impl DerefMut for ImmediateTransaction {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn
+            .as_mut()
+            .expect("only taken when the transaction ends")
+    }
+}
+
+impl Drop for ImmediateTransaction {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+
+        // Dropping can't wait for the rollback, so the connection only goes back to the pool once
+        // it's done. One that can't be rolled back is closed, which rolls it back too.
+        tokio::spawn(async move {
+            if conn.execute("ROLLBACK").await.is_err() {
+                drop(conn.detach());
+            }
+        });
+    }
+}
+
+async fn insert_author(
+    tx: &mut Transaction<'_, Sqlite>,
+    author: &NewAuthor,
+) -> StoreResult<AuthorId> {
+    let record = sqlx::query!(
+        r#"
+INSERT INTO Authors(name, date_of_birth, date_of_death, description)
+VALUES (?, ?, ?, ?)
+RETURNING author_id AS "author_id!: AuthorId"
+    "#,
+        author.name,
+        author.date_of_birth,
+        author.date_of_death,
+        author.description
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(record.author_id)
+}
+
+async fn insert_user(tx: &mut Transaction<'_, Sqlite>, user: &NewUser) -> StoreResult<UserId> {
+    let record = sqlx::query!(
+        r#"
+INSERT INTO Users(name, type, email, password, registered_at) VALUES (?, ?, ?, ?, ?)
+RETURNING user_id AS "user_id!: UserId"
+    "#,
+        user.name,
+        user.kind,
+        user.email,
+        user.password_hash,
+        user.registered_at
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(record.user_id)
+}
+
+async fn count_lent_copies(conn: &mut SqliteConnection, book_id: BookId) -> StoreResult<i64> {
+    let record = sqlx::query!(
+        r#"
+SELECT COUNT(*) as "count: i64"
+FROM Borrows
+WHERE book_id = ?
+    "#,
+        book_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(record.count)
+}
+
+async fn save_book(
+    conn: &mut SqliteConnection,
+    book_id: Option<BookId>,
+    book: &BookDetails,
+) -> StoreResult<BookId> {
+    let book_id = if let Some(book_id) = book_id {
+        sqlx::query!(
+            r#"
+UPDATE Books SET
+    title = ?,
+    author_id = ?,
+    publish_date = ?,
+    publisher = ?,
+    count = ?,
+    synopsis = ?,
+    series = ?
+WHERE book_id = ?
+        "#,
+            book.title,
+            book.author_id,
+            book.publish_date,
+            book.publisher,
+            book.count,
+            book.synopsis,
+            book.series,
+            book_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        book_id
+    } else {
+        sqlx::query!(
+            r#"
+INSERT INTO Books(title, author_id, publish_date, publisher, count, synopsis, language, series)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+RETURNING book_id AS "book_id!: BookId";
+        "#,
+            book.title,
+            book.author_id,
+            book.publish_date,
+            book.publisher,
+            book.count,
+            book.synopsis,
+            book.language,
+            book.series
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .book_id
+    };
+
+    sqlx::query!("DELETE FROM BookGenres WHERE book_id = ?", book_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for genre in &book.genres {
+        sqlx::query!("INSERT OR IGNORE INTO Genres(name) VALUES (?)", genre)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query!(
+            r#"
+INSERT OR IGNORE INTO BookGenres(book_id, genre_id)
+SELECT ?, genre_id
+FROM Genres
+WHERE name = ?
+        "#,
+            book_id,
+            genre
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(book_id)
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn user_kind(&self, user_id: UserId) -> StoreResult<Option<UserKind>> {
+        let record = sqlx::query!(
+            r#"SELECT type AS "type: UserKind" FROM Users WHERE user_id = ?"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| record.r#type))
+    }
+
+    async fn credentials(&self, email: &str) -> StoreResult<Option<Credentials>> {
+        let record = sqlx::query!(
+            r#"
+SELECT user_id AS "user_id!: UserId", type AS "type: UserKind", password
+FROM Users
+WHERE email = ?
+    "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| Credentials {
+            user_id: record.user_id,
+            kind: record.r#type,
+            password_hash: record.password,
+        }))
+    }
+
+    async fn credentials_by_id(&self, user_id: UserId) -> StoreResult<Option<Credentials>> {
+        let record = sqlx::query!(
+            r#"SELECT type AS "type: UserKind", password FROM Users WHERE user_id = ?"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| Credentials {
+            user_id,
+            kind: record.r#type,
+            password_hash: record.password,
+        }))
+    }
+
+    async fn user_id_by_email(&self, email: &str) -> StoreResult<Option<UserId>> {
+        let record = sqlx::query!(
+            r#"SELECT user_id AS "user_id!: UserId" FROM Users WHERE email = ?"#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| record.user_id))
+    }
+
+    async fn create_user(&self, user: &NewUser) -> StoreResult<UserId> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = insert_user(&mut tx, user).await?;
+        tx.commit().await?;
+
+        Ok(user_id)
+    }
+
+    async fn users(&self) -> StoreResult<Vec<UserRecord>> {
+        self.fetch_users(None).await
+    }
+
+    async fn user(&self, user_id: UserId) -> StoreResult<Option<UserRecord>> {
+        Ok(self.fetch_users(Some(user_id)).await?.pop())
+    }
+
+    async fn set_user_kind(&self, user_id: UserId, kind: UserKind) -> StoreResult<()> {
+        sqlx::query!("UPDATE Users SET type = ? WHERE user_id = ?", kind, user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_password(&self, user_id: UserId, password_hash: &str) -> StoreResult<()> {
+        sqlx::query!(
+            "UPDATE Users SET password = ? WHERE user_id = ?",
+            password_hash,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: UserId) -> StoreResult<()> {
+        sqlx::query!("DELETE FROM Users WHERE user_id = ?", user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn books(&self) -> StoreResult<Vec<Book>> {
+        self.fetch_books(None).await
+    }
+
+    async fn book(&self, book_id: BookId) -> StoreResult<Option<Book>> {
+        Ok(self.fetch_books(Some(book_id)).await?.pop())
+    }
+
+    async fn authors(&self) -> StoreResult<Vec<Author>> {
+        let records = sqlx::query!(
+            r#"
+SELECT
+    author_id AS "author_id: AuthorId", name, date_of_birth AS "date_of_birth: PartialDate",
+    date_of_death AS "date_of_death: PartialDate", description
+FROM Authors;
+    "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Author {
+                author_id: record.author_id,
+                name: record.name,
+                date_of_birth: record.date_of_birth,
+                date_of_death: record.date_of_death,
+                description: record.description,
+            })
+            .collect())
+    }
+
+    async fn add_author(&self, author: &NewAuthor) -> StoreResult<AuthorId> {
+        let mut tx = self.pool.begin().await?;
+        let author_id = insert_author(&mut tx, author).await?;
+        tx.commit().await?;
+
+        Ok(author_id)
+    }
+
+    async fn save_book(
+        &self,
+        book_id: Option<BookId>,
+        book: &BookDetails,
+    ) -> StoreResult<SaveOutcome> {
+        // Holds the write lock before counting, so that no copy can be lent in the meantime
+        let mut tx = self.begin_immediate().await?;
+
+        if let Some(book_id) = book_id {
+            let lent = count_lent_copies(&mut tx, book_id).await?;
+            if book.count < lent {
+                return Ok(SaveOutcome::CopiesLent(lent));
+            }
+        }
+
+        let book_id = save_book(&mut tx, book_id, book).await?;
+        tx.commit().await?;
+
+        Ok(SaveOutcome::Saved(book_id))
+    }
+
+    async fn delete_book(&self, book_id: BookId) -> StoreResult<bool> {
+        let mut tx = self.begin_immediate().await?;
+
+        let borrowed_count = sqlx::query!(
+            r#"
+SELECT COUNT(*) as "count"
+FROM Borrows
+WHERE book_id = ?;
+    "#,
+            book_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if borrowed_count.count > 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!("DELETE FROM Books WHERE book_id = ?", book_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn lend_book(
+        &self,
+        book_id: BookId,
+        user_id: UserId,
+        checked_out_by: Option<UserId>,
+        valid_until: Timestamp,
+    ) -> StoreResult<Option<LendOutcome>> {
+        let mut tx = self.begin_immediate().await?;
+
+        let Some(record) = sqlx::query!(
+            r#"
+SELECT b.count > (SELECT COUNT(*) FROM Borrows bo WHERE bo.book_id = b.book_id) AS "can_be_borrowed!: bool"
+FROM Books b
+WHERE b.book_id = ?;
+    "#,
+            book_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if !record.can_be_borrowed {
+            return Ok(Some(LendOutcome::NoCopiesLeft));
+        }
+
+        let record = sqlx::query!(
+            r#"
+SELECT COUNT(*) as "times_borrowed"
+FROM Borrows
+WHERE book_id = ? AND user_id = ?;
+        "#,
+            book_id,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if record.times_borrowed >= 1 {
+            return Ok(Some(LendOutcome::AlreadyBorrowed));
+        }
+
+        let record = sqlx::query!(
+            r#"
+INSERT INTO Borrows(book_id, user_id, checked_out_by) VALUES (?, ?, ?)
+RETURNING borrow_id;
+        "#,
+            book_id,
+            user_id,
+            checked_out_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+INSERT INTO BorrowData(borrow_id, valid_until, chapters_read)
+VALUES (?, ?, 0);
+    "#,
+            record.borrow_id,
+            valid_until
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(LendOutcome::Lent))
+    }
+
+    async fn borrows(&self) -> StoreResult<Vec<Borrow>> {
+        let records = sqlx::query!(
+            r#"
+SELECT
+    b.borrow_id AS "borrow_id: BorrowId",
+    b.book_id AS "book_id: BookId",
+    b.user_id AS "user_id: UserId",
+    d.valid_until AS "valid_until: Timestamp"
+FROM Borrows b JOIN BorrowData d ON b.borrow_id = d.borrow_id;
+    "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Borrow {
+                borrow_id: record.borrow_id,
+                book_id: record.book_id,
+                user_id: record.user_id,
+                valid_until: record.valid_until,
+            })
+            .collect())
+    }
+
+    async fn borrowed_by(&self, user_id: UserId) -> StoreResult<Vec<BorrowedBook>> {
+        let records = sqlx::query!(
+            r#"
+SELECT d.borrow_id AS "borrow_id: BorrowId", b.book_id AS "book_id: BookId", d.valid_until AS "valid_until: Timestamp", d.chapters_read
+FROM Borrows b JOIN BorrowData d ON b.borrow_id = d.borrow_id
+WHERE b.user_id = ?
+    "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| BorrowedBook {
+                borrow_id: record.borrow_id,
+                book_id: record.book_id,
+                valid_until: record.valid_until,
+                chapters_read: record.chapters_read,
+            })
+            .collect())
+    }
+
+    async fn borrower(&self, borrow_id: BorrowId) -> StoreResult<Option<UserId>> {
+        let record = sqlx::query!(
+            r#"SELECT user_id AS "user_id: UserId" FROM Borrows WHERE borrow_id = ?"#,
+            borrow_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| record.user_id))
+    }
+
+    async fn count_borrowed_by(&self, user_id: UserId) -> StoreResult<i64> {
+        let record = sqlx::query!(
+            r#"
+SELECT COUNT(*) as "count: i64"
+FROM Borrows
+WHERE user_id = ?
+    "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(record.count)
+    }
+
+    async fn count_lent_copies(&self, book_id: BookId) -> StoreResult<i64> {
+        count_lent_copies(&mut *self.pool.acquire().await?, book_id).await
+    }
+
+    async fn set_valid_until(
+        &self,
+        borrow_id: BorrowId,
+        valid_until: Timestamp,
+    ) -> StoreResult<()> {
+        sqlx::query!(
+            "UPDATE BorrowData SET valid_until = ? WHERE borrow_id = ?",
+            valid_until,
+            borrow_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn lengthen_borrow(&self, borrow_id: BorrowId, days: i64) -> StoreResult<()> {
+        sqlx::query!(
+            r#"
+UPDATE BorrowData
+SET valid_until = unixepoch(valid_until, 'unixepoch', '+' || ? || ' days')
+WHERE borrow_id = ?
+    "#,
+            days,
+            borrow_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_chapters_read(&self, borrow_id: BorrowId, chapters_read: i64) -> StoreResult<()> {
+        sqlx::query!(
+            "UPDATE BorrowData SET chapters_read = ? WHERE borrow_id = ?",
+            chapters_read,
+            borrow_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn return_book(&self, borrow_id: BorrowId) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("DELETE FROM BorrowData WHERE borrow_id = ?", borrow_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!("DELETE FROM Borrows WHERE borrow_id = ?", borrow_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    async fn reading_lists(
+        &self,
+        user_id: UserId,
+        requester_id: UserId,
+    ) -> StoreResult<Vec<ReadingList>> {
+        let lists = sqlx::query!(
+            r#"
+SELECT list_id AS "list_id!", user_id AS "user_id: UserId", name, is_public AS "is_public: bool"
+FROM ReadingLists
+WHERE user_id = ? AND (is_public = 1 OR user_id = ?)
+ORDER BY name;
+    "#,
+            user_id,
+            requester_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let entries = sqlx::query!(
+            r#"
+SELECT e.list_id, e.book_id AS "book_id: BookId"
+FROM ReadingListEntries e JOIN ReadingLists l ON e.list_id = l.list_id
+WHERE l.user_id = ?
+ORDER BY e.position;
+    "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut book_ids_by_list = HashMap::<Integer, Vec<BookId>>::new();
+        for entry in entries {
+            book_ids_by_list
+                .entry(entry.list_id)
+                .or_default()
+                .push(entry.book_id);
+        }
+
+        Ok(lists
+            .into_iter()
+            .map(|list| ReadingList {
+                list_id: list.list_id,
+                owner_id: list.user_id,
+                name: list.name,
+                is_public: list.is_public,
+                book_ids: book_ids_by_list.remove(&list.list_id).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn reading_list_owner(&self, list_id: Integer) -> StoreResult<Option<UserId>> {
+        let record = sqlx::query!(
+            r#"SELECT user_id AS "user_id: UserId" FROM ReadingLists WHERE list_id = ?"#,
+            list_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|record| record.user_id))
+    }
+
+    async fn create_reading_list(
+        &self,
+        user_id: UserId,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<Integer> {
+        let record = sqlx::query!(
+            r#"
+INSERT INTO ReadingLists(user_id, name, is_public)
+VALUES (?, ?, ?)
+RETURNING list_id;
+    "#,
+            user_id,
+            name,
+            is_public
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(record.list_id)
+    }
+
+    async fn change_reading_list(
+        &self,
+        list_id: Integer,
+        name: &str,
+        is_public: bool,
+    ) -> StoreResult<()> {
+        sqlx::query!(
+            "
+UPDATE ReadingLists
+SET name = ?, is_public = ?
+WHERE list_id = ?
+    ",
+            name,
+            is_public,
+            list_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_reading_list(&self, list_id: Integer) -> StoreResult<()> {
+        sqlx::query!("DELETE FROM ReadingLists WHERE list_id = ?", list_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn reading_list_contains(&self, list_id: Integer, book_id: BookId) -> StoreResult<bool> {
+        let record = sqlx::query!(
+            "
+SELECT book_id
+FROM ReadingListEntries
+WHERE list_id = ? AND book_id = ?
+    ",
+            list_id,
+            book_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.is_some())
+    }
+
+    async fn add_to_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()> {
+        sqlx::query!(
+            "
+INSERT OR IGNORE INTO ReadingListEntries(list_id, book_id, position)
+SELECT ?, ?, COALESCE(MAX(position), 0) + 1
+FROM ReadingListEntries
+WHERE list_id = ?
+    ",
+            list_id,
+            book_id,
+            list_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_from_reading_list(&self, list_id: Integer, book_id: BookId) -> StoreResult<()> {
+        sqlx::query!(
+            "
+DELETE FROM ReadingListEntries
+WHERE list_id = ? AND book_id = ?
+    ",
+            list_id,
+            book_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reorder_reading_list(
+        &self,
+        list_id: Integer,
+        book_ids: &[BookId],
+    ) -> StoreResult<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = sqlx::query!(
+            r#"SELECT book_id AS "book_id: BookId" FROM ReadingListEntries WHERE list_id = ?"#,
+            list_id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|record| record.book_id)
+        .collect::<HashSet<_>>();
+
+        let requested = book_ids.iter().copied().collect::<HashSet<_>>();
+        if requested.len() != book_ids.len() || requested != current {
+            return Ok(false);
+        }
+
+        for (position, book_id) in (1_i64..).zip(book_ids) {
+            sqlx::query!(
+                "
+UPDATE ReadingListEntries
+SET position = ?
+WHERE list_id = ? AND book_id = ?
+        ",
+                position,
+                list_id,
+                book_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn similarity_inputs(&self) -> StoreResult<SimilarityInputs> {
+        let books = sqlx::query!(
+            r#"SELECT book_id AS "book_id: BookId", author_id AS "author_id: AuthorId", series FROM Books"#
+        )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|record| (record.book_id, record.author_id, record.series))
+            .collect();
+
+        let genres =
+            sqlx::query!(r#"SELECT book_id AS "book_id: BookId", genre_id FROM BookGenres"#)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|record| (record.book_id, record.genre_id))
+                .collect();
+
+        let readers = sqlx::query!(
+            r#"SELECT DISTINCT book_id AS "book_id: BookId", user_id AS "user_id: UserId" FROM BorrowHistory"#
+        )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|record| (record.book_id, record.user_id))
+            .collect();
+
+        Ok(SimilarityInputs {
+            books,
+            genres,
+            readers,
+        })
+    }
+
+    async fn replace_similarities(
+        &self,
+        similarities: &[(BookId, BookId, f64)],
+    ) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("DELETE FROM BookSimilarities")
+            .execute(&mut *tx)
+            .await?;
+
+        for &(book_id, similar_book_id, score) in similarities {
+            sqlx::query!(
+                "
+INSERT INTO BookSimilarities(book_id, similar_book_id, score)
+VALUES (?, ?, ?)
+        ",
+                book_id,
+                similar_book_id,
+                score
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    async fn similar_books(&self, book_id: BookId) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query!(
+            r#"
+SELECT b.book_id AS "book_id!: BookId", b.title, a.name AS "author_name", s.score
+FROM BookSimilarities s
+    JOIN Books b ON s.similar_book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE s.book_id = ?
+ORDER BY s.score DESC;
+    "#,
+            book_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Recommendation {
+                book_id: record.book_id,
+                title: record.title,
+                author_name: record.author_name,
+                score: record.score,
+            })
+            .collect())
+    }
+
+    async fn recommendations(
+        &self,
+        user_id: UserId,
+        limit: i64,
+    ) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query!(
+            r#"
+SELECT b.book_id AS "book_id!: BookId", b.title, a.name AS "author_name", SUM(s.score) AS "score!: f64"
+FROM BookSimilarities s
+    JOIN Books b ON s.similar_book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE s.book_id IN (SELECT book_id FROM BorrowHistory WHERE user_id = ?)
+    AND s.similar_book_id NOT IN (SELECT book_id FROM BorrowHistory WHERE user_id = ?)
+GROUP BY b.book_id
+ORDER BY 4 DESC
+LIMIT ?;
+    "#,
+            user_id,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Recommendation {
+                book_id: record.book_id,
+                title: record.title,
+                author_name: record.author_name,
+                score: record.score,
+            })
+            .collect())
+    }
+
+    async fn popular_books(&self, user_id: UserId, limit: i64) -> StoreResult<Vec<Recommendation>> {
+        let records = sqlx::query!(
+            r#"
+SELECT b.book_id AS "book_id!: BookId", b.title, a.name AS "author_name", COUNT(h.borrow_id) AS "times_borrowed!: i64"
+FROM Books b
+    JOIN Authors a ON b.author_id = a.author_id
+    LEFT JOIN BorrowHistory h ON b.book_id = h.book_id
+WHERE b.book_id NOT IN (SELECT book_id FROM BorrowHistory WHERE user_id = ?)
+GROUP BY b.book_id
+ORDER BY 4 DESC
+LIMIT ?;
+    "#,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Recommendation {
+                book_id: record.book_id,
+                title: record.title,
+                author_name: record.author_name,
+                score: record.times_borrowed as f64,
+            })
+            .collect())
+    }
+
+    async fn most_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<MostBorrowedBook>> {
+        let records = sqlx::query!(
+            r#"
+SELECT b.book_id AS "book_id: BookId", b.title, a.name AS "author_name", COUNT(*) AS "times_borrowed!: i64"
+FROM BorrowHistory h
+    JOIN Books b ON h.book_id = b.book_id
+    JOIN Authors a ON b.author_id = a.author_id
+WHERE h.borrowed_at >= ? AND h.borrowed_at < ?
+GROUP BY b.book_id
+ORDER BY 4 DESC, b.title;
+    "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| MostBorrowedBook {
+                book_id: record.book_id,
+                title: record.title,
+                author_name: record.author_name,
+                times_borrowed: record.times_borrowed,
+            })
+            .collect())
+    }
+
+    async fn never_borrowed(&self, from: i64, to: i64) -> StoreResult<Vec<NeverBorrowedBook>> {
+        let records = sqlx::query!(
+            r#"
+SELECT b.book_id AS "book_id!: BookId", b.title, a.name AS "author_name", b.count
+FROM Books b JOIN Authors a ON b.author_id = a.author_id
+WHERE NOT EXISTS (
+    SELECT 1
+    FROM BorrowHistory h
+    WHERE h.book_id = b.book_id AND h.borrowed_at >= ? AND h.borrowed_at < ?
+)
+ORDER BY b.title;
+    "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| NeverBorrowedBook {
+                book_id: record.book_id,
+                title: record.title,
+                author_name: record.author_name,
+                count: record.count,
+            })
+            .collect())
+    }
+
+    async fn loan_status(&self, from: i64, to: i64) -> StoreResult<LoanStatusReply> {
+        let record = sqlx::query!(
+            r#"
+SELECT
+    COALESCE(SUM(d.valid_until >= unixepoch()), 0) AS "active!: i64",
+    COALESCE(SUM(d.valid_until < unixepoch()), 0) AS "overdue!: i64"
+FROM Borrows bo
+    JOIN BorrowData d ON bo.borrow_id = d.borrow_id
+    JOIN BorrowHistory h ON bo.borrow_id = h.borrow_id
+WHERE h.borrowed_at >= ? AND h.borrowed_at < ?;
+    "#,
+            from,
+            to
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(LoanStatusReply {
+            active: record.active,
+            overdue: record.overdue,
+        })
+    }
+
+    async fn author_popularity(&self, from: i64, to: i64) -> StoreResult<Vec<AuthorPopularity>> {
+        let records = sqlx::query!(
+            r#"
+SELECT
+    a.author_id AS "author_id!: AuthorId",
+    a.name,
+    COUNT(h.borrow_id) AS "times_borrowed!: i64",
+    COUNT(DISTINCT h.user_id) AS "distinct_readers!: i64"
+FROM Authors a
+    JOIN Books b ON b.author_id = a.author_id
+    LEFT JOIN BorrowHistory h
+        ON h.book_id = b.book_id AND h.borrowed_at >= ? AND h.borrowed_at < ?
+GROUP BY a.author_id
+ORDER BY 3 DESC, a.name;
+    "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| AuthorPopularity {
+                author_id: record.author_id,
+                name: record.name,
+                times_borrowed: record.times_borrowed,
+                distinct_readers: record.distinct_readers,
+            })
+            .collect())
+    }
+
+    async fn registrations(&self, from: i64, to: i64) -> StoreResult<Vec<Registrations>> {
+        let records = sqlx::query!(
+            r#"
+SELECT date(registered_at, 'unixepoch') AS "day!: String", COUNT(*) AS "registrations!: i64"
+FROM Users
+WHERE registered_at >= ? AND registered_at < ?
+GROUP BY 1
+ORDER BY 1;
+    "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| Registrations {
+                day: record.day,
+                registrations: record.registrations,
+            })
+            .collect())
+    }
+
+    async fn utilisation(&self, from: i64, to: i64) -> StoreResult<Vec<TitleUtilisation>> {
+        let records = sqlx::query!(
+            r#"
+SELECT
+    b.book_id AS "book_id!: BookId",
+    b.title,
+    b.count,
+    (
+        SELECT COUNT(*)
+        FROM Borrows bo JOIN BorrowHistory h ON bo.borrow_id = h.borrow_id
+        WHERE bo.book_id = b.book_id AND h.borrowed_at >= ? AND h.borrowed_at < ?
+    ) AS "borrowed!: i64"
+FROM Books b
+ORDER BY b.title;
+    "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| TitleUtilisation {
+                book_id: record.book_id,
+                title: record.title,
+                count: record.count,
+                borrowed: record.borrowed,
+                utilisation: if record.count > 0 {
+                    record.borrowed as f64 / record.count as f64
+                } else {
+                    0.0
+                },
+            })
+            .collect())
+    }
+
+    async fn claim_idempotency_key(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: i64,
+        expired_before: i64,
+    ) -> StoreResult<Option<IdempotentRequest>> {
+        let mut tx = self.begin_immediate().await?;
+
+        sqlx::query!(
+            "DELETE FROM IdempotencyKeys WHERE idempotency_key = ? AND created_at < ?",
+            key,
+            expired_before
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let record = sqlx::query!(
+            "SELECT fingerprint, status, content_type, body FROM IdempotencyKeys WHERE idempotency_key = ?",
+            key
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(record) = record {
+            let response = match (record.status, record.body) {
+                (Some(status), Some(body)) => Some(StoredResponse {
+                    status: status as u16,
+                    content_type: record.content_type,
+                    body,
+                }),
+                _ => None,
+            };
+
+            return Ok(Some(IdempotentRequest {
+                fingerprint: record.fingerprint,
+                response,
+            }));
+        }
+
+        sqlx::query!(
+            "INSERT INTO IdempotencyKeys(idempotency_key, fingerprint, created_at) VALUES (?, ?, ?)",
+            key,
+            fingerprint,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(None)
+    }
+
+    async fn save_idempotent_response(
+        &self,
+        key: &str,
+        response: &StoredResponse,
+    ) -> StoreResult<()> {
+        sqlx::query!(
+            "
+UPDATE IdempotencyKeys
+SET status = ?, content_type = ?, body = ?
+WHERE idempotency_key = ?;
+    ",
+            response.status,
+            response.content_type,
+            response.body,
+            key
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str) -> StoreResult<()> {
+        sqlx::query!("DELETE FROM IdempotencyKeys WHERE idempotency_key = ?", key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_idempotency_keys(&self, expired_before: i64) -> StoreResult<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM IdempotencyKeys WHERE created_at < ?",
+            expired_before
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn applied_migrations(&self) -> StoreResult<Vec<(i64, String)>> {
+        sqlx::query_as("SELECT version, description FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn check_integrity(&self) -> StoreResult<Vec<String>> {
+        let mut problems = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get::<String, _>(0))
+            .filter(|message| message != "ok")
+            .collect::<Vec<_>>();
+
+        for row in sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&self.pool)
+            .await?
+        {
+            problems.push(format!(
+                "row {} of {} references a missing row in {}",
+                row.get::<Option<i64>, _>(1)
+                    .map_or("?".to_string(), |rowid| rowid.to_string()),
+                row.get::<String, _>(0),
+                row.get::<String, _>(2)
+            ));
+        }
+
+        Ok(problems)
+    }
+
+    async fn backup_to(&self, path: &Path) -> anyhow::Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn is_empty(&self) -> StoreResult<bool> {
+        let record = sqlx::query!(
+            r#"
+SELECT
+    (SELECT COUNT(*) FROM Authors)
+    + (SELECT COUNT(*) FROM Books)
+    + (SELECT COUNT(*) FROM Users) AS "rows!: i64"
+    "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(record.rows == 0)
+    }
+
+    async fn reset(&self) -> StoreResult<()> {
+        self.pool
+            .execute(
+                "
+DELETE FROM ReadingListEntries;
+DELETE FROM ReadingLists;
+DELETE FROM BookSimilarities;
+DELETE FROM BookGenres;
+DELETE FROM Genres;
+DELETE FROM BorrowData;
+DELETE FROM Borrows;
+DELETE FROM BorrowHistory;
+DELETE FROM Books;
+DELETE FROM Authors;
+DELETE FROM Users;
+DELETE FROM IdempotencyKeys;
+DELETE FROM sqlite_sequence;
+        ",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn load_demo(&self) -> StoreResult<()> {
+        self.pool.execute(DEMO).await?;
+
+        Ok(())
+    }
+
+    async fn load_library(&self, library: &Library) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut author_ids = vec![];
+        for author in &library.authors {
+            author_ids.push(insert_author(&mut tx, author).await?);
+        }
+
+        let mut book_ids = vec![];
+        for book in &library.books {
+            let details = BookDetails {
+                author_id: author_ids[book.author],
+                ..book.details.clone()
+            };
+            book_ids.push(save_book(&mut tx, None, &details).await?);
+        }
+
+        let mut user_ids = vec![];
+        for user in &library.users {
+            user_ids.push(insert_user(&mut tx, user).await?);
+        }
+
+        for loan in &library.loans {
+            let record = sqlx::query!(
+                "
+INSERT INTO Borrows(book_id, user_id) VALUES (?, ?)
+RETURNING borrow_id
+            ",
+                book_ids[loan.book],
+                user_ids[loan.user]
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            match loan.returned_at {
+                Some(returned_at) => {
+                    // Deleting the borrow is what marks it as returned in the history
+                    sqlx::query!("DELETE FROM Borrows WHERE borrow_id = ?", record.borrow_id)
+                        .execute(&mut *tx)
+                        .await?;
+
+                    sqlx::query!(
+                        "
+UPDATE BorrowHistory
+SET borrowed_at = ?, returned_at = ?
+WHERE borrow_id = ?
+                ",
+                        loan.borrowed_at,
+                        returned_at,
+                        record.borrow_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query!(
+                        "
+INSERT INTO BorrowData(borrow_id, valid_until, chapters_read) VALUES (?, ?, ?)
+                ",
+                        record.borrow_id,
+                        loan.valid_until,
+                        loan.chapters_read
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+
+                    sqlx::query!(
+                        "UPDATE BorrowHistory SET borrowed_at = ? WHERE borrow_id = ?",
+                        loan.borrowed_at,
+                        record.borrow_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await
+    }
+}
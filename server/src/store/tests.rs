@@ -0,0 +1,742 @@
+//! Tests shared by the storage backends. Each one runs against an in-memory SQLite database and,
+//! when `BIBLIOTECA_TEST_POSTGRES_URL` is set, against a fresh database created on that PostgreSQL
+//! server for the duration of the test.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use schema::{
+    dates::{PartialDate, Timestamp},
+    ids::{AuthorId, BookId, BorrowId, UserId, UserKind},
+    Integer,
+};
+use sqlx::{postgres::PgConnectOptions, Connection, Executor, PgConnection};
+
+use super::{
+    BookDetails, LendOutcome, NewAuthor, NewUser, PostgresStore, SaveOutcome, SqliteStore, Store,
+    StoreResult, StoredResponse,
+};
+use crate::seed::{Library, LibraryBook, LibraryLoan};
+
+const POSTGRES_URL_VAR: &str = "BIBLIOTECA_TEST_POSTGRES_URL";
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// `count` days after the Unix epoch
+fn days(count: i64) -> Timestamp {
+    Timestamp::from_unix(count * DAY).unwrap()
+}
+
+/// A database made for a single test, dropped once the test passes
+struct TestDatabase {
+    server: PgConnectOptions,
+    name: String,
+    store: PostgresStore,
+}
+
+impl TestDatabase {
+    async fn create() -> Option<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let url = std::env::var(POSTGRES_URL_VAR).ok()?;
+        let server = url.parse::<PgConnectOptions>().unwrap();
+        let name = format!(
+            "biblioteca_test_{}_{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut connection = PgConnection::connect_with(&server).await.unwrap();
+        connection
+            .execute(format!("DROP DATABASE IF EXISTS {name}").as_str())
+            .await
+            .unwrap();
+        connection
+            .execute(format!("CREATE DATABASE {name}").as_str())
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let store = PostgresStore::open_with(server.clone().database(&name))
+            .await
+            .unwrap();
+
+        Some(Self {
+            server,
+            name,
+            store,
+        })
+    }
+
+    async fn drop(self) {
+        self.store.close().await;
+
+        // Closing the pool doesn't wait for the server to end the sessions
+        let mut connection = PgConnection::connect_with(&self.server).await.unwrap();
+        connection
+            .execute(format!("DROP DATABASE {} WITH (FORCE)", self.name).as_str())
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+    }
+}
+
+macro_rules! store_tests {
+    ($($name:ident),* $(,)?) => {
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    let store = super::SqliteStore::in_memory().await.unwrap();
+                    super::$name(&store).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    let Some(database) = super::TestDatabase::create().await else {
+                        eprintln!("{} isn't set, skipping", super::POSTGRES_URL_VAR);
+                        return;
+                    };
+                    super::$name(&database.store).await;
+                    database.drop().await;
+                }
+            )*
+        }
+    };
+}
+
+store_tests!(
+    books_are_saved_with_their_genres,
+    a_book_is_lent_while_copies_are_left,
+    a_borrowed_book_cant_be_deleted,
+    loans_can_be_changed,
+    users_are_managed,
+    reading_lists_are_managed,
+    reports_follow_the_borrow_history,
+    recommendations_come_from_similarities,
+    fixtures_are_loaded_and_reset,
+    migrations_are_recorded,
+    idempotency_keys_expire,
+);
+
+fn is_unique_violation<T>(result: StoreResult<T>) -> bool {
+    matches!(result, Err(sqlx::Error::Database(err)) if err.is_unique_violation())
+}
+
+fn is_foreign_key_violation<T>(result: StoreResult<T>) -> bool {
+    matches!(result, Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation())
+}
+
+fn new_author(name: &str) -> NewAuthor {
+    NewAuthor {
+        name: name.into(),
+        date_of_birth: PartialDate::from_ymd(1870, 1, 1).unwrap(),
+        date_of_death: None,
+        description: format!("Despre {name}"),
+    }
+}
+
+fn book_details(title: &str, author_id: AuthorId, count: Integer) -> BookDetails {
+    BookDetails {
+        title: title.into(),
+        author_id,
+        publish_date: PartialDate::from_ymd(1972, 9, 27).unwrap(),
+        publisher: "Polirom".into(),
+        count,
+        synopsis: format!("Rezumatul cărții {title}"),
+        language: "ro".into(),
+        series: None,
+        genres: vec![],
+    }
+}
+
+fn new_user(email: &str, registered_at: i64) -> NewUser {
+    NewUser {
+        name: email.split('@').next().unwrap().into(),
+        kind: UserKind::Reader,
+        email: email.into(),
+        password_hash: "hash".into(),
+        registered_at: Timestamp::from_unix(registered_at).unwrap(),
+    }
+}
+
+async fn add_book(store: &dyn Store, title: &str, count: Integer) -> BookId {
+    let author_id = store.add_author(&new_author("Autor")).await.unwrap();
+
+    let outcome = store
+        .save_book(None, &book_details(title, author_id, count))
+        .await
+        .unwrap();
+    let SaveOutcome::Saved(book_id) = outcome else {
+        panic!("the book wasn't added: {outcome:?}");
+    };
+
+    book_id
+}
+
+async fn add_user(store: &dyn Store, email: &str) -> UserId {
+    store.create_user(&new_user(email, 0)).await.unwrap()
+}
+
+async fn lend(store: &dyn Store, book_id: BookId, user_id: UserId) -> Option<LendOutcome> {
+    store
+        .lend_book(book_id, user_id, None, days(30))
+        .await
+        .unwrap()
+}
+
+async fn books_are_saved_with_their_genres(store: &dyn Store) {
+    let author = NewAuthor {
+        date_of_birth: PartialDate::from_year(1837).unwrap().circa(),
+        date_of_death: PartialDate::from_ymd(1889, 12, 31),
+        ..new_author("Ion Creangă")
+    };
+    let author_id = store.add_author(&author).await.unwrap();
+    let mut details = book_details("Amintiri din copilărie", author_id, 2);
+    details.publish_date = PartialDate::from_year_month(1892, 3).unwrap();
+    details.series = Some("Amintiri".into());
+    details.genres = vec!["Umor".into(), "Memorii".into()];
+
+    let SaveOutcome::Saved(book_id) = store.save_book(None, &details).await.unwrap() else {
+        panic!("the book wasn't added");
+    };
+
+    let books = store.books().await.unwrap();
+    assert_eq!(books.len(), 1);
+    let book = &books[0];
+    assert_eq!(book.book_id, book_id);
+    assert_eq!(book.title, "Amintiri din copilărie");
+    assert_eq!(book.author.author_id, author_id);
+    assert_eq!(book.author.name, "Ion Creangă");
+    assert_eq!(book.author.date_of_birth, author.date_of_birth);
+    assert_eq!(book.author.date_of_death, author.date_of_death);
+    assert_eq!(book.publish_date, details.publish_date);
+    assert_eq!(book.count, 2);
+    assert_eq!(book.series.as_deref(), Some("Amintiri"));
+    assert_eq!(book.genres, ["Memorii", "Umor"]);
+    assert!(book.can_be_borrowed);
+
+    details.title = "Amintiri".into();
+    details.genres = vec!["Umor".into(), "Clasic".into()];
+    details.series = None;
+    assert_eq!(
+        store.save_book(Some(book_id), &details).await.unwrap(),
+        SaveOutcome::Saved(book_id)
+    );
+
+    let book = store.book(book_id).await.unwrap().unwrap();
+    assert_eq!(book.title, "Amintiri");
+    assert_eq!(book.series, None);
+    assert_eq!(book.genres, ["Clasic", "Umor"]);
+    assert_eq!(store.books().await.unwrap().len(), 1);
+    assert!(store.book(BookId(book_id.0 + 1)).await.unwrap().is_none());
+
+    let authors = store.authors().await.unwrap();
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "Ion Creangă");
+    assert_eq!(authors[0].date_of_birth.to_string(), "1837~");
+    assert_eq!(authors[0].description, "Despre Ion Creangă");
+}
+
+async fn a_book_is_lent_while_copies_are_left(store: &dyn Store) {
+    let book_id = add_book(store, "Dl. Goe", 2).await;
+    let ana = add_user(store, "ana@example.com").await;
+    let radu = add_user(store, "radu@example.com").await;
+    let ioana = add_user(store, "ioana@example.com").await;
+
+    assert_eq!(lend(store, book_id, ana).await, Some(LendOutcome::Lent));
+    assert_eq!(
+        lend(store, book_id, ana).await,
+        Some(LendOutcome::AlreadyBorrowed)
+    );
+    assert_eq!(lend(store, book_id, radu).await, Some(LendOutcome::Lent));
+    assert_eq!(
+        lend(store, book_id, ioana).await,
+        Some(LendOutcome::NoCopiesLeft)
+    );
+    assert_eq!(lend(store, BookId(book_id.0 + 1), ioana).await, None);
+
+    assert!(!store.books().await.unwrap()[0].can_be_borrowed);
+    assert_eq!(store.borrows().await.unwrap().len(), 2);
+    assert_eq!(store.count_borrowed_by(ana).await.unwrap(), 1);
+    assert_eq!(store.count_borrowed_by(ioana).await.unwrap(), 0);
+    assert_eq!(store.count_lent_copies(book_id).await.unwrap(), 2);
+
+    let mut details = book_details(
+        "Dl. Goe",
+        store.books().await.unwrap()[0].author.author_id,
+        1,
+    );
+    assert_eq!(
+        store.save_book(Some(book_id), &details).await.unwrap(),
+        SaveOutcome::CopiesLent(2)
+    );
+    assert_eq!(store.book(book_id).await.unwrap().unwrap().count, 2);
+    details.count = 2;
+    assert_eq!(
+        store.save_book(Some(book_id), &details).await.unwrap(),
+        SaveOutcome::Saved(book_id)
+    );
+
+    let borrowed = store.borrowed_by(ana).await.unwrap();
+    assert_eq!(borrowed.len(), 1);
+    assert_eq!(borrowed[0].book_id, book_id);
+    assert_eq!(borrowed[0].valid_until, days(30));
+    assert_eq!(borrowed[0].chapters_read, 0);
+
+    store.return_book(borrowed[0].borrow_id).await.unwrap();
+    assert!(store.borrowed_by(ana).await.unwrap().is_empty());
+    assert_eq!(lend(store, book_id, ioana).await, Some(LendOutcome::Lent));
+}
+
+async fn a_borrowed_book_cant_be_deleted(store: &dyn Store) {
+    let book_id = add_book(store, "Nuvele", 1).await;
+    let user_id = add_user(store, "ana@example.com").await;
+    lend(store, book_id, user_id).await;
+
+    assert!(!store.delete_book(book_id).await.unwrap());
+    assert_eq!(store.books().await.unwrap().len(), 1);
+
+    let borrow_id = store.borrowed_by(user_id).await.unwrap()[0].borrow_id;
+    store.return_book(borrow_id).await.unwrap();
+
+    assert!(store.delete_book(book_id).await.unwrap());
+    assert!(store.books().await.unwrap().is_empty());
+}
+
+async fn loans_can_be_changed(store: &dyn Store) {
+    let book_id = add_book(store, "Poezii", 1).await;
+    let user_id = add_user(store, "ana@example.com").await;
+    lend(store, book_id, user_id).await;
+    let borrow_id = store.borrowed_by(user_id).await.unwrap()[0].borrow_id;
+    assert_eq!(store.borrower(borrow_id).await.unwrap(), Some(user_id));
+    assert_eq!(
+        store.borrower(BorrowId(borrow_id.0 + 1)).await.unwrap(),
+        None
+    );
+
+    store.lengthen_borrow(borrow_id, 3).await.unwrap();
+    store.set_chapters_read(borrow_id, 7).await.unwrap();
+
+    let borrowed = &store.borrowed_by(user_id).await.unwrap()[0];
+    assert_eq!(borrowed.valid_until, days(33));
+    assert_eq!(borrowed.chapters_read, 7);
+
+    store.set_valid_until(borrow_id, days(5)).await.unwrap();
+    assert_eq!(store.borrows().await.unwrap()[0].valid_until, days(5));
+}
+
+async fn users_are_managed(store: &dyn Store) {
+    let user_id = add_user(store, "ana@example.com").await;
+    assert!(is_unique_violation(
+        store.create_user(&new_user("ana@example.com", 0)).await
+    ));
+
+    let credentials = store.credentials("ana@example.com").await.unwrap().unwrap();
+    assert_eq!(credentials.user_id, user_id);
+    assert_eq!(credentials.kind, UserKind::Reader);
+    assert_eq!(credentials.password_hash, "hash");
+    assert!(store
+        .credentials("radu@example.com")
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        store.user_id_by_email("ana@example.com").await.unwrap(),
+        Some(user_id)
+    );
+    assert_eq!(
+        store.user_kind(user_id).await.unwrap(),
+        Some(UserKind::Reader)
+    );
+    assert_eq!(store.user_kind(UserId(user_id.0 + 1)).await.unwrap(), None);
+
+    store
+        .set_user_kind(user_id, UserKind::Librarian)
+        .await
+        .unwrap();
+    store.set_password(user_id, "new hash").await.unwrap();
+    assert_eq!(
+        store.user_kind(user_id).await.unwrap(),
+        Some(UserKind::Librarian)
+    );
+    assert_eq!(
+        store
+            .credentials("ana@example.com")
+            .await
+            .unwrap()
+            .unwrap()
+            .password_hash,
+        "new hash"
+    );
+
+    let users = store.users().await.unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].email, "ana@example.com");
+    assert_eq!(users[0].registered_at, Some(days(0)));
+    assert_eq!(users[0].borrowed_book_count, 0);
+    let user = store.user(user_id).await.unwrap().unwrap();
+    assert_eq!(user.email, "ana@example.com");
+    assert_eq!(user.kind, UserKind::Librarian);
+    assert!(store.user(UserId(user_id.0 + 1)).await.unwrap().is_none());
+
+    store.delete_user(user_id).await.unwrap();
+    assert!(store.users().await.unwrap().is_empty());
+}
+
+async fn reading_lists_are_managed(store: &dyn Store) {
+    let ana = add_user(store, "ana@example.com").await;
+    let radu = add_user(store, "radu@example.com").await;
+    let first = add_book(store, "Prima", 1).await;
+    let second = add_book(store, "A doua", 1).await;
+
+    let private = store
+        .create_reading_list(ana, "De citit", false)
+        .await
+        .unwrap();
+    let public = store
+        .create_reading_list(ana, "Preferate", true)
+        .await
+        .unwrap();
+    assert!(is_unique_violation(
+        store.create_reading_list(ana, "De citit", true).await
+    ));
+    assert_eq!(store.reading_list_owner(private).await.unwrap(), Some(ana));
+    assert_eq!(store.reading_list_owner(public + 1).await.unwrap(), None);
+
+    store.add_to_reading_list(private, first).await.unwrap();
+    store.add_to_reading_list(private, second).await.unwrap();
+    store.add_to_reading_list(private, first).await.unwrap();
+    assert!(is_foreign_key_violation(
+        store
+            .add_to_reading_list(private, BookId(second.0 + 1))
+            .await
+    ));
+    assert!(store.reading_list_contains(private, first).await.unwrap());
+    assert!(!store.reading_list_contains(public, first).await.unwrap());
+
+    let lists = store.reading_lists(ana, ana).await.unwrap();
+    assert_eq!(lists.len(), 2);
+    assert_eq!(lists[0].name, "De citit");
+    assert_eq!(lists[0].book_ids, [first, second]);
+    let visible = store.reading_lists(ana, radu).await.unwrap();
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].list_id, public);
+
+    assert!(!store
+        .reorder_reading_list(private, &[second])
+        .await
+        .unwrap());
+    assert!(!store
+        .reorder_reading_list(private, &[second, second])
+        .await
+        .unwrap());
+    assert!(store
+        .reorder_reading_list(private, &[second, first])
+        .await
+        .unwrap());
+    assert_eq!(
+        store.reading_lists(ana, ana).await.unwrap()[0].book_ids,
+        [second, first]
+    );
+
+    store
+        .remove_from_reading_list(private, second)
+        .await
+        .unwrap();
+    store
+        .change_reading_list(private, "Vara", true)
+        .await
+        .unwrap();
+    assert!(is_unique_violation(
+        store.change_reading_list(private, "Preferate", true).await
+    ));
+
+    let lists = store.reading_lists(ana, radu).await.unwrap();
+    assert_eq!(lists.len(), 2);
+    assert_eq!(lists[1].name, "Vara");
+    assert_eq!(lists[1].book_ids, [first]);
+
+    store.delete_reading_list(private).await.unwrap();
+    assert_eq!(store.reading_lists(ana, ana).await.unwrap().len(), 1);
+}
+
+async fn reports_follow_the_borrow_history(store: &dyn Store) {
+    let popular = add_book(store, "Populară", 3).await;
+    let forgotten = add_book(store, "Uitată", 2).await;
+    let ana = add_user(store, "ana@example.com").await;
+    let radu = store
+        .create_user(&new_user("radu@example.com", 10 * DAY))
+        .await
+        .unwrap();
+
+    lend(store, popular, ana).await;
+    let borrow_id = store.borrowed_by(ana).await.unwrap()[0].borrow_id;
+    store.return_book(borrow_id).await.unwrap();
+    lend(store, popular, ana).await;
+    lend(store, popular, radu).await;
+
+    let (from, to) = (i64::MIN, i64::MAX);
+
+    let most_borrowed = store.most_borrowed(from, to).await.unwrap();
+    assert_eq!(most_borrowed.len(), 1);
+    assert_eq!(most_borrowed[0].book_id, popular);
+    assert_eq!(most_borrowed[0].times_borrowed, 3);
+
+    let never_borrowed = store.never_borrowed(from, to).await.unwrap();
+    assert_eq!(never_borrowed.len(), 1);
+    assert_eq!(never_borrowed[0].book_id, forgotten);
+
+    // Both loans still in progress were given a due date in 1970
+    let status = store.loan_status(from, to).await.unwrap();
+    assert_eq!((status.active, status.overdue), (0, 2));
+
+    let authors = store.author_popularity(from, to).await.unwrap();
+    assert_eq!(authors.len(), 2);
+    assert_eq!(authors[0].times_borrowed, 3);
+    assert_eq!(authors[0].distinct_readers, 2);
+    assert_eq!(authors[1].times_borrowed, 0);
+
+    let registrations = store.registrations(from, to).await.unwrap();
+    assert_eq!(registrations.len(), 2);
+    assert_eq!(registrations[0].day, "1970-01-01");
+    assert_eq!(registrations[1].day, "1970-01-11");
+    assert_eq!(registrations[1].registrations, 1);
+
+    let utilisation = store.utilisation(from, to).await.unwrap();
+    assert_eq!(utilisation.len(), 2);
+    assert_eq!(utilisation[0].book_id, popular);
+    assert_eq!(utilisation[0].borrowed, 2);
+    assert_eq!(utilisation[1].borrowed, 0);
+
+    assert!(store.most_borrowed(0, 1).await.unwrap().is_empty());
+}
+
+async fn recommendations_come_from_similarities(store: &dyn Store) {
+    let first = add_book(store, "Prima", 5).await;
+    let second = add_book(store, "A doua", 5).await;
+    let third = add_book(store, "A treia", 5).await;
+    let ana = add_user(store, "ana@example.com").await;
+    let radu = add_user(store, "radu@example.com").await;
+
+    lend(store, first, ana).await;
+    lend(store, first, radu).await;
+    lend(store, second, radu).await;
+
+    let inputs = store.similarity_inputs().await.unwrap();
+    assert_eq!(inputs.books.len(), 3);
+    assert_eq!(inputs.readers.len(), 3);
+
+    store
+        .replace_similarities(&[
+            (first, second, 0.75),
+            (first, third, 0.5),
+            (second, first, 0.75),
+        ])
+        .await
+        .unwrap();
+
+    let similar = store.similar_books(first).await.unwrap();
+    assert_eq!(
+        similar.iter().map(|book| book.book_id).collect::<Vec<_>>(),
+        [second, third]
+    );
+    assert_eq!(similar[0].score, 0.75);
+
+    let recommended = store.recommendations(ana, 10).await.unwrap();
+    assert_eq!(
+        recommended
+            .iter()
+            .map(|book| book.book_id)
+            .collect::<Vec<_>>(),
+        [second, third]
+    );
+    assert_eq!(store.recommendations(ana, 1).await.unwrap().len(), 1);
+
+    let popular = store.popular_books(radu, 1).await.unwrap();
+    assert_eq!(popular[0].book_id, third);
+    let popular = store.popular_books(ana, 1).await.unwrap();
+    assert_eq!(popular[0].book_id, second);
+    assert_eq!(popular[0].score, 1.0);
+    let popular = store.popular_books(ana, 10).await.unwrap();
+    assert!(popular.iter().all(|book| book.book_id != first));
+}
+
+async fn fixtures_are_loaded_and_reset(store: &dyn Store) {
+    assert!(store.is_empty().await.unwrap());
+
+    store.load_demo().await.unwrap();
+    assert!(!store.is_empty().await.unwrap());
+    let books = store.books().await.unwrap();
+    assert!(books
+        .iter()
+        .any(|book| book.title == "Dl. Goe" && book.genres == ["Umor"]));
+    // The demo authors come with their ids, new ones must still get fresh ids
+    store.add_author(&new_author("Nou")).await.unwrap();
+
+    store.reset().await.unwrap();
+    assert!(store.is_empty().await.unwrap());
+
+    let mut reader = new_user("ana@example.com", 0);
+    let mut librarian = new_user("bibliotecar@example.com", 0);
+    librarian.kind = UserKind::Librarian;
+    reader.registered_at = days(1);
+    let library = Library {
+        authors: vec![new_author("Autor")],
+        books: vec![LibraryBook {
+            author: 0,
+            details: BookDetails {
+                genres: vec!["Roman".into()],
+                ..book_details("Generată", AuthorId::default(), 2)
+            },
+        }],
+        users: vec![librarian, reader],
+        loans: vec![
+            LibraryLoan {
+                book: 0,
+                user: 1,
+                borrowed_at: DAY,
+                returned_at: Some(2 * DAY),
+                valid_until: 31 * DAY,
+                chapters_read: 0,
+            },
+            LibraryLoan {
+                book: 0,
+                user: 1,
+                borrowed_at: 3 * DAY,
+                returned_at: None,
+                valid_until: 33 * DAY,
+                chapters_read: 4,
+            },
+        ],
+    };
+    store.load_library(&library).await.unwrap();
+
+    let books = store.books().await.unwrap();
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].genres, ["Roman"]);
+    let reader_id = store
+        .user_id_by_email("ana@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    let borrowed = store.borrowed_by(reader_id).await.unwrap();
+    assert_eq!(borrowed.len(), 1);
+    assert_eq!(borrowed[0].chapters_read, 4);
+    assert_eq!(store.most_borrowed(0, 2 * DAY).await.unwrap().len(), 1);
+    assert_eq!(
+        store.most_borrowed(0, i64::MAX).await.unwrap()[0].times_borrowed,
+        2
+    );
+}
+
+async fn migrations_are_recorded(store: &dyn Store) {
+    assert!(!store.applied_migrations().await.unwrap().is_empty());
+    assert!(store.check_integrity().await.unwrap().is_empty());
+}
+
+async fn idempotency_keys_expire(store: &dyn Store) {
+    let claim = |key: &'static str, now: i64| async move {
+        store
+            .claim_idempotency_key(key, "borrow", now, now - DAY)
+            .await
+            .unwrap()
+    };
+
+    assert!(claim("a", 0).await.is_none());
+    let pending = claim("a", 10).await.unwrap();
+    assert_eq!(pending.fingerprint, "borrow");
+    assert!(pending.response.is_none());
+
+    let response = StoredResponse {
+        status: 200,
+        content_type: Some("application/json".into()),
+        body: b"{}".to_vec(),
+    };
+    store
+        .save_idempotent_response("a", &response)
+        .await
+        .unwrap();
+    let done = claim("a", 20).await.unwrap().response.unwrap();
+    assert_eq!(done.status, 200);
+    assert_eq!(done.content_type.as_deref(), Some("application/json"));
+    assert_eq!(done.body, b"{}");
+
+    assert!(claim("b", 0).await.is_none());
+    store.release_idempotency_key("b").await.unwrap();
+    assert!(claim("b", 0).await.is_none());
+
+    // Older than a day, the key is free again
+    assert!(claim("a", 2 * DAY).await.is_none());
+
+    assert_eq!(store.delete_expired_idempotency_keys(DAY).await.unwrap(), 1);
+    assert!(claim("b", 3 * DAY).await.is_none());
+}
+
+/// Many readers asking for the last copy at once, from as many connections as the pool allows
+async fn the_last_copy_is_lent_once(store: Arc<dyn Store>) {
+    const READERS: usize = 32;
+
+    let book_id = add_book(&*store, "Moromeții", 1).await;
+    let mut readers = vec![];
+    for index in 0..READERS {
+        readers.push(add_user(&*store, &format!("cititor{index}@example.com")).await);
+    }
+
+    let tasks = readers
+        .into_iter()
+        .map(|user_id| {
+            let store = store.clone();
+            tokio::spawn(async move { store.lend_book(book_id, user_id, None, days(1)).await })
+        })
+        .collect::<Vec<_>>();
+
+    let mut outcomes = vec![];
+    for task in tasks {
+        outcomes.push(task.await.unwrap().unwrap());
+    }
+
+    let lent = outcomes
+        .iter()
+        .filter(|outcome| **outcome == Some(LendOutcome::Lent))
+        .count();
+    assert_eq!(lent, 1, "{outcomes:?}");
+    assert!(outcomes
+        .iter()
+        .all(|outcome| outcome.is_some_and(|outcome| outcome != LendOutcome::AlreadyBorrowed)));
+    assert_eq!(store.borrows().await.unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn sqlite_lends_the_last_copy_once() {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "biblioteca-store-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let store = SqliteStore::open(&dir.join("data.sqlite")).await.unwrap();
+    the_last_copy_is_lent_once(Arc::new(store)).await;
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn postgres_lends_the_last_copy_once() {
+    let Some(database) = TestDatabase::create().await else {
+        eprintln!("{POSTGRES_URL_VAR} isn't set, skipping");
+        return;
+    };
+    the_last_copy_is_lent_once(Arc::new(database.store.clone())).await;
+    database.drop().await;
+}
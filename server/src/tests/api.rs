@@ -0,0 +1,391 @@
+use axum::http::{header, Method, StatusCode};
+use schema::{
+    auth::{CreateAccount, GetAllUsersReply, LoginReply, UserChanges},
+    books::{
+        Author, AuthorFields, Book, BookFields, BorrowedBook, BorrowedByReply, BorrowsReply,
+        LoanChanges, NewLoan,
+    },
+    dates::PartialDate,
+    errors::ErrorCode,
+    ids::{BookId, UserId, UserKind},
+    lists::{ReadingList, ReadingListFields, ReadingListsReply},
+    session::Cookie,
+};
+
+use super::{TestServer, DAY};
+
+fn new_loan(book_id: BookId) -> NewLoan {
+    NewLoan {
+        book_id,
+        ..NewLoan::default()
+    }
+}
+
+#[tokio::test]
+async fn requests_need_an_authorization_header() {
+    let server = TestServer::new().await;
+
+    let reply = server.get("/api/v1/loans").await;
+    assert_eq!(
+        reply.error(StatusCode::UNAUTHORIZED),
+        "Missing or malformed Authorization header"
+    );
+
+    let stranger = Cookie {
+        id: UserId(1000),
+        password: String::new(),
+    };
+    let reply = server.api(Method::GET, "/loans", &stranger, ()).await;
+    reply.error(StatusCode::UNAUTHORIZED);
+
+    // Knowing a librarian's id isn't enough to act on their behalf
+    let librarian = server.librarian().await;
+    let impostor = Cookie {
+        password: "ghicită".into(),
+        ..librarian.clone()
+    };
+    let reply = server.api(Method::GET, "/loans", &impostor, ()).await;
+    assert_eq!(
+        reply.error_code(StatusCode::UNAUTHORIZED),
+        ErrorCode::WrongPassword
+    );
+    let reply = server.api(Method::GET, "/loans", &librarian, ()).await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    // The catalogue is public
+    let books: Vec<Book> = server.get("/api/v1/books").await.ok();
+    assert!(books.is_empty());
+}
+
+#[tokio::test]
+async fn librarians_manage_books_and_authors() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+
+    let fields = AuthorFields {
+        name: "Mihail Sadoveanu".into(),
+        date_of_birth: PartialDate::from_ymd(1880, 11, 5).unwrap(),
+        date_of_death: PartialDate::from_year(1961),
+        description: String::new(),
+    };
+    let reply = server.api(Method::POST, "/authors", &reader, &fields).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let author: Author = server
+        .api(Method::POST, "/authors", &librarian, &fields)
+        .await
+        .created();
+
+    let mut fields = BookFields {
+        title: "Baltagul".into(),
+        author_id: author.author_id,
+        publish_date: PartialDate::from_year(1930).unwrap().circa(),
+        publisher: "Polirom".into(),
+        count: 2,
+        synopsis: String::new(),
+        series: None,
+        genres: vec!["Roman".into()],
+    };
+    let book: Book = server
+        .api(Method::POST, "/books", &librarian, &fields)
+        .await
+        .created();
+    assert_eq!(book.author.name, "Mihail Sadoveanu");
+    assert_eq!(book.author.date_of_death, author.date_of_death);
+    assert_eq!(book.genres, ["Roman"]);
+
+    fields.count = 3;
+    let uri = format!("/books/{}", book.book_id);
+    let updated: Book = server
+        .api(Method::PUT, &uri, &librarian, &fields)
+        .await
+        .ok();
+    assert_eq!(updated.count, 3);
+
+    let fetched: Book = server.get(&format!("/api/v1{uri}")).await.ok();
+    assert_eq!(fetched.count, 3);
+    let fetched: serde_json::Value = server.get(&format!("/api/v1{uri}")).await.ok();
+    assert_eq!(fetched["publish_date"], "1930~");
+    assert_eq!(fetched["author"]["date_of_birth"], "1880-11-05");
+
+    let reply = server
+        .api(Method::PUT, "/books/1000", &librarian, &fields)
+        .await;
+    assert_eq!(reply.error(StatusCode::NOT_FOUND), "No book with given id");
+
+    let reply = server.api(Method::DELETE, &uri, &reader, ()).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let reply = server.api(Method::DELETE, &uri, &librarian, ()).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+    server
+        .get(&format!("/api/v1{uri}"))
+        .await
+        .error(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn loans_are_made_changed_and_returned() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let radu = server.reader("radu@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+
+    let loan: BorrowedBook = server
+        .api(Method::POST, "/loans", &ana, new_loan(book_id))
+        .await
+        .created();
+    assert_eq!(loan.book_id, book_id);
+
+    let reply = server
+        .api(Method::POST, "/loans", &ana, new_loan(book_id))
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::CONFLICT),
+        "The book is already borrowed by this reader"
+    );
+    server
+        .api(Method::POST, "/loans", &ion, new_loan(book_id))
+        .await
+        .created::<BorrowedBook>();
+    let reply = server
+        .api(Method::POST, "/loans", &radu, new_loan(book_id))
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::CONFLICT),
+        "Every copy of the book is already borrowed"
+    );
+
+    let uri = format!("/loans/{}", loan.borrow_id);
+    let changes = LoanChanges {
+        chapters_read: Some(3),
+        ..LoanChanges::default()
+    };
+    let reply = server.api(Method::PATCH, &uri, &ion, &changes).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let reply = server.api(Method::PATCH, &uri, &ana, &changes).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+
+    let changes = LoanChanges {
+        lengthen_by_days: Some(2),
+        ..LoanChanges::default()
+    };
+    let reply = server.api(Method::PATCH, &uri, &ana, &changes).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let reply = server.api(Method::PATCH, &uri, &librarian, &changes).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+
+    // Nothing changes when one of the changes is refused
+    let changes = LoanChanges {
+        lengthen_by_days: Some(2),
+        chapters_read: Some(7),
+        ..LoanChanges::default()
+    };
+    let reply = server.api(Method::PATCH, &uri, &ana, &changes).await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let borrowed: BorrowedByReply = server
+        .api(Method::GET, &format!("/users/{}/loans", ana.id), &ana, ())
+        .await
+        .ok();
+    assert_eq!(borrowed[0].chapters_read, 3);
+    assert_eq!(
+        borrowed[0].valid_until.unix(),
+        loan.valid_until.unix() + 2 * DAY
+    );
+
+    let reply = server
+        .api(Method::GET, &format!("/users/{}/loans", ana.id), &ion, ())
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+    let loans: BorrowsReply = server.api(Method::GET, "/loans", &librarian, ()).await.ok();
+    assert_eq!(loans.len(), 2);
+
+    let reply = server.api(Method::DELETE, &uri, &ion, ()).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let reply = server.api(Method::DELETE, &uri, &ana, ()).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+    let reply = server.api(Method::DELETE, &uri, &ana, ()).await;
+    assert_eq!(
+        reply.error(StatusCode::NOT_FOUND),
+        "No borrow with given id"
+    );
+
+    let checkout = NewLoan {
+        user_id: Some(radu.id),
+        ..new_loan(book_id)
+    };
+    let reply = server.api(Method::POST, "/loans", &ana, &checkout).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let loan: BorrowedBook = server
+        .api(Method::POST, "/loans", &librarian, &checkout)
+        .await
+        .created();
+    assert_eq!(
+        server.borrowed_by(radu.id).await[0].borrow_id,
+        loan.borrow_id
+    );
+}
+
+#[tokio::test]
+async fn reading_lists_are_resources() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let first = server.book("Moara cu noroc", 1).await;
+    let second = server.book("Enigma Otiliei", 1).await;
+
+    let fields = ReadingListFields {
+        name: "De citit".into(),
+        is_public: false,
+    };
+    let list: ReadingList = server
+        .api(Method::POST, "/reading-lists", &ana, &fields)
+        .await
+        .created();
+    let uri = format!("/reading-lists/{}", list.list_id);
+
+    for book_id in [first, second] {
+        let reply = server
+            .api(Method::PUT, &format!("{uri}/books/{book_id}"), &ana, ())
+            .await;
+        assert_eq!(reply.status, StatusCode::NO_CONTENT);
+    }
+    let reply = server
+        .api(Method::PUT, &format!("{uri}/books/{first}"), &ion, ())
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .api(Method::PUT, &format!("{uri}/books"), &ana, [second, first])
+        .await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+
+    let lists_uri = format!("/users/{}/reading-lists", ana.id);
+    let lists: ReadingListsReply = server.api(Method::GET, &lists_uri, &ana, ()).await.ok();
+    assert_eq!(lists[0].book_ids, [second, first]);
+    let lists: ReadingListsReply = server.api(Method::GET, &lists_uri, &ion, ()).await.ok();
+    assert!(lists.is_empty());
+
+    let borrow = NewLoan {
+        reading_list_id: Some(list.list_id),
+        ..new_loan(first)
+    };
+    server
+        .api(Method::POST, "/loans", &ana, borrow)
+        .await
+        .created::<BorrowedBook>();
+    let lists: ReadingListsReply = server.api(Method::GET, &lists_uri, &ana, ()).await.ok();
+    assert_eq!(lists[0].book_ids, [second]);
+
+    let reply = server.api(Method::DELETE, &uri, &ana, ()).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+    let reply = server.api(Method::DELETE, &uri, &ana, ()).await;
+    assert_eq!(
+        reply.error(StatusCode::NOT_FOUND),
+        "No reading list with given id"
+    );
+}
+
+#[tokio::test]
+async fn users_are_resources() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+
+    let account = CreateAccount {
+        name: "Ana Popescu".into(),
+        email: "ana@example.com".into(),
+        password: "parola".into(),
+    };
+    let created: LoginReply = server.post("/api/v1/users", &account).await.created();
+    let ana = Cookie {
+        id: created.id,
+        password: "parola".into(),
+    };
+
+    let uri = format!("/users/{}", ana.id);
+    let promotion = UserChanges {
+        kind: UserKind::Librarian,
+    };
+    let reply = server.api(Method::PATCH, &uri, &ana, &promotion).await;
+    reply.error(StatusCode::FORBIDDEN);
+    let reply = server
+        .api(Method::PATCH, &uri, &librarian, &promotion)
+        .await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+
+    let users: GetAllUsersReply = server.api(Method::GET, "/users", &ana, ()).await.ok();
+    assert!(users.iter().all(|user| user.kind == UserKind::Librarian));
+
+    let reply = server.api(Method::DELETE, &uri, &ana, ()).await;
+    assert_eq!(
+        reply.error(StatusCode::CONFLICT),
+        "You can't delete your own account"
+    );
+    let reply = server.api(Method::DELETE, &uri, &librarian, ()).await;
+    assert_eq!(reply.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn errors_carry_a_code() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let radu = server.reader("radu@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+
+    server
+        .api(Method::POST, "/loans", &ana, new_loan(book_id))
+        .await
+        .created::<BorrowedBook>();
+    let reply = server
+        .api(Method::POST, "/loans", &ana, new_loan(book_id))
+        .await;
+    assert_eq!(
+        reply.error_code(StatusCode::CONFLICT),
+        ErrorCode::AlreadyBorrowed
+    );
+    server
+        .api(Method::POST, "/loans", &ion, new_loan(book_id))
+        .await
+        .created::<BorrowedBook>();
+    let reply = server
+        .api(Method::POST, "/loans", &radu, new_loan(book_id))
+        .await;
+    assert_eq!(
+        reply.error_code(StatusCode::CONFLICT),
+        ErrorCode::NoCopiesLeft
+    );
+
+    let reply = server.api(Method::DELETE, "/books/1000", &ana, ()).await;
+    assert_eq!(
+        reply.error_code(StatusCode::FORBIDDEN),
+        ErrorCode::NotLibrarian
+    );
+    let reply = server.get("/api/v1/loans").await;
+    assert_eq!(
+        reply.error_code(StatusCode::UNAUTHORIZED),
+        ErrorCode::MissingAuthorization
+    );
+
+    // Errors without a code of their own get one after their status
+    let reply = server.get("/no-such-route").await;
+    assert_eq!(reply.error_code(StatusCode::NOT_FOUND), ErrorCode::NotFound);
+}
+
+#[tokio::test]
+async fn legacy_routes_point_to_their_successor() {
+    let server = TestServer::new().await;
+
+    let reply = server.get("/books").await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.headers["Deprecation"], "true");
+    assert_eq!(
+        reply.headers[header::LINK],
+        "</api/v1>; rel=\"successor-version\""
+    );
+
+    let reply = server.get("/api/v1/books").await;
+    assert!(!reply.headers.contains_key("Deprecation"));
+}
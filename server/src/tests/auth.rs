@@ -0,0 +1,200 @@
+use axum::http::StatusCode;
+use schema::{
+    auth::{
+        CreateAccount, DeleteUserReply, DeleteUserRequest, GetAllUsersReply, GetAllUsersRequest,
+        Login, LoginReply, PromoteUserRequest,
+    },
+    ids::{UserId, UserKind},
+    session::Cookie,
+};
+
+use super::TestServer;
+
+fn account(email: &str, password: &str) -> CreateAccount {
+    CreateAccount {
+        name: "Ana Popescu".into(),
+        email: email.into(),
+        password: password.into(),
+    }
+}
+
+fn login(email: &str, password: &str) -> Login {
+    Login {
+        email: email.into(),
+        password: password.into(),
+    }
+}
+
+fn all_users(cookie: &Cookie) -> GetAllUsersRequest {
+    GetAllUsersRequest {
+        cookie: cookie.clone(),
+    }
+}
+
+fn delete_user(cookie: &Cookie, user_id: UserId) -> DeleteUserRequest {
+    DeleteUserRequest {
+        user_to_be_deleted: user_id,
+        cookie: cookie.clone(),
+    }
+}
+
+#[tokio::test]
+async fn accounts_are_created_and_logged_into() {
+    let server = TestServer::new().await;
+
+    let created: LoginReply = server
+        .post("/auth/create-account", account("ana@example.com", "parola"))
+        .await
+        .ok();
+    assert_eq!(created.kind, UserKind::Reader);
+
+    let logged_in: LoginReply = server
+        .post("/auth/login", login("ana@example.com", "parola"))
+        .await
+        .ok();
+    assert_eq!(logged_in.id, created.id);
+    assert_eq!(logged_in.kind, UserKind::Reader);
+
+    let reply = server
+        .post("/auth/login", login("ana@example.com", "gresita"))
+        .await;
+    assert_eq!(reply.error(StatusCode::UNAUTHORIZED), "Wrong password");
+
+    let reply = server
+        .post("/auth/login", login("ion@example.com", "parola"))
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::NOT_FOUND),
+        "No account with given email"
+    );
+}
+
+#[tokio::test]
+async fn accounts_need_every_detail_and_a_new_email() {
+    let server = TestServer::new().await;
+
+    let reply = server
+        .post("/auth/create-account", account("", "parola"))
+        .await;
+    assert_eq!(reply.invalid_fields(), ["email"]);
+
+    let reply = server
+        .post("/auth/create-account", account("ana@example.com", " "))
+        .await;
+    assert_eq!(reply.invalid_fields(), ["password"]);
+
+    let reply = server
+        .post("/auth/create-account", account("ana", "parola"))
+        .await;
+    assert_eq!(reply.invalid_fields(), ["email"]);
+
+    server
+        .post("/auth/create-account", account("ana@example.com", "parola"))
+        .await
+        .ok::<LoginReply>();
+    let reply = server
+        .post("/auth/create-account", account("ana@example.com", "alta"))
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::CONFLICT),
+        "An account with this email already exists"
+    );
+}
+
+#[tokio::test]
+async fn only_librarians_see_all_users() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Ion", 1).await;
+    server.borrow(&reader, book_id).await;
+
+    let users: GetAllUsersReply = server
+        .post("/auth/all-users", all_users(&librarian))
+        .await
+        .ok();
+    assert_eq!(users.len(), 2);
+    let ana = users.iter().find(|user| user.id == reader.id).unwrap();
+    assert_eq!(ana.email, "ana@example.com");
+    assert_eq!(ana.kind, UserKind::Reader);
+    assert_eq!(ana.borrowed_book_count, 1);
+
+    let reply = server.post("/auth/all-users", all_users(&reader)).await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let stranger = Cookie {
+        id: UserId(1000),
+        password: String::new(),
+    };
+    let reply = server.post("/auth/all-users", all_users(&stranger)).await;
+    reply.error(StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn librarians_promote_readers() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let other_reader = server.reader("ion@example.com").await;
+
+    let request = PromoteUserRequest {
+        user_to_be_promoted: other_reader.id,
+        cookie: reader.clone(),
+    };
+    let reply = server.post("/auth/promote-user", request).await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let request = PromoteUserRequest {
+        user_to_be_promoted: reader.id,
+        cookie: librarian,
+    };
+    let reply = server.post("/auth/promote-user", request).await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let users: GetAllUsersReply = server
+        .post("/auth/all-users", all_users(&reader))
+        .await
+        .ok();
+    let promoted = users.iter().find(|user| user.id == reader.id).unwrap();
+    assert_eq!(promoted.kind, UserKind::Librarian);
+}
+
+#[tokio::test]
+async fn deleting_users_replies_with_the_outcome() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Ion", 1).await;
+    let borrow_id = server.borrow(&reader, book_id).await;
+
+    let reply = server
+        .post("/auth/delete-user", delete_user(&librarian, librarian.id))
+        .await;
+    assert!(matches!(reply.ok(), DeleteUserReply::CannotDeleteSelf));
+
+    let reply = server
+        .post("/auth/delete-user", delete_user(&librarian, reader.id))
+        .await;
+    assert!(matches!(reply.ok(), DeleteUserReply::UsersStillHadBooks));
+
+    let reply = server
+        .post(&format!("/return-book/{borrow_id}"), &reader)
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let reply = server
+        .post("/auth/delete-user", delete_user(&reader, librarian.id))
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post("/auth/delete-user", delete_user(&librarian, reader.id))
+        .await;
+    assert!(matches!(reply.ok(), DeleteUserReply::Ok));
+
+    let users: GetAllUsersReply = server
+        .post("/auth/all-users", all_users(&librarian))
+        .await
+        .ok();
+    assert!(users.iter().all(|user| user.id != reader.id));
+}
@@ -0,0 +1,57 @@
+use axum::http::StatusCode;
+use schema::{
+    backups::{Backup, BackupRequest, BackupsReply},
+    session::Cookie,
+};
+
+use super::TestServer;
+use crate::{
+    backup::verify_checksum,
+    store::{SqliteStore, Store},
+};
+
+fn request(cookie: &Cookie) -> BackupRequest {
+    BackupRequest {
+        cookie: cookie.clone(),
+    }
+}
+
+#[tokio::test]
+async fn librarians_make_and_list_backups() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    server.book("Amintiri din copilărie", 1).await;
+
+    let backups: BackupsReply = server.post("/backups", request(&librarian)).await.ok();
+    assert!(backups.is_empty());
+
+    let backup: Backup = server
+        .post("/create-backup", request(&librarian))
+        .await
+        .ok();
+    let path = server.dir.join("backups").join(&backup.name);
+    assert!(verify_checksum(&path).await.unwrap());
+
+    let copy = SqliteStore::open_read_only(&path).await.unwrap();
+    assert_eq!(copy.books().await.unwrap().len(), 1);
+
+    let backups: BackupsReply = server.post("/backups", request(&librarian)).await.ok();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].name, backup.name);
+    assert_eq!(backups[0].sha256, backup.sha256);
+    assert_eq!(backups[0].size, backup.size);
+}
+
+#[tokio::test]
+async fn backups_are_only_for_librarians() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+
+    for uri in ["/backups", "/create-backup"] {
+        server
+            .post(uri, request(&reader))
+            .await
+            .error(StatusCode::FORBIDDEN);
+    }
+    assert!(!server.dir.join("backups").exists());
+}
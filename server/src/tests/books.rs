@@ -0,0 +1,583 @@
+use axum::http::StatusCode;
+use schema::{
+    books::{
+        Author, Book, BorrowReply, BorrowRequest, BorrowsReply, BorrowsRequest,
+        ChangeAuthorDetailsRequest, ChangeBookDetailsRequest, CheckoutReply, CheckoutRequest,
+        RecommendationsReply, RecommendationsRequest, SimilarBooksReply,
+    },
+    dates::{PartialDate, Timestamp},
+    endpoints::{Books, EndBorrow, LengthenBorrow, ReturnBook, UpdateChaptersRead},
+    ids::{AuthorId, BookId, UserId},
+    session::Cookie,
+};
+
+use super::{TestServer, DAY};
+use crate::recommendations::refresh_similarities;
+
+fn book_request(
+    cookie: &Cookie,
+    book_id: Option<BookId>,
+    author_id: AuthorId,
+) -> ChangeBookDetailsRequest {
+    ChangeBookDetailsRequest {
+        book_id,
+        title: "Maitreyi".into(),
+        author_id,
+        publish_date: PartialDate::from_year(1933).unwrap(),
+        publisher: "Humanitas".into(),
+        count: 2,
+        synopsis: "Un roman de dragoste".into(),
+        series: None,
+        genres: vec!["Roman".into()],
+        cookie: cookie.clone(),
+    }
+}
+
+fn author_request(cookie: &Cookie, author_id: Option<AuthorId>) -> ChangeAuthorDetailsRequest {
+    ChangeAuthorDetailsRequest {
+        author_id,
+        name: "Mircea Eliade".into(),
+        date_of_birth: PartialDate::from_ymd(1907, 3, 13).unwrap(),
+        date_of_death: PartialDate::from_ymd(1986, 4, 22),
+        description: "Istoric al religiilor și scriitor".into(),
+        cookie: cookie.clone(),
+    }
+}
+
+fn checkout(cookie: &Cookie, book_id: BookId, user_id: UserId) -> CheckoutRequest {
+    CheckoutRequest {
+        user_id,
+        book_id,
+        cookie: cookie.clone(),
+    }
+}
+
+async fn books(server: &TestServer) -> Vec<Book> {
+    server.call(&Books).await.ok()
+}
+
+async fn borrows(server: &TestServer, cookie: &Cookie) -> BorrowsReply {
+    let request = BorrowsRequest {
+        cookie: cookie.clone(),
+    };
+    server.post("/borrows", request).await.ok()
+}
+
+#[tokio::test]
+async fn librarians_edit_the_catalog() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+
+    let reply = server
+        .post("/change-author-details", author_request(&reader, None))
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post("/change-author-details", author_request(&librarian, None))
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let authors: Vec<Author> = server.get("/authors").await.ok();
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "Mircea Eliade");
+    let author_id = authors[0].author_id;
+
+    let reply = server
+        .post(
+            "/change-author-details",
+            author_request(&librarian, Some(author_id)),
+        )
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::BAD_REQUEST),
+        "Authors can't be edited"
+    );
+
+    let reply = server
+        .post(
+            "/change-book-details",
+            book_request(&reader, None, author_id),
+        )
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post(
+            "/change-book-details",
+            book_request(&librarian, None, author_id),
+        )
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let book = books(&server).await.remove(0);
+    assert_eq!(book.title, "Maitreyi");
+    assert_eq!(book.author.name, "Mircea Eliade");
+    assert_eq!(book.count, 2);
+    assert!(book.can_be_borrowed);
+    assert_eq!(book.genres, ["Roman"]);
+
+    let request = ChangeBookDetailsRequest {
+        count: 5,
+        series: Some(" Proză ".into()),
+        genres: vec!["Roman".into(), " Autobiografic ".into()],
+        ..book_request(&librarian, Some(book.book_id), author_id)
+    };
+    let reply = server.post("/change-book-details", request).await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let books = books(&server).await;
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].count, 5);
+    assert_eq!(books[0].series.as_deref(), Some("Proză"));
+    let mut genres = books[0].genres.clone();
+    genres.sort();
+    assert_eq!(genres, ["Autobiografic", "Roman"]);
+
+    // Clients from before series and genres can leave them out
+    let mut request = serde_json::to_value(book_request(&librarian, None, author_id)).unwrap();
+    request.as_object_mut().unwrap().remove("series");
+    request.as_object_mut().unwrap().remove("genres");
+    let reply = server.post("/change-book-details", request).await;
+    assert_eq!(reply.status, StatusCode::OK, "{}", reply.body);
+    let all: Vec<Book> = server.get("/books").await.ok();
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().any(|book| book.genres.is_empty()));
+}
+
+#[tokio::test]
+async fn book_and_author_details_are_validated() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+    let author_id = books(&server).await[0].author.author_id;
+
+    let request = ChangeAuthorDetailsRequest {
+        name: " ".into(),
+        date_of_birth: PartialDate::from_ymd(1907, 3, 13).unwrap(),
+        date_of_death: PartialDate::from_year(1906),
+        ..author_request(&librarian, None)
+    };
+    let reply = server.post("/change-author-details", request).await;
+    assert_eq!(reply.invalid_fields(), ["name", "date_of_death"]);
+
+    let request = ChangeBookDetailsRequest {
+        title: String::new(),
+        count: -1,
+        ..book_request(&librarian, None, author_id)
+    };
+    let reply = server.post("/change-book-details", request).await;
+    assert_eq!(reply.invalid_fields(), ["title", "count"]);
+
+    // Copies that are lent out can't be taken off the catalog
+    server.borrow(&reader, book_id).await;
+    let request = ChangeBookDetailsRequest {
+        count: 0,
+        ..book_request(&librarian, Some(book_id), author_id)
+    };
+    let reply = server.post("/change-book-details", request).await;
+    assert_eq!(reply.invalid_fields(), ["count"]);
+
+    let request = ChangeBookDetailsRequest {
+        count: 1,
+        ..book_request(&librarian, Some(book_id), author_id)
+    };
+    let reply = server.post("/change-book-details", request).await;
+    assert_eq!(reply.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn readers_borrow_books() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+
+    let borrow_id = server.borrow(&reader, book_id).await;
+
+    let request = BorrowRequest {
+        cookie: reader.clone(),
+        book_id,
+    };
+    let reply: BorrowReply = server.post("/borrow", request).await.ok();
+    assert!(reply.already_borrowed);
+
+    let ion = server.reader("ion@example.com").await;
+    server.borrow(&ion, book_id).await;
+
+    let request = BorrowRequest {
+        cookie: server.reader("maria@example.com").await,
+        book_id,
+    };
+    let reply: BorrowReply = server.post("/borrow", request).await.ok();
+    assert!(reply.no_copies_left);
+
+    let borrowed = server.borrowed_by(reader.id).await;
+    assert_eq!(borrowed.len(), 1);
+    assert_eq!(borrowed[0].borrow_id, borrow_id);
+    assert_eq!(borrowed[0].chapters_read, 0);
+    assert!(borrowed[0].valid_until > Timestamp::now());
+
+    let request = BorrowRequest {
+        cookie: reader.clone(),
+        book_id: BookId(1000),
+    };
+    let reply = server.post("/borrow", request).await;
+    assert_eq!(reply.error(StatusCode::NOT_FOUND), "No book with given id");
+}
+
+#[tokio::test]
+async fn librarians_check_books_out() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let other_reader = server.reader("ion@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+
+    let reply = server
+        .post("/checkout", checkout(&reader, book_id, other_reader.id))
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post("/checkout", checkout(&librarian, book_id, UserId(1000)))
+        .await;
+    assert_eq!(reply.error(StatusCode::NOT_FOUND), "No user with given id");
+
+    let reply = server
+        .post("/checkout", checkout(&librarian, book_id, reader.id))
+        .await;
+    assert_eq!(reply.ok::<CheckoutReply>(), CheckoutReply::Ok);
+
+    let reply = server
+        .post("/checkout", checkout(&librarian, book_id, other_reader.id))
+        .await;
+    assert_eq!(reply.ok::<CheckoutReply>(), CheckoutReply::NoCopiesLeft);
+
+    let borrows = borrows(&server, &librarian).await;
+    assert_eq!(borrows.len(), 1);
+    assert_eq!(borrows[0].user_id, reader.id);
+    assert_eq!(borrows[0].book_id, book_id);
+
+    let request = BorrowsRequest { cookie: reader };
+    let reply = server.post("/borrows", request).await;
+    reply.error(StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn a_book_is_only_checked_out_once_to_a_reader() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+
+    server.borrow(&reader, book_id).await;
+
+    let reply = server
+        .post("/checkout", checkout(&librarian, book_id, reader.id))
+        .await;
+    assert_eq!(reply.ok::<CheckoutReply>(), CheckoutReply::AlreadyBorrowed);
+}
+
+#[tokio::test]
+async fn librarians_lengthen_and_end_loans() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+    let borrow_id = server.borrow(&reader, book_id).await;
+    let due = server.borrowed_by(reader.id).await[0].valid_until;
+
+    let reply = server
+        .post(&format!("/lengthen-borrow/{borrow_id}?days=7"), &reader)
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post(&format!("/lengthen-borrow/{borrow_id}"), &librarian)
+        .await;
+    reply.error(StatusCode::BAD_REQUEST);
+
+    let reply = server
+        .post(
+            &format!("/lengthen-borrow/{borrow_id}?days=-30"),
+            &librarian,
+        )
+        .await;
+    assert_eq!(reply.invalid_fields(), ["days"]);
+
+    let reply = server
+        .post(
+            &format!("/lengthen-borrow/{borrow_id}?days=1000"),
+            &librarian,
+        )
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::BAD_REQUEST),
+        "A borrow can be lengthened by at most 90 days at once"
+    );
+
+    let request = LengthenBorrow {
+        borrow_id,
+        days: 7,
+        cookie: librarian.clone(),
+    };
+    assert_eq!(server.call(&request).await.status, StatusCode::OK);
+    assert_eq!(
+        server.borrowed_by(reader.id).await[0].valid_until.unix(),
+        due.unix() + 7 * DAY
+    );
+
+    let reply = server
+        .post(&format!("/end-borrow/{borrow_id}"), &reader)
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let request = EndBorrow {
+        borrow_id,
+        cookie: librarian.clone(),
+    };
+    assert_eq!(server.call(&request).await.status, StatusCode::OK);
+    assert!(borrows(&server, &librarian).await[0].valid_until <= Timestamp::now());
+}
+
+#[tokio::test]
+async fn readers_track_and_return_their_books() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+    let borrow_id = server.borrow(&reader, book_id).await;
+
+    let reply = server
+        .post(
+            &format!("/update-borrow-chapters-read/{borrow_id}"),
+            &reader,
+        )
+        .await;
+    reply.error(StatusCode::BAD_REQUEST);
+
+    let reply = server
+        .post(
+            &format!("/update-borrow-chapters-read/{borrow_id}?value=-5"),
+            &reader,
+        )
+        .await;
+    assert_eq!(reply.invalid_fields(), ["value"]);
+
+    let request = UpdateChaptersRead {
+        borrow_id,
+        chapters_read: 4,
+        cookie: reader.clone(),
+    };
+    assert_eq!(server.call(&request).await.status, StatusCode::OK);
+    assert_eq!(server.borrowed_by(reader.id).await[0].chapters_read, 4);
+    assert!(!books(&server).await[0].can_be_borrowed);
+
+    let request = ReturnBook {
+        borrow_id,
+        cookie: reader.clone(),
+    };
+    assert_eq!(server.call(&request).await.status, StatusCode::OK);
+    assert!(server.borrowed_by(reader.id).await.is_empty());
+    assert!(books(&server).await[0].can_be_borrowed);
+}
+
+#[tokio::test]
+async fn a_borrowed_book_cant_be_deleted() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 3).await;
+    let borrow_id = server.borrow(&reader, book_id).await;
+
+    let reply = server
+        .post(&format!("/delete-book/{book_id}"), &librarian)
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::FORBIDDEN),
+        "Some copies of this book are still borrowed"
+    );
+    assert_eq!(books(&server).await.len(), 1);
+
+    server
+        .post(&format!("/return-book/{borrow_id}"), &reader)
+        .await;
+
+    let reply = server
+        .post(&format!("/delete-book/{book_id}"), &reader)
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::FORBIDDEN),
+        "Only librarians can do this"
+    );
+
+    let reply = server
+        .post(&format!("/delete-book/{book_id}"), &librarian)
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert!(books(&server).await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn the_last_copy_is_lent_only_once() {
+    const READERS: usize = 16;
+
+    let server = TestServer::on_file().await;
+    let librarian = server.librarian().await;
+    let book_id = server.book("Baltagul", 1).await;
+
+    let mut readers = vec![];
+    for index in 0..READERS {
+        readers.push(server.reader(&format!("cititor{index}@example.com")).await);
+    }
+
+    let tasks = readers
+        .into_iter()
+        .map(|reader| {
+            let client = server.client.clone();
+            tokio::spawn(async move {
+                let request = BorrowRequest {
+                    cookie: reader,
+                    book_id,
+                };
+                client.post("/borrow", request).await.ok::<BorrowReply>()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut lent = 0;
+    for task in tasks {
+        let reply = task.await.unwrap();
+        assert!(!reply.already_borrowed);
+        if !reply.no_copies_left {
+            lent += 1;
+        }
+    }
+
+    assert_eq!(lent, 1);
+    assert_eq!(borrows(&server, &librarian).await.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn the_last_copy_is_checked_out_only_once() {
+    const READERS: usize = 16;
+
+    let server = TestServer::on_file().await;
+    let librarian = server.librarian().await;
+    let book_id = server.book("Baltagul", 1).await;
+
+    let mut tasks = vec![];
+    for index in 0..READERS {
+        let reader = server.reader(&format!("cititor{index}@example.com")).await;
+        let request = checkout(&librarian, book_id, reader.id);
+        let client = server.client.clone();
+
+        tasks.push(tokio::spawn(async move {
+            client
+                .post("/checkout", request)
+                .await
+                .ok::<CheckoutReply>()
+        }));
+    }
+
+    let mut replies = vec![];
+    for task in tasks {
+        replies.push(task.await.unwrap());
+    }
+
+    let lent = replies
+        .iter()
+        .filter(|reply| **reply == CheckoutReply::Ok)
+        .count();
+    assert_eq!(lent, 1);
+    assert_eq!(borrows(&server, &librarian).await.len(), 1);
+}
+
+#[tokio::test]
+async fn only_the_most_similar_books_are_kept() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+
+    let mut book_ids = vec![];
+    for index in 0..12 {
+        let book_id = server.book(&format!("Nuvela {index}"), 2).await;
+        server.borrow(&ana, book_id).await;
+        book_ids.push(book_id);
+    }
+    // Borrowed by both of them, which makes the last book the most similar to the first
+    server.borrow(&ion, book_ids[0]).await;
+    server.borrow(&ion, book_ids[11]).await;
+    refresh_similarities(&*server.store).await.unwrap();
+
+    let similar: SimilarBooksReply = server
+        .get(&format!("/books/{}/similar", book_ids[0]))
+        .await
+        .ok();
+    assert_eq!(similar.len(), 10);
+    assert_eq!(similar[0].book_id, book_ids[11]);
+}
+
+#[tokio::test]
+async fn readers_get_recommendations() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let maria = server.reader("maria@example.com").await;
+    let baltagul = server.book("Baltagul", 5).await;
+    let ion_book = server.book("Ion", 5).await;
+
+    server.borrow(&ana, baltagul).await;
+    server.borrow(&ana, ion_book).await;
+    server.borrow(&ion, baltagul).await;
+    refresh_similarities(&*server.store).await.unwrap();
+
+    let similar: SimilarBooksReply = server.get(&format!("/books/{baltagul}/similar")).await.ok();
+    assert_eq!(similar.len(), 1);
+    assert_eq!(similar[0].book_id, ion_book);
+
+    let request = RecommendationsRequest {
+        cookie: ion.clone(),
+    };
+    let recommended: RecommendationsReply = server.post("/me/recommendations", request).await.ok();
+    assert_eq!(recommended.len(), 1);
+    assert_eq!(recommended[0].book_id, ion_book);
+
+    // Readers with nothing borrowed get the most popular books
+    let request = RecommendationsRequest { cookie: maria };
+    let recommended: RecommendationsReply = server.post("/me/recommendations", request).await.ok();
+    assert_eq!(recommended.len(), 2);
+    assert_eq!(recommended[0].book_id, baltagul);
+
+    // The cookie's password is checked, an id isn't enough to see someone's recommendations
+    let cookie = Cookie {
+        password: "not the password".into(),
+        ..ion
+    };
+    let request = RecommendationsRequest { cookie };
+    server
+        .post("/me/recommendations", request)
+        .await
+        .error(StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn readers_who_read_every_similar_book_get_popular_ones_instead() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let baltagul = server.book("Baltagul", 5).await;
+    let ion_book = server.book("Ion", 5).await;
+    let enigma = server.book("Enigma Otiliei", 5).await;
+
+    server.borrow(&ana, baltagul).await;
+    server.borrow(&ana, ion_book).await;
+    server.borrow(&ion, enigma).await;
+    refresh_similarities(&*server.store).await.unwrap();
+
+    let request = RecommendationsRequest { cookie: ana };
+    let recommended: RecommendationsReply = server.post("/me/recommendations", request).await.ok();
+    assert_eq!(recommended.len(), 1);
+    assert_eq!(recommended[0].book_id, enigma);
+}
@@ -0,0 +1,391 @@
+//! The `client` crate against a real server, over a real connection
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use client::{Client, ClientBuilder, Error, RetryPolicy};
+use schema::{
+    auth::{CreateAccount, UserChanges},
+    books::{AuthorFields, BookFields, LoanChanges, NewLoan},
+    dates::PartialDate,
+    endpoints::LoanStatusReport,
+    errors::ErrorCode,
+    events::Event,
+    ids::{BorrowId, UserId, UserKind},
+    lists::ReadingListFields,
+    session::Cookie,
+};
+
+use super::{serve, TestServer, DAY};
+
+fn as_user(client: &Client, cookie: &Cookie) -> Client {
+    client.with_credentials(cookie.id, cookie.password.clone())
+}
+
+/// Retries quickly, so that the tests don't wait
+fn quick_retries() -> RetryPolicy {
+    RetryPolicy {
+        attempts: 3,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(10),
+    }
+}
+
+#[tokio::test]
+async fn accounts_are_made_and_logged_into() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+
+    let account = CreateAccount {
+        name: "Ana".into(),
+        email: "ana@example.com".into(),
+        password: "parola".into(),
+    };
+    let created = client.create_account(&account).await.unwrap();
+    assert_eq!(created.kind, UserKind::Reader);
+
+    let err = client
+        .login("ana@example.com", "greșită")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+    let reply = client.login("ana@example.com", "parola").await.unwrap();
+    assert_eq!(reply.id, created.id);
+
+    let ana = client.with_credentials(reply.id, "parola");
+    assert!(ana.borrowed_by(reply.id).await.unwrap().is_empty());
+    let err = ana.users().await.err().unwrap();
+    assert_eq!(err.code(), Some(ErrorCode::NotLibrarian));
+
+    let librarian = as_user(&client, &server.librarian().await);
+    let changes = UserChanges {
+        kind: UserKind::Librarian,
+    };
+    librarian.update_user(reply.id, &changes).await.unwrap();
+    assert_eq!(ana.users().await.unwrap().len(), 2);
+
+    librarian.delete_user(reply.id).await.unwrap();
+    let err = ana.borrowed_by(reply.id).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+}
+
+#[tokio::test]
+async fn the_catalog_is_managed() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+    let librarian = as_user(&client, &server.librarian().await);
+
+    let fields = AuthorFields {
+        name: "Ion Creangă".into(),
+        date_of_birth: PartialDate::from_ymd(1837, 3, 1).unwrap(),
+        date_of_death: PartialDate::from_year(1889),
+        description: "Povestitor".into(),
+    };
+    let author = librarian.add_author(&fields).await.unwrap();
+    let authors = client.authors().await.unwrap();
+    assert_eq!(authors[0].name, "Ion Creangă");
+    assert_eq!(authors[0].description, "Povestitor");
+
+    let mut fields = BookFields {
+        title: "Amintiri din copilărie".into(),
+        author_id: author.author_id,
+        publish_date: PartialDate::from_year(1892).unwrap(),
+        publisher: "Humanitas".into(),
+        count: 1,
+        synopsis: String::new(),
+        series: None,
+        genres: vec!["Memorii".into()],
+    };
+    let book = librarian.create_book(&fields).await.unwrap();
+    fields.count = 4;
+    librarian.update_book(book.book_id, &fields).await.unwrap();
+    assert_eq!(client.book(book.book_id).await.unwrap().count, 4);
+    assert_eq!(client.books().await.unwrap().len(), 1);
+    assert!(client.similar_books(book.book_id).await.unwrap().is_empty());
+
+    librarian.delete_book(book.book_id).await.unwrap();
+    let err = client.book(book.book_id).await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+    assert_eq!(err.code(), Some(ErrorCode::NoSuchBook));
+}
+
+#[tokio::test]
+async fn invalid_fields_are_detailed() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+    let librarian = as_user(&client, &server.librarian().await);
+
+    let err = librarian
+        .add_author(&AuthorFields::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidFields));
+    assert!(err.details().iter().any(|detail| detail.field == "name"));
+
+    let err = client.loans().await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::MissingAuthorization));
+    assert!(err.details().is_empty());
+}
+
+#[tokio::test]
+async fn books_are_lent_and_returned() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+    let librarian = as_user(&client, &server.librarian().await);
+    let ana_cookie = server.reader("ana@example.com").await;
+    let ana = as_user(&client, &ana_cookie);
+    let book_id = server.book("Ion", 1).await;
+
+    let loan = ana
+        .borrow(&NewLoan {
+            book_id,
+            ..NewLoan::default()
+        })
+        .await
+        .unwrap();
+    let changes = LoanChanges {
+        chapters_read: Some(2),
+        ..LoanChanges::default()
+    };
+    ana.update_loan(loan.borrow_id, &changes).await.unwrap();
+    let changes = LoanChanges {
+        lengthen_by_days: Some(7),
+        ..LoanChanges::default()
+    };
+    librarian
+        .update_loan(loan.borrow_id, &changes)
+        .await
+        .unwrap();
+
+    let borrowed = ana.borrowed_by(ana_cookie.id).await.unwrap();
+    assert_eq!(borrowed[0].chapters_read, 2);
+    assert_eq!(
+        borrowed[0].valid_until.unix(),
+        loan.valid_until.unix() + 7 * DAY
+    );
+
+    let status = librarian
+        .report::<LoanStatusReport>(None, None)
+        .await
+        .unwrap();
+    assert_eq!(status.active, 1);
+    let csv = librarian.report_csv("loans", None, None).await.unwrap();
+    assert_eq!(csv.lines().next(), Some("active,overdue"));
+
+    ana.return_book(loan.borrow_id).await.unwrap();
+    assert!(librarian.loans().await.unwrap().is_empty());
+    let err = ana.return_book(loan.borrow_id).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NoSuchBorrow));
+}
+
+#[tokio::test]
+async fn reading_lists_are_kept() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+    let ana_cookie = server.reader("ana@example.com").await;
+    let ana = as_user(&client, &ana_cookie);
+    let first = server.book("Maitreyi", 1).await;
+    let second = server.book("Nuntă în cer", 1).await;
+
+    let mut fields = ReadingListFields {
+        name: "Eliade".into(),
+        is_public: false,
+    };
+    let list = ana.create_reading_list(&fields).await.unwrap();
+    ana.add_to_reading_list(list.list_id, first).await.unwrap();
+    ana.add_to_reading_list(list.list_id, second).await.unwrap();
+    ana.reorder_reading_list(list.list_id, &[second, first])
+        .await
+        .unwrap();
+    ana.remove_from_reading_list(list.list_id, first)
+        .await
+        .unwrap();
+    fields.is_public = true;
+    ana.update_reading_list(list.list_id, &fields)
+        .await
+        .unwrap();
+
+    let ion = as_user(&client, &server.reader("ion@example.com").await);
+    let lists = ion.reading_lists(ana_cookie.id).await.unwrap();
+    assert_eq!(lists[0].book_ids, [second]);
+
+    ana.delete_reading_list(list.list_id).await.unwrap();
+    assert!(ana.reading_lists(ana_cookie.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn backups_are_made_on_request() {
+    let server = TestServer::new().await;
+    let client = Client::new(server.listen().await).unwrap();
+    let librarian = as_user(&client, &server.librarian().await);
+
+    let backup = librarian.create_backup().await.unwrap();
+    let backups = librarian.backups().await.unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].sha256, backup.sha256);
+}
+
+#[tokio::test]
+async fn events_are_followed_past_the_timeout() {
+    let server = TestServer::new().await;
+    let client = ClientBuilder::new(server.listen().await)
+        .timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let librarian = as_user(&client, &server.librarian().await);
+    let mut events = librarian.events().await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let book_id = server.book("Pădurea spânzuraților", 1).await;
+    let fields = BookFields {
+        title: "Pădurea spânzuraților".into(),
+        author_id: librarian.book(book_id).await.unwrap().author.author_id,
+        publish_date: PartialDate::from_year(1922).unwrap(),
+        publisher: "Polirom".into(),
+        count: 2,
+        synopsis: String::new(),
+        series: None,
+        genres: vec![],
+    };
+    librarian.update_book(book_id, &fields).await.unwrap();
+
+    let Some(Event::BookSaved { book }) = events.next().await.unwrap() else {
+        panic!("expected the changed book");
+    };
+    assert_eq!(book.title, "Pădurea spânzuraților");
+    assert_eq!(book.count, 2);
+}
+
+/// Keeps the `Idempotency-Key` of every request, answering `503` to the first `failures` of them
+async fn flaky(failures: usize, keys: Arc<Mutex<Vec<Option<String>>>>, app: Router) -> String {
+    let app = app.layer(middleware::from_fn(move |request: Request, next: Next| {
+        let keys = keys.clone();
+
+        async move {
+            let key = request
+                .headers()
+                .get("Idempotency-Key")
+                .map(|key| key.to_str().unwrap().to_string());
+            let seen = {
+                let mut keys = keys.lock().unwrap();
+                keys.push(key);
+                keys.len()
+            };
+
+            if seen <= failures {
+                StatusCode::SERVICE_UNAVAILABLE.into_response()
+            } else {
+                next.run(request).await
+            }
+        }
+    }));
+
+    serve(app).await
+}
+
+#[tokio::test]
+async fn requests_are_retried_when_the_server_is_unavailable() {
+    let server = TestServer::new().await;
+    let cookie = server.reader("ana@example.com").await;
+    let book_id = server.book("Ultima noapte de dragoste", 1).await;
+
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let url = flaky(2, keys.clone(), server.app.clone()).await;
+    let client = ClientBuilder::new(url)
+        .credentials(cookie.id, cookie.password.clone())
+        .retry_policy(quick_retries())
+        .build()
+        .unwrap();
+
+    let loan = client
+        .borrow(&NewLoan {
+            book_id,
+            ..NewLoan::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(loan.book_id, book_id);
+
+    // Every attempt was the same request as far as the server can tell
+    let keys = keys.lock().unwrap().clone();
+    assert_eq!(keys.len(), 3);
+    assert!(keys[0].is_some());
+    assert!(keys.iter().all(|key| *key == keys[0]));
+}
+
+#[tokio::test]
+async fn requests_give_up_after_the_last_attempt() {
+    let server = TestServer::new().await;
+    let cookie = server.reader("ana@example.com").await;
+
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let url = flaky(usize::MAX, keys.clone(), server.app.clone()).await;
+    let client = ClientBuilder::new(url)
+        .credentials(cookie.id, cookie.password.clone())
+        .retry_policy(quick_retries())
+        .build()
+        .unwrap();
+
+    let err = client.loans().await.unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(err.code(), Some(ErrorCode::Unknown));
+    assert_eq!(keys.lock().unwrap().len(), 3);
+
+    // Patches are sent again too, under a key of their own
+    let err = client
+        .update_loan(BorrowId(1), &LoanChanges::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    let keys = keys.lock().unwrap().clone();
+    assert_eq!(keys.len(), 6);
+    assert!(keys[3].is_some());
+    assert!(keys[3..].iter().all(|key| *key == keys[3]));
+    assert_eq!(keys[0], None);
+}
+
+#[tokio::test]
+async fn slow_requests_time_out() {
+    async fn slow() -> Response {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        StatusCode::OK.into_response()
+    }
+
+    let url = serve(Router::new().route("/api/v1/books", get(slow))).await;
+    let client = ClientBuilder::new(url)
+        .timeout(Duration::from_millis(50))
+        .retry_policy(RetryPolicy::never())
+        .build()
+        .unwrap();
+
+    let err = client.books().await.unwrap_err();
+    assert!(matches!(err, Error::Timeout(timeout) if timeout == Duration::from_millis(50)));
+    assert_eq!(err.code(), None);
+}
+
+#[tokio::test]
+async fn unreachable_servers_are_network_errors() {
+    // Nothing listens on the port once the listener is dropped
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let client = ClientBuilder::new(url)
+        .credentials(UserId(1), "parola")
+        .retry_policy(quick_retries())
+        .build()
+        .unwrap();
+    let err = client.books().await.unwrap_err();
+    assert!(matches!(err, Error::Network(_)));
+}
@@ -0,0 +1,129 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderName, Method, Request, StatusCode},
+};
+use schema::{
+    books::{Author, AuthorFields, Book},
+    dates::PartialDate,
+    endpoints::ReturnBook,
+};
+
+use super::{Reply, TestServer};
+
+/// Gets `uri` with a `condition` header saying what the client already has
+async fn get_if(server: &TestServer, uri: &str, condition: HeaderName, value: &str) -> Reply {
+    let request = Request::get(uri)
+        .header(condition, value)
+        .body(Body::empty())
+        .unwrap();
+    server.request(request).await
+}
+
+fn etag(reply: &Reply) -> String {
+    reply.headers[header::ETAG].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn unchanged_catalogs_are_not_sent_again() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    server.book("Baltagul", 1).await;
+
+    let first = server.get("/books").await;
+    assert_eq!(first.ok::<Vec<Book>>().len(), 1);
+    assert_eq!(first.headers[header::CACHE_CONTROL], "no-cache");
+    let first_etag = etag(&first);
+
+    let again = get_if(&server, "/books", header::IF_NONE_MATCH, &first_etag).await;
+    assert_eq!(again.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(again.body, "");
+    assert_eq!(etag(&again), first_etag);
+
+    // The authors share the catalog's revision, under both versions of the API
+    let authors = get_if(&server, "/authors", header::IF_NONE_MATCH, &first_etag).await;
+    assert_eq!(authors.status, StatusCode::NOT_MODIFIED);
+    let authors = get_if(
+        &server,
+        "/api/v1/authors",
+        header::IF_NONE_MATCH,
+        &first_etag,
+    )
+    .await;
+    assert_eq!(authors.status, StatusCode::NOT_MODIFIED);
+
+    let fields = AuthorFields {
+        name: "Mircea Eliade".into(),
+        date_of_birth: PartialDate::from_ymd(1907, 3, 13).unwrap(),
+        date_of_death: None,
+        description: String::new(),
+    };
+    server
+        .api(Method::POST, "/authors", &librarian, &fields)
+        .await
+        .created::<Author>();
+
+    let changed = get_if(
+        &server,
+        "/api/v1/authors",
+        header::IF_NONE_MATCH,
+        &first_etag,
+    )
+    .await;
+    assert_eq!(changed.ok::<Vec<Author>>().len(), 2);
+    assert_ne!(etag(&changed), first_etag);
+
+    let books = get_if(&server, "/books", header::IF_NONE_MATCH, &etag(&changed)).await;
+    assert_eq!(books.status, StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn lending_and_returning_change_the_catalog() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+    let before = etag(&server.get("/books").await);
+
+    let borrow_id = server.borrow(&reader, book_id).await;
+    let lent = get_if(&server, "/books", header::IF_NONE_MATCH, &before).await;
+    assert!(!lent.ok::<Vec<Book>>()[0].can_be_borrowed);
+
+    let request = ReturnBook {
+        borrow_id,
+        cookie: reader.clone(),
+    };
+    server.call(&request).await;
+    let returned = get_if(&server, "/books", header::IF_NONE_MATCH, &etag(&lent)).await;
+    assert!(returned.ok::<Vec<Book>>()[0].can_be_borrowed);
+    assert_ne!(etag(&returned), before);
+}
+
+#[tokio::test]
+async fn modification_dates_are_honoured() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let book_id = server.book("Baltagul", 1).await;
+
+    let first = server.get("/books").await;
+    let last_modified = first.headers[header::LAST_MODIFIED].to_str().unwrap();
+
+    let again = get_if(&server, "/books", header::IF_MODIFIED_SINCE, last_modified).await;
+    assert_eq!(again.status, StatusCode::NOT_MODIFIED);
+
+    let long_ago = "Sat, 01 Jan 2000 00:00:00 GMT";
+    let stale = get_if(&server, "/books", header::IF_MODIFIED_SINCE, long_ago).await;
+    assert_eq!(stale.status, StatusCode::OK);
+
+    // Dates only count whole seconds, so an `ETag` is trusted over them
+    let path = format!("/books/{book_id}");
+    server.api(Method::DELETE, &path, &librarian, ()).await;
+    let request = Request::get("/books")
+        .header(header::IF_NONE_MATCH, etag(&first))
+        .header(header::IF_MODIFIED_SINCE, "Fri, 31 Dec 9999 23:59:59 GMT")
+        .body(Body::empty())
+        .unwrap();
+    let deleted = server.request(request).await;
+    assert!(deleted.ok::<Vec<Book>>().is_empty());
+
+    let garbled = get_if(&server, "/books", header::IF_MODIFIED_SINCE, "ieri").await;
+    assert_eq!(garbled.status, StatusCode::OK);
+}
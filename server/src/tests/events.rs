@@ -0,0 +1,196 @@
+use std::{pin::Pin, time::Duration};
+
+use axum::{
+    body::Bytes,
+    http::{header, Method, StatusCode},
+};
+use schema::{
+    books::{BookFields, LoanChanges},
+    endpoints::ReturnBook,
+    events::Event,
+    ids::UserId,
+    session::Cookie,
+};
+use tokio_stream::{Stream, StreamExt};
+use tower::ServiceExt;
+
+use super::{api_request, TestServer};
+
+/// The events sent to one follower of `/api/v1/events`
+struct Follower {
+    body: Pin<Box<dyn Stream<Item = Result<Bytes, axum::Error>> + Send>>,
+    buffer: String,
+}
+
+impl Follower {
+    async fn new(server: &TestServer, cookie: &Cookie) -> Self {
+        let request = api_request(Method::GET, "/events", cookie, ());
+        let response = server.app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        Self {
+            body: Box::pin(response.into_body().into_data_stream()),
+            buffer: String::new(),
+        }
+    }
+
+    /// Waits for the next event, skipping keep-alive comments
+    async fn next(&mut self) -> Event {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let message: String = self.buffer.drain(..end + 2).collect();
+                let data = message.lines().find_map(|line| line.strip_prefix("data: "));
+                let Some(data) = data else {
+                    continue;
+                };
+
+                let event: Event = serde_json::from_str(data).unwrap();
+                let name = message
+                    .lines()
+                    .find_map(|line| line.strip_prefix("event: "));
+                assert_eq!(name, Some(event.name()));
+                return event;
+            }
+
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.body.next())
+                .await
+                .expect("no event was sent")
+                .unwrap()
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn changes_are_sent_as_they_happen() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+    let mut follower = Follower::new(&server, &librarian).await;
+
+    let borrow_id = server.borrow(&reader, book_id).await;
+    let Event::LoanSaved { loan } = follower.next().await else {
+        panic!("expected the new loan");
+    };
+    assert_eq!(loan.borrow_id, borrow_id);
+    assert_eq!(loan.user_id, reader.id);
+    let Event::BookSaved { book } = follower.next().await else {
+        panic!("expected the borrowed book");
+    };
+    assert_eq!(book.book_id, book_id);
+    assert!(!book.can_be_borrowed);
+    let Event::UserSaved { user } = follower.next().await else {
+        panic!("expected the reader");
+    };
+    assert_eq!(user.borrowed_book_count, 1);
+
+    let changes = LoanChanges {
+        chapters_read: Some(4),
+        ..LoanChanges::default()
+    };
+    let path = format!("/loans/{borrow_id}");
+    server.api(Method::PATCH, &path, &reader, &changes).await;
+    let Event::LoanSaved { loan } = follower.next().await else {
+        panic!("expected the changed loan");
+    };
+    assert_eq!(loan.chapters_read, 4);
+
+    let request = ReturnBook {
+        borrow_id,
+        cookie: reader.clone(),
+    };
+    server.call(&request).await;
+    let Event::LoanReturned {
+        borrow_id: returned_id,
+        ..
+    } = follower.next().await
+    else {
+        panic!("expected the returned loan");
+    };
+    assert_eq!(returned_id, borrow_id);
+    let Event::BookSaved { book } = follower.next().await else {
+        panic!("expected the returned book");
+    };
+    assert!(book.can_be_borrowed);
+    let Event::UserSaved { user } = follower.next().await else {
+        panic!("expected the reader");
+    };
+    assert_eq!(user.borrowed_book_count, 0);
+
+    let path = format!("/books/{book_id}");
+    server.api(Method::DELETE, &path, &librarian, ()).await;
+    let Event::BookDeleted {
+        book_id: deleted_id,
+    } = follower.next().await
+    else {
+        panic!("expected the deleted book");
+    };
+    assert_eq!(deleted_id, book_id);
+}
+
+#[tokio::test]
+async fn readers_only_follow_the_catalogue_and_their_own_account() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let dan = server.reader("dan@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+    let mut follower = Follower::new(&server, &ana).await;
+
+    // Only the book changes for Ana when Ion borrows it
+    server.borrow(&ion, book_id).await;
+    let Event::BookSaved { book } = follower.next().await else {
+        panic!("expected the borrowed book");
+    };
+    assert_eq!(book.book_id, book_id);
+
+    // Nor does anything for her when Dan's account is deleted
+    let path = format!("/users/{}", dan.id);
+    server.api(Method::DELETE, &path, &librarian, ()).await;
+    let fields = BookFields {
+        title: "Enigma Otiliei".into(),
+        author_id: book.author.author_id,
+        publish_date: book.publish_date,
+        publisher: "Polirom".into(),
+        count: 1,
+        synopsis: String::new(),
+        series: None,
+        genres: vec![],
+    };
+    server
+        .api(Method::POST, "/books", &librarian, &fields)
+        .await;
+    let Event::BookSaved { book } = follower.next().await else {
+        panic!("expected the new book");
+    };
+    assert_eq!(book.title, "Enigma Otiliei");
+
+    server.borrow(&ana, book.book_id).await;
+    let Event::LoanSaved { loan } = follower.next().await else {
+        panic!("expected Ana's loan");
+    };
+    assert_eq!(loan.user_id, ana.id);
+}
+
+#[tokio::test]
+async fn following_needs_an_account() {
+    let server = TestServer::new().await;
+
+    let reply = server.get("/api/v1/events").await;
+    reply.error(StatusCode::UNAUTHORIZED);
+
+    let stranger = Cookie {
+        id: UserId(1000),
+        password: String::new(),
+    };
+    let request = api_request(Method::GET, "/events", &stranger, ());
+    let response = server.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
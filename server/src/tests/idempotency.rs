@@ -0,0 +1,172 @@
+use axum::http::{Method, StatusCode};
+use schema::{
+    books::{BorrowReply, BorrowRequest, BorrowedBook, LoanChanges, NewLoan},
+    lists::{CreateReadingListRequest, ReadingList},
+    session::Cookie,
+};
+
+use super::{api_request, TestServer, DAY};
+
+#[tokio::test]
+async fn repeated_requests_get_the_first_response() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+    let request = BorrowRequest {
+        cookie: reader.clone(),
+        book_id,
+    };
+
+    let first = server.post_with_key("/borrow", "cheie", &request).await;
+    let reply: BorrowReply = first.ok();
+    assert!(!reply.already_borrowed);
+    assert!(!first.headers.contains_key("Idempotent-Replayed"));
+
+    let again = server.post_with_key("/borrow", "cheie", &request).await;
+    assert_eq!(again.headers["Idempotent-Replayed"], "true");
+    assert_eq!(again.content_type.as_deref(), first.content_type.as_deref());
+    assert_eq!(again.body, first.body);
+    assert_eq!(server.borrowed_by(reader.id).await.len(), 1);
+
+    // Without the key, borrowing again is noticed as such
+    let reply: BorrowReply = server.post("/borrow", &request).await.ok();
+    assert!(reply.already_borrowed);
+}
+
+#[tokio::test]
+async fn failures_are_replayed_too() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let request = CreateReadingListRequest {
+        name: "De citit".into(),
+        is_public: false,
+        cookie: reader.clone(),
+    };
+
+    let list: ReadingList = server
+        .post_with_key("/create-reading-list", "prima", &request)
+        .await
+        .ok();
+    let reply = server
+        .post_with_key("/create-reading-list", "a-doua", &request)
+        .await;
+    reply.error(StatusCode::CONFLICT);
+
+    let replayed = server
+        .post_with_key("/create-reading-list", "prima", &request)
+        .await;
+    assert_eq!(replayed.ok::<ReadingList>().list_id, list.list_id);
+    let replayed = server
+        .post_with_key("/create-reading-list", "a-doua", &request)
+        .await;
+    replayed.error(StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn keys_belong_to_a_single_request() {
+    let server = TestServer::new().await;
+    let reader = server.reader("ana@example.com").await;
+    let first = server.book("Baltagul", 1).await;
+    let second = server.book("Maitreyi", 1).await;
+
+    let borrow = |book_id| BorrowRequest {
+        cookie: reader.clone(),
+        book_id,
+    };
+    server
+        .post_with_key("/borrow", "cheie", borrow(first))
+        .await
+        .ok::<BorrowReply>();
+
+    let reply = server
+        .post_with_key("/borrow", "cheie", borrow(second))
+        .await;
+    assert_eq!(
+        reply.error(StatusCode::UNPROCESSABLE_ENTITY),
+        "The idempotency key was already used for a different request"
+    );
+    assert_eq!(server.borrowed_by(reader.id).await.len(), 1);
+
+    let reply = server.post_with_key("/borrow", "", borrow(second)).await;
+    reply.error(StatusCode::BAD_REQUEST);
+    let reply = server
+        .post_with_key("/borrow", &"x".repeat(256), borrow(second))
+        .await;
+    reply.error(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn patches_are_only_made_once() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let book_id = server.book("Baltagul", 1).await;
+    let borrow_id = server.borrow(&reader, book_id).await;
+    let due = server.borrowed_by(reader.id).await[0].valid_until;
+
+    let changes = LoanChanges {
+        lengthen_by_days: Some(30),
+        ..LoanChanges::default()
+    };
+    for _ in 0..2 {
+        let mut request = api_request(
+            Method::PATCH,
+            &format!("/loans/{borrow_id}"),
+            &librarian,
+            &changes,
+        );
+        request
+            .headers_mut()
+            .insert("Idempotency-Key", "prelungire".parse().unwrap());
+        let reply = server.request(request).await;
+        assert_eq!(reply.status, StatusCode::NO_CONTENT);
+    }
+
+    // Lengthened once, which also keeps it within the longest extension
+    let lengthened = server.borrowed_by(reader.id).await[0].valid_until;
+    assert_eq!(lengthened.unix(), due.unix() + 30 * DAY);
+}
+
+#[tokio::test]
+async fn accounts_dont_share_keys() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let book_id = server.book("Baltagul", 2).await;
+
+    let lend = |cookie: &Cookie| {
+        let loan = NewLoan {
+            book_id,
+            user_id: None,
+            reading_list_id: None,
+        };
+        let mut request = api_request(Method::POST, "/loans", cookie, loan);
+        request
+            .headers_mut()
+            .insert("Idempotency-Key", "cheie".parse().unwrap());
+        server.request(request)
+    };
+
+    let first = lend(&ana).await;
+    let first_loan: BorrowedBook = first.created();
+
+    // The same body under the same key, only sent by someone else
+    let second = lend(&ion).await;
+    assert!(!second.headers.contains_key("Idempotent-Replayed"));
+    let second_loan: BorrowedBook = second.created();
+    assert_ne!(second_loan.borrow_id, first_loan.borrow_id);
+    assert_eq!(server.borrowed_by(ana.id).await.len(), 1);
+    assert_eq!(server.borrowed_by(ion.id).await.len(), 1);
+
+    // The older routes carry the account in the body
+    let other_book = server.book("Maitreyi", 2).await;
+    for reader in [&ana, &ion] {
+        let request = BorrowRequest {
+            cookie: reader.clone(),
+            book_id: other_book,
+        };
+        let reply = server.post_with_key("/borrow", "vechi", &request).await;
+        assert!(!reply.headers.contains_key("Idempotent-Replayed"));
+        assert!(!reply.ok::<BorrowReply>().already_borrowed);
+    }
+}
@@ -0,0 +1,221 @@
+use axum::http::StatusCode;
+use schema::{
+    books::BorrowReply,
+    ids::{BookId, UserId},
+    lists::{
+        ChangeReadingListRequest, CreateReadingListRequest, ReadingList, ReadingListEntryRequest,
+        ReadingListsReply, ReadingListsRequest, ReorderReadingListRequest,
+    },
+    session::Cookie,
+    Integer,
+};
+
+use super::TestServer;
+
+async fn create_list(server: &TestServer, cookie: &Cookie, name: &str, is_public: bool) -> Integer {
+    let request = CreateReadingListRequest {
+        name: name.into(),
+        is_public,
+        cookie: cookie.clone(),
+    };
+    let list: ReadingList = server.post("/create-reading-list", request).await.ok();
+
+    list.list_id
+}
+
+async fn lists(server: &TestServer, cookie: &Cookie, user_id: UserId) -> ReadingListsReply {
+    let request = ReadingListsRequest {
+        cookie: cookie.clone(),
+    };
+    server
+        .post(&format!("/reading-lists/{user_id}"), request)
+        .await
+        .ok()
+}
+
+fn entry(cookie: &Cookie, book_id: BookId) -> ReadingListEntryRequest {
+    ReadingListEntryRequest {
+        book_id,
+        cookie: cookie.clone(),
+    }
+}
+
+#[tokio::test]
+async fn readers_keep_reading_lists() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let list_id = create_list(&server, &ana, "De citit", false).await;
+    create_list(&server, &ana, "Preferate", true).await;
+
+    let request = CreateReadingListRequest {
+        name: "De citit".into(),
+        is_public: true,
+        cookie: ana.clone(),
+    };
+    let reply = server.post("/create-reading-list", request).await;
+    assert_eq!(
+        reply.error(StatusCode::CONFLICT),
+        "You already have a reading list with this name"
+    );
+
+    let request = CreateReadingListRequest {
+        name: " ".into(),
+        is_public: true,
+        cookie: ana.clone(),
+    };
+    let reply = server.post("/create-reading-list", request).await;
+    reply.error(StatusCode::BAD_REQUEST);
+
+    assert_eq!(lists(&server, &ana, ana.id).await.len(), 2);
+    let visible = lists(&server, &ion, ana.id).await;
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].name, "Preferate");
+
+    let request = ChangeReadingListRequest {
+        name: "Vacanța".into(),
+        is_public: true,
+        cookie: ion.clone(),
+    };
+    let reply = server
+        .post(&format!("/change-reading-list/{list_id}"), request)
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let request = ChangeReadingListRequest {
+        name: "Vacanța".into(),
+        is_public: true,
+        cookie: ana.clone(),
+    };
+    let reply = server
+        .post(&format!("/change-reading-list/{list_id}"), request)
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(lists(&server, &ion, ana.id).await.len(), 2);
+
+    let reply = server
+        .post(&format!("/delete-reading-list/{list_id}"), &ion)
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    let reply = server
+        .post(&format!("/delete-reading-list/{list_id}"), &ana)
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(lists(&server, &ana, ana.id).await.len(), 1);
+
+    let reply = server
+        .post(&format!("/delete-reading-list/{list_id}"), &ana)
+        .await;
+    reply.error(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn books_are_added_removed_and_reordered() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let ion = server.reader("ion@example.com").await;
+    let list_id = create_list(&server, &ana, "De citit", false).await;
+    let first = server.book("Moara cu noroc", 1).await;
+    let second = server.book("Pădurea spânzuraților", 1).await;
+
+    for book_id in [first, second] {
+        let reply = server
+            .post(
+                &format!("/add-to-reading-list/{list_id}"),
+                entry(&ana, book_id),
+            )
+            .await;
+        assert_eq!(reply.status, StatusCode::OK);
+    }
+
+    let reply = server
+        .post(
+            &format!("/add-to-reading-list/{list_id}"),
+            entry(&ana, BookId(1000)),
+        )
+        .await;
+    assert_eq!(reply.error(StatusCode::NOT_FOUND), "No book with given id");
+
+    let reply = server
+        .post(
+            &format!("/add-to-reading-list/{list_id}"),
+            entry(&ion, first),
+        )
+        .await;
+    reply.error(StatusCode::FORBIDDEN);
+
+    assert_eq!(
+        lists(&server, &ana, ana.id).await[0].book_ids,
+        [first, second]
+    );
+
+    let request = ReorderReadingListRequest {
+        book_ids: vec![second],
+        cookie: ana.clone(),
+    };
+    let reply = server
+        .post(&format!("/reorder-reading-list/{list_id}"), request)
+        .await;
+    reply.error(StatusCode::BAD_REQUEST);
+
+    let request = ReorderReadingListRequest {
+        book_ids: vec![second, first],
+        cookie: ana.clone(),
+    };
+    let reply = server
+        .post(&format!("/reorder-reading-list/{list_id}"), request)
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(
+        lists(&server, &ana, ana.id).await[0].book_ids,
+        [second, first]
+    );
+
+    let reply = server
+        .post(
+            &format!("/remove-from-reading-list/{list_id}"),
+            entry(&ana, second),
+        )
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(lists(&server, &ana, ana.id).await[0].book_ids, [first]);
+}
+
+#[tokio::test]
+async fn books_are_borrowed_off_a_list() {
+    let server = TestServer::new().await;
+    let ana = server.reader("ana@example.com").await;
+    let list_id = create_list(&server, &ana, "De citit", false).await;
+    let on_list = server.book("Moara cu noroc", 1).await;
+    let not_on_list = server.book("Enigma Otiliei", 1).await;
+
+    server
+        .post(
+            &format!("/add-to-reading-list/{list_id}"),
+            entry(&ana, on_list),
+        )
+        .await;
+
+    let reply = server
+        .post(
+            &format!("/borrow-from-reading-list/{list_id}"),
+            entry(&ana, not_on_list),
+        )
+        .await;
+    reply.error(StatusCode::NOT_FOUND);
+
+    let reply: BorrowReply = server
+        .post(
+            &format!("/borrow-from-reading-list/{list_id}"),
+            entry(&ana, on_list),
+        )
+        .await
+        .ok();
+    assert!(!reply.already_borrowed);
+
+    assert!(lists(&server, &ana, ana.id).await[0].book_ids.is_empty());
+    let borrowed = server.borrowed_by(ana.id).await;
+    assert_eq!(borrowed.len(), 1);
+    assert_eq!(borrowed[0].book_id, on_list);
+}
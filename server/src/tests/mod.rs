@@ -0,0 +1,395 @@
+//! End-to-end tests of the HTTP API. Every test gets the full [`crate::app`] router on top of its
+//! own in-memory SQLite database, and sends it requests the way the app would.
+
+use std::{
+    fs,
+    future::Future,
+    ops::Deref,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use schema::{
+    books::{BorrowReply, BorrowRequest, BorrowedByReply},
+    dates::{PartialDate, Timestamp},
+    endpoints::{self, BorrowedBy, Endpoint},
+    errors::{ErrorCode, FieldError},
+    ids::{BookId, BorrowId, UserId, UserKind},
+    session::Cookie,
+    Integer,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::{
+    config::Config,
+    service::users::hash_password,
+    store::{BookDetails, NewAuthor, NewUser, SaveOutcome, SqliteStore, Store},
+};
+
+mod api;
+mod auth;
+mod backups;
+mod books;
+mod client;
+mod conditional;
+mod events;
+mod idempotency;
+mod lists;
+mod openapi;
+mod reports;
+
+const DAY: i64 = 24 * 60 * 60;
+/// The password of every account the tests make
+const PASSWORD: &str = "parola";
+
+/// A response, with the body kept as text so that failures can show it
+struct Reply {
+    status: StatusCode,
+    content_type: Option<String>,
+    headers: HeaderMap,
+    body: String,
+}
+
+impl Reply {
+    /// Checks that the request went through and parses the body
+    #[track_caller]
+    fn ok<T: DeserializeOwned>(&self) -> T {
+        assert_eq!(self.status, StatusCode::OK, "{}", self.body);
+        serde_json::from_str(&self.body).unwrap()
+    }
+
+    /// Checks that something was created and parses it
+    #[track_caller]
+    fn created<T: DeserializeOwned>(&self) -> T {
+        assert_eq!(self.status, StatusCode::CREATED, "{}", self.body);
+        serde_json::from_str(&self.body).unwrap()
+    }
+
+    /// Checks that the request failed with `status`, returning the public error message
+    #[track_caller]
+    fn error(&self, status: StatusCode) -> String {
+        assert_eq!(self.status, status, "{}", self.body);
+        let body: Value = serde_json::from_str(&self.body).unwrap();
+        body["error"].as_str().unwrap().to_string()
+    }
+
+    /// Checks that the request was refused for breaking validation rules, returning the names of
+    /// the fields that broke them
+    #[track_caller]
+    fn invalid_fields(&self) -> Vec<String> {
+        assert_eq!(
+            self.error_code(StatusCode::UNPROCESSABLE_ENTITY),
+            ErrorCode::InvalidFields
+        );
+        let body: Value = serde_json::from_str(&self.body).unwrap();
+        let details: Vec<FieldError> = serde_json::from_value(body["details"].clone()).unwrap();
+        details.into_iter().map(|detail| detail.field).collect()
+    }
+
+    /// Checks that the request failed with `status`, returning the error code
+    #[track_caller]
+    fn error_code(&self, status: StatusCode) -> ErrorCode {
+        assert_eq!(self.status, status, "{}", self.body);
+        let body: Value = serde_json::from_str(&self.body).unwrap();
+        serde_json::from_value(body["code"].clone()).unwrap()
+    }
+}
+
+/// Sends requests to the router. Clones can be moved into tasks to send requests concurrently.
+#[derive(Clone)]
+struct Client {
+    app: Router,
+}
+
+impl Client {
+    // Not `async fn`s, the returned futures don't borrow the client, which isn't `Sync`
+    fn request(&self, request: Request<Body>) -> impl Future<Output = Reply> {
+        let app = self.app.clone();
+
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+
+            let status = response.status();
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|value| value.to_str().unwrap().to_string());
+            let headers = response.headers().clone();
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+            Reply {
+                status,
+                content_type,
+                headers,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }
+        }
+    }
+
+    fn get(&self, uri: &str) -> impl Future<Output = Reply> {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        self.request(request)
+    }
+
+    fn post(&self, uri: &str, body: impl Serialize) -> impl Future<Output = Reply> {
+        self.request(json_post(uri, body, None))
+    }
+
+    /// Sends `request` where its [`Endpoint`] says, the way the app does
+    fn call<E: Endpoint>(&self, request: &E) -> impl Future<Output = Reply> {
+        let request = match E::METHOD {
+            endpoints::Method::Get => Request::get(request.path()).body(Body::empty()).unwrap(),
+            endpoints::Method::Post => json_post(&request.path(), request.body(), None),
+        };
+
+        self.request(request)
+    }
+
+    /// Sends a request to the `/api/v1` routes on behalf of `cookie`'s account
+    fn api(
+        &self,
+        method: Method,
+        uri: &str,
+        cookie: &Cookie,
+        body: impl Serialize,
+    ) -> impl Future<Output = Reply> {
+        self.request(api_request(method, uri, cookie, body))
+    }
+
+    /// Posts with an `Idempotency-Key` header
+    fn post_with_key(
+        &self,
+        uri: &str,
+        key: &str,
+        body: impl Serialize,
+    ) -> impl Future<Output = Reply> {
+        self.request(json_post(uri, body, Some(key)))
+    }
+}
+
+/// A request to the `/api/v1` routes on behalf of `cookie`'s account
+fn api_request(method: Method, uri: &str, cookie: &Cookie, body: impl Serialize) -> Request<Body> {
+    let credentials = STANDARD.encode(format!("{}:{}", cookie.id, cookie.password));
+
+    Request::builder()
+        .method(method)
+        .uri(format!("/api/v1{uri}"))
+        .header(header::AUTHORIZATION, format!("Basic {credentials}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&body).unwrap().into())
+        .unwrap()
+}
+
+fn json_post(uri: &str, body: impl Serialize, idempotency_key: Option<&str>) -> Request<Body> {
+    let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+    if let Some(key) = idempotency_key {
+        request = request.header("Idempotency-Key", key);
+    }
+
+    request
+        .body(serde_json::to_vec(&body).unwrap().into())
+        .unwrap()
+}
+
+/// A new directory for the backups and the media of a test server
+fn test_dir() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "biblioteca-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(dir.join("media")).unwrap();
+
+    dir
+}
+
+struct TestServer {
+    client: Client,
+    store: Arc<SqliteStore>,
+    /// Holds the backups, the media and the database file if there is one, deleted with the
+    /// server
+    dir: PathBuf,
+}
+
+impl TestServer {
+    async fn new() -> Self {
+        let dir = test_dir();
+        let store = SqliteStore::in_memory().await.unwrap();
+
+        Self::with_store(dir, store)
+    }
+
+    /// Like [`TestServer::new`], but on a database file the store opens several connections to,
+    /// so that concurrent requests really are handled at the same time
+    async fn on_file() -> Self {
+        let dir = test_dir();
+        let store = SqliteStore::open(&dir.join("biblioteca.db")).await.unwrap();
+
+        Self::with_store(dir, store)
+    }
+
+    fn with_store(dir: PathBuf, store: SqliteStore) -> Self {
+        let mut config = Config {
+            media_dir: dir.join("media"),
+            ..Config::default()
+        };
+        config.backups.dir = dir.join("backups");
+
+        let store = Arc::new(store);
+        let client = Client {
+            app: crate::app(config, store.clone()),
+        };
+
+        Self { client, store, dir }
+    }
+
+    /// Makes an account of the given kind with [`PASSWORD`], returning the cookie the app would
+    /// send for it
+    async fn user(&self, email: &str, kind: UserKind) -> Cookie {
+        // Hashing takes a while, and every account can share the same hash
+        static PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+
+        let user = NewUser {
+            name: email.split('@').next().unwrap().into(),
+            kind,
+            email: email.into(),
+            password_hash: PASSWORD_HASH
+                .get_or_init(|| hash_password(PASSWORD).unwrap())
+                .clone(),
+            registered_at: Timestamp::default(),
+        };
+        let id = self.store.create_user(&user).await.unwrap();
+
+        Cookie {
+            id,
+            password: PASSWORD.into(),
+        }
+    }
+
+    async fn librarian(&self) -> Cookie {
+        self.user("bibliotecar@example.com", UserKind::Librarian)
+            .await
+    }
+
+    async fn reader(&self, email: &str) -> Cookie {
+        self.user(email, UserKind::Reader).await
+    }
+
+    /// Adds a book with `count` copies by a new author, returning the book's id
+    async fn book(&self, title: &str, count: Integer) -> BookId {
+        let author = NewAuthor {
+            name: format!("Autorul cărții {title}"),
+            date_of_birth: PartialDate::from_year(1900).unwrap(),
+            date_of_death: None,
+            description: String::new(),
+        };
+        let author_id = self.store.add_author(&author).await.unwrap();
+
+        let details = BookDetails {
+            title: title.into(),
+            author_id,
+            publish_date: PartialDate::from_year(1950).unwrap(),
+            publisher: "Polirom".into(),
+            count,
+            synopsis: String::new(),
+            language: "ro".into(),
+            series: None,
+            genres: vec![],
+        };
+        let outcome = self.store.save_book(None, &details).await.unwrap();
+        let SaveOutcome::Saved(book_id) = outcome else {
+            panic!("the book wasn't added: {outcome:?}");
+        };
+
+        book_id
+    }
+
+    /// Borrows a book for `cookie`, returning the id of the borrow
+    async fn borrow(&self, cookie: &Cookie, book_id: BookId) -> BorrowId {
+        let request = BorrowRequest {
+            cookie: cookie.clone(),
+            book_id,
+        };
+        let reply: BorrowReply = self.call(&request).await.ok();
+        assert!(!reply.already_borrowed && !reply.no_copies_left);
+
+        self.borrowed_by(cookie.id)
+            .await
+            .iter()
+            .find(|borrowed| borrowed.book_id == book_id)
+            .unwrap()
+            .borrow_id
+    }
+
+    async fn borrowed_by(&self, user_id: UserId) -> BorrowedByReply {
+        self.call(&BorrowedBy { user_id }).await.ok()
+    }
+
+    /// Serves the router on a local port, for clients that need a real connection, returning
+    /// its URL
+    async fn listen(&self) -> String {
+        serve(self.app.clone()).await
+    }
+}
+
+/// Serves `app` on a free local port until the test ends, returning its URL
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    format!("http://{address}")
+}
+
+impl Deref for TestServer {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[tokio::test]
+async fn unknown_routes_are_not_found() {
+    let server = TestServer::new().await;
+
+    let reply = server.get("/nowhere").await;
+    assert_eq!(
+        reply.error(StatusCode::NOT_FOUND),
+        "The resource was not found"
+    );
+}
+
+#[tokio::test]
+async fn media_is_served() {
+    let server = TestServer::new().await;
+    fs::write(server.dir.join("media/cover.txt"), "copertă").unwrap();
+
+    let reply = server.get("/media/cover.txt").await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.body, "copertă");
+
+    assert_eq!(
+        server.get("/media/missing.txt").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
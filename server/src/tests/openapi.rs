@@ -0,0 +1,28 @@
+use std::{env, fs};
+
+use serde_json::Value;
+
+use super::TestServer;
+
+const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+/// Fails when a route or a `bd-schema` type changes without `openapi.json` being regenerated, which
+/// is done by running the test with `BIBLIOTECA_UPDATE_OPENAPI=1`
+#[tokio::test]
+async fn served_spec_matches_the_snapshot() {
+    let server = TestServer::new().await;
+    let served: Value = server.get("/openapi.json").await.ok();
+
+    if env::var_os("BIBLIOTECA_UPDATE_OPENAPI").is_some() {
+        let pretty = serde_json::to_string_pretty(&served).unwrap();
+        fs::write(SNAPSHOT, pretty + "\n").unwrap();
+        return;
+    }
+
+    let snapshot: Value = serde_json::from_str(&fs::read_to_string(SNAPSHOT).unwrap()).unwrap();
+    assert!(
+        served == snapshot,
+        "/openapi.json no longer matches openapi.json, run the tests with \
+         BIBLIOTECA_UPDATE_OPENAPI=1 to update it if the change is intended"
+    );
+}
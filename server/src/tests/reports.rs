@@ -0,0 +1,150 @@
+use axum::http::StatusCode;
+use schema::{
+    dates::Timestamp,
+    errors::ErrorCode,
+    ids::UserId,
+    reports::{
+        AuthorPopularityReply, LoanStatusReply, MostBorrowedReply, NeverBorrowedReply,
+        RegistrationsReply, ReportRequest, UtilisationReply,
+    },
+    session::Cookie,
+};
+
+use super::TestServer;
+
+const REPORTS: [&str; 6] = [
+    "most-borrowed",
+    "never-borrowed",
+    "loans",
+    "author-popularity",
+    "registrations",
+    "utilisation",
+];
+
+fn report(cookie: &Cookie) -> ReportRequest {
+    ReportRequest {
+        from: None,
+        to: None,
+        cookie: cookie.clone(),
+    }
+}
+
+#[tokio::test]
+async fn reports_follow_the_borrow_history() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let borrowed = server.book("Ultima noapte de dragoste", 2).await;
+    let never_borrowed = server.book("Patul lui Procust", 1).await;
+    server.borrow(&reader, borrowed).await;
+
+    let rows: MostBorrowedReply = server
+        .post("/reports/most-borrowed", report(&librarian))
+        .await
+        .ok();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].book_id, borrowed);
+    assert_eq!(rows[0].times_borrowed, 1);
+
+    let rows: NeverBorrowedReply = server
+        .post("/reports/never-borrowed", report(&librarian))
+        .await
+        .ok();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].book_id, never_borrowed);
+
+    let loans: LoanStatusReply = server.post("/reports/loans", report(&librarian)).await.ok();
+    assert_eq!(loans.active, 1);
+    assert_eq!(loans.overdue, 0);
+
+    let rows: AuthorPopularityReply = server
+        .post("/reports/author-popularity", report(&librarian))
+        .await
+        .ok();
+    assert_eq!(rows[0].name, "Autorul cărții Ultima noapte de dragoste");
+    assert_eq!(rows[0].distinct_readers, 1);
+
+    let rows: RegistrationsReply = server
+        .post("/reports/registrations", report(&librarian))
+        .await
+        .ok();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].day, "1970-01-01");
+    assert_eq!(rows[0].registrations, 2);
+
+    let rows: UtilisationReply = server
+        .post("/reports/utilisation", report(&librarian))
+        .await
+        .ok();
+    let row = rows.iter().find(|row| row.book_id == borrowed).unwrap();
+    assert_eq!(row.borrowed, 1);
+    assert_eq!(row.utilisation, 0.5);
+}
+
+#[tokio::test]
+async fn reports_are_exported_as_csv() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    server.book("Patul lui Procust", 1).await;
+
+    let reply = server
+        .post("/reports/never-borrowed?format=csv", report(&librarian))
+        .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(
+        reply.content_type.as_deref(),
+        Some("text/csv; charset=utf-8")
+    );
+
+    let mut lines = reply.body.lines();
+    assert_eq!(lines.next(), Some("book_id,title,author_name,count"));
+    assert_eq!(
+        lines.next(),
+        Some("1,Patul lui Procust,Autorul cărții Patul lui Procust,1")
+    );
+}
+
+#[tokio::test]
+async fn reports_are_only_for_librarians() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+    let reader = server.reader("ana@example.com").await;
+    let stranger = Cookie {
+        id: UserId(1000),
+        password: String::new(),
+    };
+
+    for name in REPORTS {
+        let uri = format!("/reports/{name}");
+        server
+            .post(&uri, report(&reader))
+            .await
+            .error(StatusCode::FORBIDDEN);
+        server
+            .post(&uri, report(&stranger))
+            .await
+            .error(StatusCode::UNAUTHORIZED);
+
+        let request = ReportRequest {
+            from: Timestamp::from_unix(10),
+            to: Timestamp::from_unix(0),
+            ..report(&librarian)
+        };
+        server
+            .post(&uri, request)
+            .await
+            .error(StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn unknown_reports_are_not_found() {
+    let server = TestServer::new().await;
+    let librarian = server.librarian().await;
+
+    let reply = server.post("/reports/nothing", report(&librarian)).await;
+    assert_eq!(
+        reply.error_code(StatusCode::NOT_FOUND),
+        ErrorCode::NoSuchReport
+    );
+}
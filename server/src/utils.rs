@@ -1,22 +1,10 @@
-use axum::http::StatusCode;
-use schema::{session, LIBRARIAN};
-use sqlx::SqlitePool;
+use schema::session;
 
-use crate::error::{IntoRouteError, RouteError};
+use crate::{error::RouteError, service::users::require_librarian, store::Store};
 
 pub async fn verify_user_is_librarian(
-    pool: &SqlitePool,
+    store: &dyn Store,
     cookie: session::Cookie,
 ) -> Result<(), RouteError> {
-    let requester_id = cookie.id;
-    let requester_type = sqlx::query!("SELECT type FROM Users WHERE user_id = ?", requester_id)
-        .fetch_one(pool)
-        .await
-        .http_status_error(StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    if requester_type.r#type != LIBRARIAN {
-        return Err(RouteError::new_forbidden());
-    }
-
-    Ok(())
+    Ok(require_librarian(store, cookie.id).await?)
 }
@@ -0,0 +1,48 @@
+//! Refuses requests whose body breaks the rules in its [`Validate`] implementation.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use schema::{
+    errors::{ErrorCode, FieldError},
+    validation::Validate,
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::RouteError;
+
+/// Like [`Json`], but the body is validated before the handler gets it
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        value
+            .validated()
+            .map_err(|details| invalid_fields(details).into_response())?;
+
+        Ok(Self(value))
+    }
+}
+
+/// Refuses a request that breaks the rules described by `details`
+pub fn invalid_fields(details: Vec<FieldError>) -> RouteError {
+    RouteError::from_status(StatusCode::UNPROCESSABLE_ENTITY)
+        .set_public_error_message("Some fields are invalid")
+        .set_error_code(ErrorCode::InvalidFields)
+        .set_details(details)
+}